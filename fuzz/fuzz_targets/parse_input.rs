@@ -0,0 +1,9 @@
+// cargo +nightly fuzz run parse_input
+
+#![no_main]
+
+libfuzzer_sys::fuzz_target!(|data: &[u8]| {
+    let events = snake::input::parse_input(data);
+    // Every event takes at least a byte.
+    assert!(events.len() <= data.len());
+});
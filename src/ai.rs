@@ -0,0 +1,240 @@
+// Computer players. A `Controller` looks at the game before every tick and
+// decides which way its snake should go, the same as a player pressing a key,
+// so it can steer any snake in any mode.
+
+use crate::game::{Coordinate, Game, MoveDirection};
+use crate::level::TerrainKind;
+use crate::random::Rng;
+
+const DIRECTIONS: [MoveDirection; 4] = [
+    MoveDirection::Up,
+    MoveDirection::Down,
+    MoveDirection::Left,
+    MoveDirection::Right,
+];
+
+pub trait Controller {
+    // The way `player` should turn before the next tick, if at all.
+    fn steer(&mut self, game: &Game, player: usize) -> Option<MoveDirection>;
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    // What beating it is worth, see `ratings`.
+    pub fn rating(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 800,
+            Difficulty::Medium => 1200,
+            Difficulty::Hard => 1600,
+        }
+    }
+
+    // Steering `player` in `game`, with random numbers from its seed so the
+    // computer plays the same every time it's given it.
+    pub fn controller(&self, game: &Game, player: usize) -> Box<dyn Controller> {
+        match self {
+            Difficulty::Easy => Box::new(Wanderer(game.stream(player as u64))),
+            Difficulty::Medium => Box::new(Greedy),
+            Difficulty::Hard => Box::new(Pathfinder),
+        }
+    }
+}
+
+// Who to play against in a versus game with the computer.
+pub enum Opponent {
+    Bot(Difficulty),
+    #[cfg(feature = "neural")]
+    Net(std::rc::Rc<crate::neural::Net>),
+}
+
+impl Opponent {
+    // What beating it is worth, if anything. A net is as good as it was
+    // trained to be, so there's no telling.
+    pub fn rating(&self) -> Option<u32> {
+        match self {
+            Opponent::Bot(difficulty) => Some(difficulty.rating()),
+            #[cfg(feature = "neural")]
+            Opponent::Net(_) => None,
+        }
+    }
+
+    pub fn controller(&self, game: &Game, player: usize) -> Box<dyn Controller> {
+        match self {
+            Opponent::Bot(difficulty) => difficulty.controller(game, player),
+            #[cfg(feature = "neural")]
+            Opponent::Net(net) => Box::new(crate::neural::Driver(net.clone())),
+        }
+    }
+}
+
+// Easy: goes straight, turning at random now and then and when it would crash
+// otherwise. Doesn't care about food.
+struct Wanderer(Box<dyn Rng>);
+
+impl Controller for Wanderer {
+    fn steer(&mut self, game: &Game, player: usize) -> Option<MoveDirection> {
+        let current = game.players[player].move_direction;
+        let safe = safe_directions(game, player);
+
+        if safe.contains(&current) && self.0.below(10) < 9 {
+            return None;
+        }
+        match safe.as_slice() {
+            [] => None,
+            safe => Some(safe[self.0.below(safe.len() as u32) as usize]),
+        }
+    }
+}
+
+// Medium: heads for the nearest food as the crow flies, as long as that
+// doesn't mean crashing straight away.
+struct Greedy;
+
+impl Controller for Greedy {
+    fn steer(&mut self, game: &Game, player: usize) -> Option<MoveDirection> {
+        let food = game.players[player].nearest_food(&game.food)?;
+
+        safe_directions(game, player)
+            .into_iter()
+            .min_by_key(|d| distance(step(game, game.players[player].head(), *d), food))
+    }
+}
+
+// Hard: finds the shortest way to the nearest food with A*, and only takes it
+// if there's still room to get out again afterwards. Otherwise it goes
+// wherever there's the most room, to wait for its tail to clear a way.
+struct Pathfinder;
+
+impl Controller for Pathfinder {
+    fn steer(&mut self, game: &Game, player: usize) -> Option<MoveDirection> {
+        let length = game.players[player].segments.len();
+        let head = game.players[player].head();
+        let safe = safe_directions(game, player);
+
+        let food = game.players[player].nearest_food(&game.food);
+        if let Some(first) = food.and_then(|f| first_step(game, player, f)) {
+            if room(game, step(game, head, first), first) > length {
+                return Some(first);
+            }
+        }
+
+        safe.into_iter()
+            .max_by_key(|d| room(game, step(game, head, *d), *d))
+    }
+}
+
+fn distance(a: Coordinate, b: Coordinate) -> u16 {
+    a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+}
+
+// The cell a snake at `from` going `direction` ends up on, through portals.
+fn step(game: &Game, from: Coordinate, direction: MoveDirection) -> Coordinate {
+    let next = game.board().step(from, direction);
+    game.level.portal_exit(&next).unwrap_or(next)
+}
+
+// Whether moving onto `cell` going `direction` would crash, as far as can be told before the
+// other snakes move.
+fn blocked(game: &Game, cell: Coordinate, direction: MoveDirection) -> bool {
+    game.level.is_wall(&cell)
+        || !game.level.gate_allows(&cell, direction)
+        || game.level.terrain_at(&cell) == Some(TerrainKind::Water)
+        || game
+            .players
+            .iter()
+            .any(|p| p.check_collisions(&cell, &game.board_size))
+}
+
+fn safe_directions(game: &Game, player: usize) -> Vec<MoveDirection> {
+    let snake = &game.players[player];
+    DIRECTIONS
+        .into_iter()
+        .filter(|d| *d != snake.move_direction.opposite())
+        .filter(|d| !blocked(game, step(game, snake.head(), *d), *d))
+        .collect()
+}
+
+// The most room `player` has whichever way it goes next.
+pub fn space(game: &Game, player: usize) -> usize {
+    let head = game.players[player].head();
+    safe_directions(game, player)
+        .into_iter()
+        .map(|d| room(game, step(game, head, d), d))
+        .max()
+        .unwrap_or(0)
+}
+
+// How many free cells can be reached from `from`, gone onto going
+// `direction`, counting it.
+fn room(game: &Game, from: Coordinate, direction: MoveDirection) -> usize {
+    if blocked(game, from, direction) {
+        return 0;
+    }
+
+    let mut seen = std::collections::HashSet::from([from]);
+    let mut queue = vec![from];
+    while let Some(cell) = queue.pop() {
+        for direction in DIRECTIONS {
+            let next = step(game, cell, direction);
+            if !blocked(game, next, direction) && seen.insert(next) {
+                queue.push(next);
+            }
+        }
+    }
+    seen.len()
+}
+
+// The first move of the shortest way from `player`'s head to `goal`.
+fn first_step(game: &Game, player: usize, goal: Coordinate) -> Option<MoveDirection> {
+    use std::cmp::Reverse;
+
+    let head = game.players[player].head();
+    // Every cell reached, with the first move of the best way there found yet
+    // and how long it is.
+    let mut best: std::collections::HashMap<Coordinate, (MoveDirection, u16)> =
+        std::collections::HashMap::new();
+    let mut open = std::collections::BinaryHeap::new();
+
+    for direction in safe_directions(game, player) {
+        let next = step(game, head, direction);
+        best.insert(next, (direction, 1));
+        open.push(Reverse((1 + distance(next, goal), next.0, next.1)));
+    }
+
+    while let Some(Reverse((_, x, y))) = open.pop() {
+        let cell = Coordinate(x, y);
+        let (first, length) = best[&cell];
+        if cell == goal {
+            return Some(first);
+        }
+
+        for direction in DIRECTIONS {
+            let next = step(game, cell, direction);
+            if blocked(game, next, direction) || next == head {
+                continue;
+            }
+            if best.get(&next).is_none_or(|(_, l)| length + 1 < *l) {
+                best.insert(next, (first, length + 1));
+                open.push(Reverse((length + 1 + distance(next, goal), next.0, next.1)));
+            }
+        }
+    }
+
+    None
+}
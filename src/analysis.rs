@@ -0,0 +1,251 @@
+// What happened in a game, noted down every tick while it's played, and the
+// page that sums it up after: how fast food was eaten over the game, the
+// longest it went without crashing, how often a snake was about to crash and
+// turned just in time, and where the crash that ended it was set up. Being
+// boxed in counts from the tick a snake no longer had as much room as it is
+// long, the same as the hard computer player goes by.
+//
+// Every game also leaves a summary in the runs directory, for dashboards,
+// the last MAX_RUNS of them:
+//
+//   {"date": 1760000000, "seed": 42, "mode": "classic", "duration": 63.2,
+//    "ticks": 421, "score": 12, "inputs": 57, "cause": "wall"}
+//
+// The cause is what the snake that died crashed into, wall, edge, water, gate,
+// self, snake or head_on, or null if none did.
+
+use crate::game::{Coordinate, Event, Game};
+use crate::l10n::{fill, tr};
+use serde::Serialize;
+
+const SPARKLINE: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const SPARKLINE_WIDTH: u32 = 30;
+// Shorter ones are mostly noise.
+const MIN_BUCKET: std::time::Duration = std::time::Duration::from_secs(5);
+const MAX_RUNS: usize = 100;
+
+pub struct Metrics {
+    elapsed: std::time::Duration,
+    // When each bit of food was eaten and each snake crashed, in game time.
+    eaten: Vec<std::time::Duration>,
+    crashes: Vec<std::time::Duration>,
+    near_misses: u32,
+    // Whether each snake was heading into something after the last tick.
+    danger: Vec<bool>,
+    // The tick and time each snake has been boxed in since, if it still is.
+    boxed: Vec<Option<(u32, std::time::Duration)>>,
+    // The tick and time the crash that ended the game was set up, and whether
+    // that was by boxing itself in.
+    mistake: Option<(u32, std::time::Duration, bool)>,
+    cause: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+struct Run {
+    date: u64,
+    seed: u64,
+    mode: &'static str,
+    duration: f64,
+    ticks: u32,
+    score: u32,
+    inputs: usize,
+    cause: Option<&'static str>,
+}
+
+impl Metrics {
+    pub fn new(game: &Game) -> Self {
+        Metrics {
+            elapsed: std::time::Duration::ZERO,
+            eaten: Vec::new(),
+            crashes: Vec::new(),
+            near_misses: 0,
+            danger: (0..game.players.len())
+                .map(|i| game.crash_ahead(i))
+                .collect(),
+            boxed: vec![None; game.players.len()],
+            mistake: None,
+            cause: None,
+        }
+    }
+
+    // After every tick, with where the heads were before it and how long it
+    // took.
+    pub fn tick(&mut self, game: &Game, heads: &[Coordinate], duration: std::time::Duration) {
+        self.elapsed += duration;
+        let mut crashed = vec![false; game.players.len()];
+
+        for event in &game.events {
+            match event {
+                Event::FoodEaten { .. } => self.eaten.push(self.elapsed),
+                Event::LostLife { player } => {
+                    self.crashes.push(self.elapsed);
+                    crashed[*player] = true;
+                    self.boxed[*player] = None;
+                }
+                Event::Died { player } => {
+                    self.crashes.push(self.elapsed);
+                    crashed[*player] = true;
+                    let (tick, time) = self.boxed[*player].unwrap_or((game.tick, self.elapsed));
+                    self.mistake = Some((tick, time, self.boxed[*player].is_some()));
+                    self.cause = Some(cause(game, heads, *player));
+                }
+                Event::Bumped { player } => crashed[*player] = true,
+                _ => {}
+            }
+        }
+
+        for (index, player) in game.players.iter().enumerate() {
+            // Waiting out a grace tick isn't getting away yet.
+            let moved = player.head() != heads[index];
+            if self.danger[index] && moved && !crashed[index] {
+                self.near_misses += 1;
+            }
+            if !player.alive {
+                continue;
+            }
+            self.danger[index] = game.crash_ahead(index);
+            self.boxed[index] = match crate::ai::space(game, index) < player.segments.len() {
+                true => self.boxed[index].or(Some((game.tick, self.elapsed))),
+                false => None,
+            };
+        }
+    }
+
+    // Food eaten a minute over the game, a bar for every stretch of it.
+    fn food_rates(&self) -> Vec<f64> {
+        let bucket = (self.elapsed / SPARKLINE_WIDTH).max(MIN_BUCKET);
+        let buckets = self.elapsed.as_secs_f64() / bucket.as_secs_f64();
+        let mut counts = vec![0; (buckets.ceil() as usize).max(1)];
+        let last = counts.len() - 1;
+        for time in &self.eaten {
+            let index = (time.as_secs_f64() / bucket.as_secs_f64()) as usize;
+            counts[index.min(last)] += 1;
+        }
+        counts
+            .into_iter()
+            .map(|count| f64::from(count) * 60.0 / bucket.as_secs_f64())
+            .collect()
+    }
+
+    fn longest_streak(&self) -> std::time::Duration {
+        let mut since = std::time::Duration::ZERO;
+        let mut longest = std::time::Duration::ZERO;
+        for time in self.crashes.iter().chain([&self.elapsed]) {
+            longest = longest.max(*time - since);
+            since = *time;
+        }
+        longest
+    }
+}
+
+// What `player` crashed into, going on from `heads`.
+fn cause(game: &Game, heads: &[Coordinate], player: usize) -> &'static str {
+    let next = game
+        .board()
+        .step(heads[player], game.players[player].move_direction);
+    let next = game.level.portal_exit(&next).unwrap_or(next);
+    if !next.on_board(game.board_size) {
+        "edge"
+    } else if game.level.is_wall(&next) {
+        "wall"
+    } else if game.level.terrain_at(&next) == Some(crate::level::TerrainKind::Water)
+        && game.players[player].bridging == 0
+    {
+        "water"
+    } else if !game
+        .level
+        .gate_allows(&next, game.players[player].move_direction)
+    {
+        "gate"
+    } else if game.players[player].segments.contains(&next) {
+        "self"
+    } else if game.players.iter().any(|p| p.segments.contains(&next)) {
+        "snake"
+    } else {
+        "head_on"
+    }
+}
+
+// Writes down how `game` went in the runs directory, and forgets the oldest
+// runs past MAX_RUNS.
+pub fn save(
+    metrics: &Metrics,
+    game: &Game,
+    replay: &crate::replay::Replay,
+) -> Result<std::path::PathBuf, std::io::Error> {
+    let date = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let run = Run {
+        date,
+        seed: game.seed,
+        mode: game.mode.name(),
+        duration: metrics.elapsed.as_secs_f64(),
+        ticks: game.tick,
+        score: game.players.iter().map(|p| p.score).sum(),
+        inputs: replay.inputs.len(),
+        cause: metrics.cause,
+    };
+
+    let dir = crate::paths::runs_dir();
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("run-{}-{}.json", date, game.seed));
+    std::fs::write(&path, serde_json::to_string_pretty(&run)?)?;
+
+    // The dates in the names sort them oldest first.
+    let mut runs: Vec<std::path::PathBuf> = std::fs::read_dir(&dir)?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|e| e == "json"))
+        .collect();
+    runs.sort();
+    for old in &runs[..runs.len().saturating_sub(MAX_RUNS)] {
+        std::fs::remove_file(old)?;
+    }
+    Ok(path)
+}
+
+fn sparkline(values: &[f64]) -> String {
+    let most = values.iter().copied().fold(0.0, f64::max);
+    values
+        .iter()
+        .map(|value| match most > 0.0 {
+            true => SPARKLINE[(value / most * 7.0).round() as usize],
+            false => SPARKLINE[0],
+        })
+        .collect()
+}
+
+fn clock(time: std::time::Duration) -> String {
+    let seconds = time.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+pub fn show(screen: &mut dyn crate::term::Terminal, metrics: &Metrics) {
+    let rates = metrics.food_rates();
+    let most = rates.iter().copied().fold(0.0, f64::max);
+    let mistake = match metrics.mistake {
+        Some((tick, time, true)) => fill(
+            "Boxed in on tick {}, {} before the end",
+            &[&tick, &clock(metrics.elapsed - time)],
+        ),
+        Some((tick, _, false)) => fill("Crashed with room to spare on tick {}", &[&tick]),
+        None => tr("Nothing went fatally wrong").to_string(),
+    };
+
+    crate::message(
+        screen,
+        &[
+            tr("How it went"),
+            "",
+            &fill("Food a minute, at most {}", &[&most.round()]),
+            &sparkline(&rates),
+            "",
+            &fill(
+                "Longest without crashing: {}",
+                &[&clock(metrics.longest_streak())],
+            ),
+            &fill("Near misses: {}", &[&metrics.near_misses]),
+            &mistake,
+        ],
+    );
+}
@@ -0,0 +1,240 @@
+// Sound without a sound library. Samples are made here, a square wave lead
+// over a triangle bass like an old console would and short tones for cues on
+// top, and played by whichever command line player is installed, fed raw 16
+// bit stereo through a pipe. No player means no sound, which is never worth an
+// error.
+
+const SAMPLE_RATE: u32 = 22050;
+
+// Tried in order.
+const PLAYERS: &[(&str, &[&str])] = &[
+    (
+        "pw-play",
+        &["--rate=22050", "--channels=2", "--format=s16", "-"],
+    ),
+    (
+        "paplay",
+        &["--raw", "--rate=22050", "--channels=2", "--format=s16le"],
+    ),
+    (
+        "aplay",
+        &["-q", "-t", "raw", "-r", "22050", "-c", "2", "-f", "S16_LE"],
+    ),
+];
+
+// Samples are made a little ahead of being played, no more, so changes are
+// heard straight away instead of after whatever the pipe has buffered.
+const CHUNK: std::time::Duration = std::time::Duration::from_millis(20);
+const AHEAD: std::time::Duration = std::time::Duration::from_millis(100);
+
+pub const MAX_VOLUME: u8 = 10;
+
+// The loop, in eighth notes at `TEMPO` as MIDI note numbers, 0 is a rest.
+const TEMPO: f32 = 140.0;
+const MELODY: [u8; 32] = [
+    76, 0, 79, 76, 74, 72, 74, 0, 76, 0, 72, 69, 71, 72, 74, 0, //
+    76, 0, 79, 81, 79, 76, 74, 72, 74, 76, 74, 71, 72, 0, 0, 0,
+];
+const BASS: [u8; 32] = [
+    45, 45, 52, 45, 43, 43, 50, 43, 41, 41, 48, 41, 43, 43, 50, 43, //
+    45, 45, 52, 45, 43, 43, 50, 43, 41, 41, 48, 41, 40, 40, 47, 40,
+];
+
+// A short beep over the music.
+#[derive(Copy, Clone)]
+pub struct Cue {
+    pub frequency: f32,
+    pub duration: std::time::Duration,
+    // From -1 for all the way left to 1 for all the way right.
+    pub pan: f32,
+}
+
+struct Settings {
+    // How much faster than `TEMPO` to play.
+    speed: f32,
+    volume: u8,
+    music: bool,
+    // Waiting to be mixed in.
+    cues: Vec<Cue>,
+    stopped: bool,
+}
+
+// Plays the loop, if `music` is on, and cues until dropped.
+pub struct Sound {
+    settings: std::sync::Arc<std::sync::Mutex<Settings>>,
+    player: std::process::Child,
+}
+
+impl Sound {
+    pub fn start(volume: u8, music: bool) -> Option<Self> {
+        let mut player = PLAYERS.iter().find_map(|(command, args)| {
+            std::process::Command::new(command)
+                .args(*args)
+                .stdin(std::process::Stdio::piped())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .spawn()
+                .ok()
+        })?;
+        let mut pipe = player.stdin.take()?;
+
+        let settings = std::sync::Arc::new(std::sync::Mutex::new(Settings {
+            speed: 1.0,
+            volume: volume.min(MAX_VOLUME),
+            music,
+            cues: Vec::new(),
+            stopped: false,
+        }));
+
+        let shared = std::sync::Arc::clone(&settings);
+        std::thread::spawn(move || {
+            use std::io::Write;
+
+            let mut synth = Synth::default();
+            let mut tones: Vec<Tone> = Vec::new();
+            let started = std::time::Instant::now();
+            let mut written = 0u64;
+            loop {
+                let (speed, volume, music) = {
+                    let mut settings = shared.lock().unwrap();
+                    if settings.stopped {
+                        return;
+                    }
+                    tones.extend(settings.cues.drain(..).map(Tone::new));
+                    (settings.speed, settings.volume, settings.music)
+                };
+
+                let samples = SAMPLE_RATE as u64 * CHUNK.as_millis() as u64 / 1000;
+                let mut bytes = Vec::with_capacity(samples as usize * 4);
+                for _ in 0..samples {
+                    let mut left = 0.0;
+                    let mut right = 0.0;
+                    if music {
+                        let sample = synth.next(speed);
+                        left += sample;
+                        right += sample;
+                    }
+                    for tone in &mut tones {
+                        let (l, r) = tone.next();
+                        left += l;
+                        right += r;
+                    }
+
+                    for sample in [left, right] {
+                        let sample = (sample * volume as f32 / MAX_VOLUME as f32).clamp(-1.0, 1.0);
+                        bytes.extend_from_slice(&((sample * i16::MAX as f32) as i16).to_le_bytes());
+                    }
+                }
+                tones.retain(|t| t.remaining > 0);
+                if pipe.write_all(&bytes).is_err() {
+                    return;
+                }
+                written += samples;
+
+                let played_until =
+                    std::time::Duration::from_secs_f64(written as f64 / SAMPLE_RATE as f64);
+                if let Some(ahead) = played_until.checked_sub(started.elapsed()) {
+                    std::thread::sleep(ahead.saturating_sub(AHEAD));
+                }
+            }
+        });
+
+        Some(Sound { settings, player })
+    }
+
+    pub fn set_music(&self, music: bool) {
+        self.settings.lock().unwrap().music = music;
+    }
+
+    pub fn cue(&self, cue: Cue) {
+        self.settings.lock().unwrap().cues.push(cue);
+    }
+
+    // The game moving `speed` times as fast as usual.
+    pub fn set_speed(&self, speed: f32) {
+        self.settings.lock().unwrap().speed = speed;
+    }
+
+    pub fn set_volume(&self, volume: u8) {
+        self.settings.lock().unwrap().volume = volume.min(MAX_VOLUME);
+    }
+}
+
+impl Drop for Sound {
+    fn drop(&mut self) {
+        self.settings.lock().unwrap().stopped = true;
+        let _ = self.player.kill();
+        let _ = self.player.wait();
+    }
+}
+
+#[derive(Default)]
+struct Synth {
+    // Position in the loop, in eighth notes.
+    position: f32,
+    lead_phase: f32,
+    bass_phase: f32,
+}
+
+impl Synth {
+    // The next sample, between -1 and 1.
+    fn next(&mut self, speed: f32) -> f32 {
+        let step = self.position as usize;
+        // Notes fade over their step so repeated ones can be told apart.
+        let fade = 1.0 - self.position.fract() * 0.7;
+
+        let mut sample = 0.0;
+        if let Some(frequency) = frequency(MELODY[step]) {
+            self.lead_phase = (self.lead_phase + frequency / SAMPLE_RATE as f32).fract();
+            sample += if self.lead_phase < 0.5 { 0.2 } else { -0.2 } * fade;
+        }
+        if let Some(frequency) = frequency(BASS[step]) {
+            self.bass_phase = (self.bass_phase + frequency / SAMPLE_RATE as f32).fract();
+            sample += (4.0 * (self.bass_phase - 0.5).abs() - 1.0) * 0.3;
+        }
+
+        let steps_per_second = TEMPO / 60.0 * 2.0 * speed;
+        self.position =
+            (self.position + steps_per_second / SAMPLE_RATE as f32) % MELODY.len() as f32;
+
+        sample
+    }
+}
+
+// A cue being played.
+struct Tone {
+    cue: Cue,
+    phase: f32,
+    // Samples left.
+    remaining: u32,
+}
+
+impl Tone {
+    fn new(cue: Cue) -> Self {
+        Tone {
+            cue,
+            phase: 0.0,
+            remaining: (cue.duration.as_secs_f32() * SAMPLE_RATE as f32) as u32,
+        }
+    }
+
+    // The next left and right samples, silent once it's over.
+    fn next(&mut self) -> (f32, f32) {
+        if self.remaining == 0 {
+            return (0.0, 0.0);
+        }
+        self.remaining -= 1;
+
+        self.phase = (self.phase + self.cue.frequency / SAMPLE_RATE as f32).fract();
+        let sample = (self.phase * std::f32::consts::TAU).sin() * 0.3;
+        let pan = self.cue.pan.clamp(-1.0, 1.0);
+        (sample * (1.0 - pan) / 2.0, sample * (1.0 + pan) / 2.0)
+    }
+}
+
+pub fn frequency(note: u8) -> Option<f32> {
+    match note {
+        0 => None,
+        note => Some(440.0 * 2f32.powf((note as f32 - 69.0) / 12.0)),
+    }
+}
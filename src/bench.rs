@@ -0,0 +1,103 @@
+// `snake bench [<thousands of ticks>]` plays games without a terminal, with
+// the medium computer player steering, and says how fast ticking, drawing and
+// steering went and how much each allocated, so changes can be measured
+// against each other. The games are seeded the same every run.
+
+use crate::game::{Game, Input, Mode};
+
+const DEFAULT_THOUSANDS: u64 = 100;
+const BOARD_SIZE: (u16, u16) = (40, 20);
+
+static ALLOCATIONS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+// The system allocator, counting every allocation.
+struct Counting;
+
+unsafe impl std::alloc::GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        unsafe { std::alloc::System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+        unsafe { std::alloc::System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: std::alloc::Layout, size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        unsafe { std::alloc::System.realloc(ptr, layout, size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+// Time spent and allocations made by one part of the loop.
+#[derive(Default)]
+struct Measure {
+    time: std::time::Duration,
+    allocations: u64,
+}
+
+impl Measure {
+    fn run<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        let allocations = ALLOCATIONS.load(std::sync::atomic::Ordering::Relaxed);
+        let started = std::time::Instant::now();
+        let result = f();
+        self.time += started.elapsed();
+        self.allocations += ALLOCATIONS.load(std::sync::atomic::Ordering::Relaxed) - allocations;
+        result
+    }
+
+    fn report(&self, name: &str, ticks: u64) {
+        println!(
+            "{:<8}{:>12.0} ticks/s {:>8.2} allocations/tick",
+            name,
+            ticks as f64 / self.time.as_secs_f64(),
+            self.allocations as f64 / ticks as f64
+        );
+    }
+}
+
+pub fn parse(thousands: Option<&str>) -> Option<u64> {
+    match thousands {
+        Some(thousands) => thousands.parse().ok().filter(|t| *t > 0),
+        None => Some(DEFAULT_THOUSANDS),
+    }
+}
+
+pub fn run(thousands: u64) {
+    let ticks = thousands * 1000;
+    let mut tick = Measure::default();
+    let mut render = Measure::default();
+    let mut steer = Measure::default();
+    let mut games = 1;
+    let mut game = Game::new(Mode::Classic, 0, BOARD_SIZE);
+    let mut bot = crate::ai::Difficulty::Medium.controller(&game, 0);
+    let mut screen = Vec::new();
+
+    let started = std::time::Instant::now();
+    for _ in 0..ticks {
+        if let Some(direction) = steer.run(|| bot.steer(&game, 0)) {
+            game.handle_input(0, Input::Turn(direction));
+        }
+        if tick.run(|| game.tick()) {
+            game = Game::new(Mode::Classic, games, BOARD_SIZE);
+            games += 1;
+        }
+        render.run(|| {
+            screen.clear();
+            crate::render_game(&mut screen, &game).unwrap();
+        });
+    }
+
+    println!(
+        "{} ticks of {} games in {:.2}s",
+        ticks,
+        games,
+        started.elapsed().as_secs_f64()
+    );
+    tick.report("tick", ticks);
+    render.report("render", ticks);
+    steer.report("steer", ticks);
+}
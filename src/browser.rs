@@ -0,0 +1,308 @@
+// The high scores screen: a tab for the scores kept here and, with the
+// `online` feature and a `leaderboard_url`, one for the online leaderboard's.
+// m picks the mode shown, d how far back, and enter watches the chosen
+// score's replay when there is one. Online scores are only fetched once their
+// tab is first looked at.
+
+use crate::game::Mode;
+use crate::l10n::{fill, tr};
+use crate::scores::{Entry, HighScores};
+
+const MODES: [Option<Mode>; 5] = [
+    None,
+    Some(Mode::Classic),
+    Some(Mode::Versus),
+    Some(Mode::Twins),
+    Some(Mode::Coop),
+];
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Since {
+    Ever,
+    Month,
+    Week,
+    Today,
+}
+
+impl Since {
+    fn next(self) -> Self {
+        match self {
+            Since::Ever => Since::Month,
+            Since::Month => Since::Week,
+            Since::Week => Since::Today,
+            Since::Today => Since::Ever,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Since::Ever => "All time",
+            Since::Month => "Last 30 days",
+            Since::Week => "Last 7 days",
+            Since::Today => "Today",
+        }
+    }
+
+    // The earliest time, in seconds since 1970, a score can be from to show.
+    fn start(self, now: u64) -> Option<u64> {
+        match self {
+            Since::Ever => None,
+            Since::Month => Some(now.saturating_sub(30 * 86_400)),
+            Since::Week => Some(now.saturating_sub(7 * 86_400)),
+            Since::Today => Some(now - now % 86_400),
+        }
+    }
+}
+
+enum Tab {
+    Local,
+    #[cfg(feature = "online")]
+    Online(String),
+}
+
+impl Tab {
+    fn label(&self) -> &'static str {
+        match self {
+            Tab::Local => "High scores",
+            #[cfg(feature = "online")]
+            Tab::Online(_) => "Online scores",
+        }
+    }
+
+    fn load(&self, high_scores: &HighScores) -> Result<Vec<Entry>, String> {
+        match self {
+            Tab::Local => Ok(high_scores.entries().to_vec()),
+            #[cfg(feature = "online")]
+            Tab::Online(url) => crate::leaderboard::fetch(url),
+        }
+    }
+
+    fn replay(&self, hash: &str) -> Result<crate::replay::Replay, String> {
+        match self {
+            Tab::Local => crate::replay::Replay::load(&crate::scores::replay_file(hash))
+                .map_err(|e| e.to_string()),
+            #[cfg(feature = "online")]
+            Tab::Online(url) => crate::leaderboard::fetch_replay(url, hash),
+        }
+    }
+}
+
+#[cfg(feature = "online")]
+fn online_tab(config: &crate::config::Config) -> Option<Tab> {
+    config.leaderboard_url.clone().map(Tab::Online)
+}
+
+#[cfg(not(feature = "online"))]
+fn online_tab(_: &crate::config::Config) -> Option<Tab> {
+    None
+}
+
+fn loading(screen: &mut dyn crate::term::Terminal, text: &str) -> Result<(), std::io::Error> {
+    let screen_size = screen.size();
+    crate::clear(screen)?;
+    crate::ui::write_centered(screen, &screen_size, screen_size.1 / 2, text)?;
+    screen.flush()
+}
+
+// Opens on the online tab with `online`, when there is one.
+pub fn browse(
+    screen: &mut dyn crate::term::Terminal,
+    config: &crate::config::Config,
+    high_scores: &HighScores,
+    online: bool,
+) {
+    let tabs: Vec<Tab> = std::iter::once(Tab::Local)
+        .chain(online_tab(config))
+        .collect();
+    let mut loaded: Vec<Option<Result<Vec<Entry>, String>>> = tabs.iter().map(|_| None).collect();
+
+    let mut tab = if online { tabs.len() - 1 } else { 0 };
+    let mut mode = 0;
+    let mut since = Since::Ever;
+    let mut selected = 0;
+    let mut scroll = 0;
+
+    screen.read_input();
+    loop {
+        if loaded[tab].is_none() {
+            loading(screen, tr("Loading scores...")).unwrap();
+            loaded[tab] = Some(tabs[tab].load(high_scores));
+        }
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let entries: Vec<&Entry> = match &loaded[tab] {
+            Some(Ok(entries)) => entries
+                .iter()
+                .filter(|e| MODES[mode].is_none_or(|mode| e.mode == mode))
+                .filter(|e| match since.start(now) {
+                    Some(start) => e.date.is_some_and(|date| date >= start),
+                    None => true,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        selected = selected.min(entries.len().saturating_sub(1));
+
+        let shown = (tab, mode, since);
+        for b in screen.read_input() {
+            match b {
+                // q
+                113 => return,
+                // Tab, h and l
+                9 | 104 | 108 => {
+                    tab = match b {
+                        104 => (tab + tabs.len() - 1) % tabs.len(),
+                        _ => (tab + 1) % tabs.len(),
+                    };
+                    selected = 0;
+                }
+                // j and k
+                106 => selected = (selected + 1).min(entries.len().saturating_sub(1)),
+                107 => selected = selected.saturating_sub(1),
+                // m
+                109 => {
+                    mode = (mode + 1) % MODES.len();
+                    selected = 0;
+                }
+                // d
+                100 => {
+                    since = since.next();
+                    selected = 0;
+                }
+                // Enter
+                13 => {
+                    if let Some(hash) = entries.get(selected).and_then(|e| e.replay_hash.clone()) {
+                        watch(screen, config, &tabs[tab], &hash);
+                        screen.read_input();
+                    }
+                }
+                _ => {}
+            }
+        }
+        if (tab, mode, since) != shown {
+            continue;
+        }
+
+        let screen_size = screen.size();
+        crate::clear(screen).unwrap();
+
+        let titles: Vec<String> = tabs
+            .iter()
+            .enumerate()
+            .map(|(index, t)| match index == tab {
+                true => format!("[{}]", tr(t.label())),
+                false => tr(t.label()).to_string(),
+            })
+            .collect();
+        crate::ui::write_centered(screen, &screen_size, 1, &titles.join("   ")).unwrap();
+        let mode_name = match MODES[mode] {
+            Some(mode) => mode_label(mode),
+            None => "All modes",
+        };
+        crate::ui::write_centered(
+            screen,
+            &screen_size,
+            2,
+            &fill("{} (m), {} (d)", &[&tr(mode_name), &tr(since.label())]),
+        )
+        .unwrap();
+
+        // The title and filters above, the header and a line of keys below.
+        let visible = (screen_size.1 as usize).saturating_sub(6).max(1);
+        if selected < scroll {
+            scroll = selected;
+        } else if selected >= scroll + visible {
+            scroll = selected + 1 - visible;
+        }
+
+        match &loaded[tab] {
+            Some(Err(e)) => {
+                crate::ui::write_centered(
+                    screen,
+                    &screen_size,
+                    4,
+                    tr("Could not load online scores"),
+                )
+                .unwrap();
+                crate::ui::write_centered(screen, &screen_size, 5, e).unwrap();
+            }
+            _ if entries.is_empty() => {
+                crate::ui::write_centered(screen, &screen_size, 4, tr("No scores yet")).unwrap();
+            }
+            _ => {
+                let rows: Vec<Vec<String>> = entries
+                    .iter()
+                    .enumerate()
+                    .skip(scroll)
+                    .take(visible)
+                    .map(|(rank, entry)| row(rank, entry))
+                    .collect();
+                crate::ui::render_table(
+                    screen,
+                    &screen_size,
+                    4,
+                    &["#", tr("Name"), tr("Score"), tr("Mode"), tr("Date"), ""],
+                    &rows,
+                    Some(selected - scroll),
+                )
+                .unwrap();
+            }
+        }
+
+        crate::ui::write_centered(
+            screen,
+            &screen_size,
+            screen_size.1,
+            tr("* has a replay, enter watches it, q goes back"),
+        )
+        .unwrap();
+        screen.flush().unwrap();
+
+        screen.wait_for_input(crate::IDLE_TIMEOUT);
+    }
+}
+
+fn mode_label(mode: Mode) -> &'static str {
+    match mode {
+        Mode::Classic => "Classic",
+        Mode::Versus => "Versus",
+        Mode::Twins => "Twins",
+        Mode::Coop => "Co-op",
+    }
+}
+
+fn row(rank: usize, entry: &Entry) -> Vec<String> {
+    let date = match entry.date {
+        Some(date) => {
+            let (year, month, day) = crate::theme::civil_from_days(date / 86_400);
+            format!("{}-{:02}-{:02}", year, month, day)
+        }
+        None => "-".to_string(),
+    };
+    vec![
+        format!("{}.", rank + 1),
+        entry.name.clone(),
+        entry.score.to_string(),
+        tr(mode_label(entry.mode)).to_string(),
+        date,
+        match entry.replay_hash {
+            Some(_) => "*".to_string(),
+            None => String::new(),
+        },
+    ]
+}
+
+fn watch(
+    screen: &mut dyn crate::term::Terminal,
+    config: &crate::config::Config,
+    tab: &Tab,
+    hash: &str,
+) {
+    loading(screen, tr("Loading replay...")).unwrap();
+    match (tab.replay(hash), crate::rules::load(config)) {
+        (Ok(replay), Ok(rules)) => crate::watch(screen, &replay, rules),
+        (Err(e), _) => crate::message(screen, &[tr("Could not load the replay"), &e]),
+        (_, Err(e)) => crate::message(screen, &[tr("Could not load the rules script"), &e]),
+    }
+}
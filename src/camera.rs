@@ -0,0 +1,140 @@
+// Which part of a board too big for the screen gets drawn. A camera either
+// follows a snake, keeping its head in the middle until that would show past
+// an edge, or stays where it was put. Drawing goes through the viewport the
+// camera picks, so everything in `render_game` and friends ends up in the
+// right place without knowing about it. With `reduced_motion` in the config a
+// followed snake's camera stays put until the head gets most of the way to
+// an edge and then jumps half the view, rather than scrolling every move.
+
+use crate::game::{Coordinate, Player};
+
+// `size` cells across and down from `origin`, drawn with their top left at
+// `corner` on the screen instead of the whole board from the top left. Cells
+// outside it aren't drawn at all.
+#[derive(Copy, Clone)]
+pub struct Viewport {
+    pub origin: Coordinate,
+    pub size: (u16, u16),
+    pub corner: (u16, u16),
+}
+
+// Set while anything but the whole board is being drawn.
+static VIEWPORT: std::sync::Mutex<Option<Viewport>> = std::sync::Mutex::new(None);
+
+pub fn viewport() -> Option<Viewport> {
+    *VIEWPORT.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn set_viewport(viewport: Option<Viewport>) {
+    *VIEWPORT.lock().unwrap_or_else(|e| e.into_inner()) = viewport;
+}
+
+// Whether cameras jump instead of scrolling, set from the config.
+static STEADY: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+pub fn set_steady(steady: bool) {
+    STEADY.store(steady, std::sync::atomic::Ordering::Relaxed);
+}
+
+// Where to start showing `shown` cells to keep `head` in the middle, or a
+// half of them, snapped to steps of half the view when the camera is steady.
+fn around(head: u16, shown: u16) -> u16 {
+    if !STEADY.load(std::sync::atomic::Ordering::Relaxed) {
+        return head.saturating_sub(shown / 2);
+    }
+    let step = (shown / 2).max(1);
+    let page = head.saturating_sub(1) / step * step;
+    (page + 1).saturating_sub(shown / 4)
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Target {
+    Snake(usize),
+    // The top left cell to show, moved around by hand.
+    Free(Coordinate),
+}
+
+pub struct Camera {
+    pub target: Target,
+}
+
+impl Camera {
+    pub fn following(snake: usize) -> Self {
+        Camera {
+            target: Target::Snake(snake),
+        }
+    }
+
+    // Tab follows the next snake, f lets go of it where it is and hjkl then
+    // move it a quarter of `size` at a time. Returns whether `key` was one of
+    // those.
+    pub fn handle_key(
+        &mut self,
+        key: u8,
+        players: &[Player],
+        board_size: (u16, u16),
+        size: (u16, u16),
+    ) -> bool {
+        let step = ((size.0 / 4).max(1), (size.1 / 4).max(1));
+        self.target = match (key, self.target) {
+            (9, Target::Snake(snake)) => Target::Snake((snake + 1) % players.len().max(1)),
+            (9, Target::Free(_)) => Target::Snake(0),
+            (102, Target::Snake(_)) => Target::Free(self.origin(players, board_size, size)),
+            (102, Target::Free(_)) => Target::Snake(0),
+            (104, Target::Free(Coordinate(x, y))) => {
+                Target::Free(Coordinate(x.saturating_sub(step.0), y))
+            }
+            (108, Target::Free(Coordinate(x, y))) => Target::Free(Coordinate(x + step.0, y)),
+            (107, Target::Free(Coordinate(x, y))) => {
+                Target::Free(Coordinate(x, y.saturating_sub(step.1)))
+            }
+            (106, Target::Free(Coordinate(x, y))) => Target::Free(Coordinate(x, y + step.1)),
+            _ => return false,
+        };
+        // Kept on the board, so moving back doesn't take longer than it went.
+        if let Target::Free(_) = self.target {
+            self.target = Target::Free(self.origin(players, board_size, size));
+        }
+        true
+    }
+
+    // The top left cell of the `size` cells to show.
+    pub fn origin(
+        &self,
+        players: &[Player],
+        board_size: (u16, u16),
+        size: (u16, u16),
+    ) -> Coordinate {
+        let clamp = |start: u16, shown: u16, board: u16| {
+            start.min((board + 1).saturating_sub(shown)).max(1)
+        };
+        let Coordinate(x, y) = match self.target {
+            Target::Snake(snake) => match players.get(snake) {
+                Some(player) => {
+                    let head = player.head();
+                    Coordinate(around(head.0, size.0), around(head.1, size.1))
+                }
+                None => Coordinate(1, 1),
+            },
+            Target::Free(origin) => origin,
+        };
+        Coordinate(
+            clamp(x, size.0, board_size.0),
+            clamp(y, size.1, board_size.1),
+        )
+    }
+
+    pub fn viewport(
+        &self,
+        players: &[Player],
+        board_size: (u16, u16),
+        size: (u16, u16),
+        corner: (u16, u16),
+    ) -> Viewport {
+        Viewport {
+            origin: self.origin(players, board_size, size),
+            size,
+            corner,
+        }
+    }
+}
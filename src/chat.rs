@@ -0,0 +1,78 @@
+// Talking during games over the network: t starts a line, enter sends it and
+// escape gives up on it. Keys go to the line while it's being typed, so the
+// snake keeps going the way it was. The last few lines said show above the
+// scores for a while.
+
+use crate::l10n::fill;
+use std::io::Write;
+
+const LENGTH: usize = 60;
+const SHOWN: usize = 3;
+const SHOWN_FOR: std::time::Duration = std::time::Duration::from_secs(8);
+
+fn printable(c: char) -> bool {
+    (' '..='~').contains(&c)
+}
+
+#[derive(Default)]
+pub struct Chat {
+    typing: Option<crate::ui::TextInput>,
+    // Who said what and when, oldest first.
+    said: std::collections::VecDeque<(String, String, std::time::Instant)>,
+}
+
+impl Chat {
+    // Takes `key` if it's for the chat, with a line to send when enter was
+    // pressed on one. Anything it doesn't take is for the game.
+    pub fn handle_key(&mut self, key: u8) -> Option<Option<String>> {
+        let Some(typing) = &mut self.typing else {
+            if key != b't' {
+                return None;
+            }
+            self.typing = Some(crate::ui::TextInput::new(1, LENGTH, printable));
+            return Some(None);
+        };
+        if key == 27 {
+            self.typing = None;
+            return Some(None);
+        }
+        let line = typing.handle_key(key)?;
+        self.typing = None;
+        Some(Some(line))
+    }
+
+    pub fn said(&mut self, who: &str, line: &str) {
+        if self.said.len() == SHOWN {
+            self.said.pop_front();
+        }
+        self.said
+            .push_back((who.to_string(), line.to_string(), std::time::Instant::now()));
+    }
+
+    // Above the scores, which are on row `hud`.
+    pub fn render(&self, screen: &mut dyn Write, hud: u16) -> Result<(), std::io::Error> {
+        let mut lines: Vec<String> = self
+            .said
+            .iter()
+            .filter(|(_, _, at)| at.elapsed() < SHOWN_FOR)
+            .map(|(who, line, _)| fill("{}: {}", &[&who, &line]))
+            .collect();
+        if self.typing.is_some() {
+            lines.push(String::new());
+        }
+
+        let top = hud.saturating_sub(lines.len() as u16).max(1);
+        for (y, line) in (top..).zip(&lines) {
+            write!(screen, "{} {} ", termion::cursor::Goto(2, y), line)?;
+        }
+        if let Some(typing) = &self.typing {
+            write!(
+                screen,
+                "{} > ",
+                termion::cursor::Goto(2, hud.saturating_sub(1).max(1))
+            )?;
+            typing.render(screen, (5, hud.saturating_sub(1).max(1)))?;
+        }
+        Ok(())
+    }
+}
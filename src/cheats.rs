@@ -0,0 +1,82 @@
+// Codes typed on the main menu, the way old games had them. Typing one turns
+// it on for the rest of the session and typing it again turns it off:
+//
+//   nokia    the retro theme, green like an old phone's screen
+//   python   the snake starts out much longer
+//
+// Games played with any on don't make the high scores or the leaderboard.
+
+use crate::game::{Game, Input};
+
+const CODES: [(&str, Cheat); 2] = [("nokia", Cheat::Nokia), ("python", Cheat::Python)];
+// How much longer python makes the snake.
+const PYTHON_CELLS: u32 = 16;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Cheat {
+    Nokia,
+    Python,
+}
+
+impl Cheat {
+    pub fn description(self) -> &'static str {
+        match self {
+            Cheat::Nokia => "Retro theme",
+            Cheat::Python => "Long snake",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Cheats {
+    // The last keys typed, as many as the longest code.
+    typed: std::collections::VecDeque<u8>,
+    on: Vec<Cheat>,
+}
+
+impl Cheats {
+    // Remembers `key`, and returns the cheat it finished typing, which has
+    // been turned on or off.
+    pub fn key(&mut self, key: u8) -> Option<Cheat> {
+        let longest = CODES.iter().map(|(code, _)| code.len()).max().unwrap_or(0);
+        if self.typed.len() == longest {
+            self.typed.pop_front();
+        }
+        self.typed.push_back(key.to_ascii_lowercase());
+
+        let typed = self.typed.make_contiguous();
+        let (_, cheat) = CODES
+            .iter()
+            .find(|(code, _)| typed.ends_with(code.as_bytes()))?;
+        self.typed.clear();
+        match self.on.iter().position(|c| c == cheat) {
+            Some(index) => {
+                self.on.remove(index);
+            }
+            None => self.on.push(*cheat),
+        }
+        Some(*cheat)
+    }
+
+    pub fn is_on(&self, cheat: Cheat) -> bool {
+        self.on.contains(&cheat)
+    }
+
+    pub fn any(&self) -> bool {
+        !self.on.is_empty()
+    }
+
+    // Makes a new `game` the way the cheats that are on would have it, before
+    // its first move. Returns the inputs that did it, which replays need to
+    // have on tick 0.
+    pub fn apply(&self, game: &mut Game) -> Vec<(u32, Input)> {
+        let mut inputs = Vec::new();
+        if self.is_on(Cheat::Python) {
+            for _ in 0..PYTHON_CELLS {
+                game.handle_input(0, Input::Elongate);
+                inputs.push((0, Input::Elongate));
+            }
+        }
+        inputs
+    }
+}
@@ -0,0 +1,170 @@
+// Settings read from config.toml in the config directory. Every key is
+// optional, a missing file is the same as an empty one.
+
+use serde::Deserialize;
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    // Base URL of an online leaderboard, scores are only submitted when this
+    // is set. Needs a build with the `online` feature.
+    #[cfg_attr(not(feature = "online"), allow(dead_code))]
+    pub leaderboard_url: Option<String>,
+    // A Lua file with custom rules for single player games, see `script`.
+    // Needs a build with the `lua` feature.
+    pub script: Option<std::path::PathBuf>,
+    // The language for menus and messages, like "nl", see `l10n`. LANG
+    // when not set.
+    pub language: Option<String>,
+    // What happens when a snake eats or dies: "bell", "flash" or "off".
+    // `--no-bell` turns the bell into a flash.
+    pub feedback: snake::reactions::Feedback,
+    // Never flashes the screen, whatever `feedback` says, or slows down after
+    // a near miss, for anyone sensitive to things moving that aren't the
+    // snake. Cameras following a snake jump instead of scrolling, and the
+    // dark of nights and random events dims the board instead of hiding it.
+    // Games play the same.
+    pub reduced_motion: bool,
+    // Slows the game down for a moment when a snake turns away just before
+    // crashing, while going faster than the usual speed.
+    pub slow_motion: bool,
+    // Chiptune music during games, played with pw-play, paplay or aplay.
+    pub music: bool,
+    // A ping for the nearest food that gets higher the closer it is, panned
+    // to its side, and a warning tone before crashing, for playing with
+    // little or no sight. Uses the same players as `music`.
+    pub audio_cues: bool,
+    // How many columns and rows of the terminal every cell of the board takes
+    // up: "1x1", "2x1" or "2x2". Terminal cells are about twice as tall as
+    // they're wide, so "2x1" makes the board look square.
+    pub cell_size: CellSize,
+    // Draws food as 🍎, 🍒 and 🐭 instead of letters, when the terminal looks
+    // like it can and `cell_size` is at least two columns wide.
+    pub emoji_food: bool,
+    // "classic", "winter" or "halloween", see `theme`. Follows the seasons
+    // when not set.
+    pub theme: Option<crate::theme::ThemeName>,
+    // Plays the computer on a board twice as big as the terminal both ways,
+    // with the screen split down the middle and each half following a snake.
+    pub split_screen: bool,
+    // An arrow next to the score pointing to the nearest food.
+    pub food_hint: bool,
+    // Night falls every minute or so during games, and for a while only the
+    // board around the snakes can be seen, in darker colors. Games play the
+    // same.
+    pub day_night: bool,
+    // From 0 to 10, 5 when not set.
+    pub volume: Option<u8>,
+    // How many times the snake can crash before the game is over, 1 when not
+    // set.
+    pub lives: Option<u32>,
+    // Easy mode: crashing only stops the snake until it turns away. Scores
+    // aren't submitted to the leaderboard.
+    pub bumper: bool,
+    // Snakes can go diagonally too, with y, u, b and n, and can't squeeze
+    // between two cells that are both taken. Scores aren't submitted to the
+    // leaderboard.
+    pub diagonal: bool,
+    // The wind pushes the snake an extra cell every few moves, and turns now
+    // and then. Scores aren't submitted to the leaderboard.
+    pub wind: bool,
+    // How many ticks the snake waits in front of a crash for a turn that came
+    // a little late, 0 when not set. Worth 1 or 2 at high speeds.
+    pub grace: Option<u32>,
+    // Counts which games get played and how long sessions last, in usage.json
+    // in the data directory, see `usage`. Nothing leaves the computer unless
+    // `usage_stats_url` is set too.
+    pub usage_stats: bool,
+    // Where to send the counts at the end of every session. Needs a build
+    // with the `online` feature.
+    #[cfg_attr(not(feature = "online"), allow(dead_code))]
+    pub usage_stats_url: Option<String>,
+    // Whose versus rating goes up and down, see `ratings`, so people sharing
+    // a computer can each have one. "default" when not set.
+    pub profile: Option<String>,
+    // A neural net to play against, offered next to the other computer
+    // players, see `neural`. Needs a build with the `neural` feature.
+    #[cfg_attr(not(feature = "neural"), allow(dead_code))]
+    pub neural_net: Option<std::path::PathBuf>,
+    // How fast single player games go as the score goes up, as points of
+    // score and milliseconds between moves, like [[0, 150], [10, 120],
+    // [30, 90]]: 150 to start with, down to 120 by 10 points, 90 by 30 and
+    // staying there. Between points it's a straight line. + and - still
+    // change it until the next point scored. Replays play back at the usual
+    // speed. Moves keep to `--tick-ms` when not set.
+    pub speed_curve: Option<SpeedCurve>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(try_from = "Vec<(u32, u64)>")]
+pub struct SpeedCurve(Vec<(u32, u64)>);
+
+impl TryFrom<Vec<(u32, u64)>> for SpeedCurve {
+    type Error = String;
+
+    fn try_from(points: Vec<(u32, u64)>) -> Result<Self, String> {
+        match points.first() {
+            Some((0, _)) => {}
+            _ => return Err("the speed curve has to start at a score of 0".to_string()),
+        }
+        if points.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+            return Err("the speed curve's scores have to go up".to_string());
+        }
+        if points.iter().any(|(_, ms)| *ms == 0) {
+            return Err("the speed curve can't have 0 ms between moves".to_string());
+        }
+        Ok(SpeedCurve(points))
+    }
+}
+
+impl SpeedCurve {
+    // The time between moves at `score`.
+    pub fn at(&self, score: u32) -> std::time::Duration {
+        let after = self.0.iter().position(|(from, _)| *from > score);
+        let ms = match after {
+            None => self.0[self.0.len() - 1].1 as f64,
+            Some(index) => {
+                let (from, slow) = self.0[index - 1];
+                let (to, fast) = self.0[index];
+                let along = f64::from(score - from) / f64::from(to - from);
+                slow as f64 + (fast as f64 - slow as f64) * along
+            }
+        };
+        std::time::Duration::from_secs_f64(ms / 1000.0)
+    }
+}
+
+#[derive(Copy, Clone, Default, Deserialize)]
+pub enum CellSize {
+    #[default]
+    #[serde(rename = "1x1")]
+    Single,
+    #[serde(rename = "2x1")]
+    Wide,
+    #[serde(rename = "2x2")]
+    Large,
+}
+
+impl CellSize {
+    pub fn columns_rows(&self) -> (u16, u16) {
+        match self {
+            CellSize::Single => (1, 1),
+            CellSize::Wide => (2, 1),
+            CellSize::Large => (2, 2),
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Self, String> {
+        let path = crate::paths::config_file();
+
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => {
+                toml::from_str(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+}
@@ -0,0 +1,213 @@
+// The console ~ opens during a game when snake is started with --dev, for
+// trying things out while making levels. The game waits while it's open, up
+// and down go through what was typed before, and enter runs a command:
+//
+//   spawn food <x> <y> [<kind>]   food there, an apple unless it's a cherry,
+//                                 mouse or feast
+//   speed <ms>                    milliseconds between moves, 50 to 400
+//   seed <seed>                   random numbers from here on as if the game
+//                                 had been started with that seed
+//   grow <cells>                  a longer snake
+//   give bridge                   the bridge power-up, for crossing water
+//   help                          the commands
+//
+// Replays don't know about any of it, so games the console changed don't
+// play back the same and don't make the high scores.
+
+use crate::game::{Coordinate, Food, FoodKind, Game, MoveDirection, BRIDGE_TICKS};
+use crate::input::InputEvent;
+use std::io::Write;
+
+const HISTORY: usize = 50;
+const HELP: &str = "spawn food <x> <y> [kind], speed <ms>, seed <seed>, grow <cells>, give bridge";
+
+// Set from --dev on the command line, before any game.
+pub static ENABLED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Command {
+    SpawnFood(Coordinate, FoodKind),
+    Speed(u64),
+    Seed(u64),
+    Grow(u32),
+    GiveBridge,
+    Help,
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Command, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let number = |word: &str| {
+            word.parse::<u64>()
+                .map_err(|_| format!("{} isn't a number", word))
+        };
+
+        match words.as_slice() {
+            ["spawn", "food", x, y, kind @ ..] => {
+                let kind = match kind {
+                    [] | ["apple"] => FoodKind::Apple,
+                    ["cherry"] => FoodKind::Cherry,
+                    ["mouse"] => FoodKind::Mouse,
+                    ["feast"] => FoodKind::Feast,
+                    _ => return Err(format!("no food called {}", kind.join(" "))),
+                };
+                let (x, y) = (number(x)?, number(y)?);
+                match (u16::try_from(x), u16::try_from(y)) {
+                    (Ok(x), Ok(y)) => Ok(Command::SpawnFood(Coordinate(x, y), kind)),
+                    _ => Err(format!("{},{} isn't on the board", x, y)),
+                }
+            }
+            ["speed", ms] => {
+                let range =
+                    crate::FASTEST_MOVE.as_millis() as u64..=crate::SLOWEST_MOVE.as_millis() as u64;
+                match number(ms)? {
+                    ms if range.contains(&ms) => Ok(Command::Speed(ms)),
+                    _ => Err(format!("speed takes {} to {}", range.start(), range.end())),
+                }
+            }
+            ["seed", seed] => Ok(Command::Seed(number(seed)?)),
+            ["grow", cells] => match u32::try_from(number(cells)?) {
+                Ok(cells) => Ok(Command::Grow(cells)),
+                Err(_) => Err(format!("{} is too many cells", cells)),
+            },
+            ["give", "bridge"] => Ok(Command::GiveBridge),
+            ["give", thing] => Err(format!("no power-up called {}", thing)),
+            ["help"] => Ok(Command::Help),
+            [] => Err(String::new()),
+            _ => Err(format!("unknown command {}, try help", line.trim())),
+        }
+    }
+
+    // Does what it says to `game`, and says what happened.
+    pub fn run(self, game: &mut Game, move_duration: &mut std::time::Duration) -> String {
+        match self {
+            Command::SpawnFood(location, kind) => {
+                let food = Food {
+                    kind,
+                    ..Food::new(location)
+                };
+                if !food.cells().all(|c| c.on_board(game.board_size)) {
+                    return format!("{} isn't on the board", location);
+                }
+                game.food.push(food);
+                format!("{:?} at {}", kind, location)
+            }
+            Command::Speed(ms) => {
+                *move_duration = std::time::Duration::from_millis(ms);
+                format!("{}ms between moves", ms)
+            }
+            Command::Seed(seed) => {
+                game.reseed(seed);
+                format!("seed {}", seed)
+            }
+            Command::Grow(cells) => {
+                let snake = game.active;
+                game.players[snake].grow(cells);
+                format!("{} cells longer", cells)
+            }
+            Command::GiveBridge => {
+                let snake = game.active;
+                game.players[snake].bridging = BRIDGE_TICKS;
+                format!("across water for {} moves", BRIDGE_TICKS)
+            }
+            Command::Help => HELP.to_string(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Console {
+    pub open: bool,
+    line: String,
+    history: std::collections::VecDeque<String>,
+    // How far back up and down have gone, none when at a new line.
+    browsing: Option<usize>,
+    // What the last command said.
+    reply: String,
+}
+
+impl Console {
+    // Takes `event` if it's for the console, with a command when enter was
+    // pressed on one. Anything it doesn't take is for the game.
+    pub fn handle(&mut self, event: InputEvent) -> Option<Option<Command>> {
+        if !ENABLED.load(std::sync::atomic::Ordering::Relaxed) {
+            return None;
+        }
+        if !self.open {
+            if event != InputEvent::Key(b'~') {
+                return None;
+            }
+            self.open = true;
+            return Some(None);
+        }
+
+        match event {
+            // Escape or ~ again.
+            InputEvent::Key(b'~' | 27) => self.open = false,
+            InputEvent::Key(b'\r' | b'\n') => {
+                let line = std::mem::take(&mut self.line);
+                self.browsing = None;
+                if line.trim().is_empty() {
+                    return Some(None);
+                }
+                if self.history.len() == HISTORY {
+                    self.history.pop_front();
+                }
+                self.history.push_back(line.clone());
+                match Command::parse(&line) {
+                    Ok(command) => return Some(Some(command)),
+                    Err(e) => self.reply = e,
+                }
+            }
+            InputEvent::Key(127 | 8) => {
+                self.line.pop();
+            }
+            InputEvent::Key(b) if (0x20..0x7f).contains(&b) => self.line.push(b as char),
+            InputEvent::Arrow(MoveDirection::Up) if !self.history.is_empty() => {
+                let back = self.browsing.map_or(0, |back| back + 1);
+                let back = back.min(self.history.len() - 1);
+                self.browsing = Some(back);
+                self.line = self.history[self.history.len() - 1 - back].clone();
+            }
+            InputEvent::Arrow(MoveDirection::Down) => {
+                self.browsing = self.browsing.and_then(|back| back.checked_sub(1));
+                self.line = match self.browsing {
+                    Some(back) => self.history[self.history.len() - 1 - back].clone(),
+                    None => String::new(),
+                };
+            }
+            _ => {}
+        }
+        Some(None)
+    }
+
+    pub fn replied(&mut self, reply: String) {
+        self.reply = reply;
+    }
+
+    // Along the bottom of the screen, the last reply above what's being typed.
+    pub fn render(
+        &self,
+        screen: &mut dyn Write,
+        screen_size: (u16, u16),
+    ) -> Result<(), std::io::Error> {
+        if !self.open {
+            return Ok(());
+        }
+
+        let width = screen_size.0 as usize;
+        let lines = [self.reply.clone(), format!("> {}", self.line)];
+        for (index, line) in lines.iter().enumerate() {
+            let line: String = line.chars().take(width).collect();
+            write!(
+                screen,
+                "{}{}{:<width$}{}",
+                termion::cursor::Goto(1, screen_size.1.saturating_sub(1).max(1) + index as u16),
+                termion::style::Invert,
+                line,
+                termion::style::Reset,
+            )?;
+        }
+        Ok(())
+    }
+}
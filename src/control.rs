@@ -0,0 +1,187 @@
+// `--control <path>` opens a unix socket that scripts, stream overlays and
+// tests can drive a local game through, one command per line:
+//
+//   pause, resume      stop and restart the game
+//   state              the game as JSON, the same as spectators get plus
+//                      "paused" and "tick_ms"
+//   turn <direction>   as if the player had turned, up, down, left or right,
+//                      or up_left, up_right, down_left or down_right in a
+//                      diagonal game
+//   speed <ms>         milliseconds between moves, 50 to 400, as close as
+//                      + and - can get there
+//   dump               the whole game to a JSON file in the dumps directory,
+//                      answered with where it went
+//
+// Every command gets one line back: "ok", the state, a path or "error: ...".
+// The game loop owns the game, so commands are handed over to it and answered
+// from there; outside a game they fail.
+
+use crate::game::{Game, MoveDirection};
+use serde::Serialize;
+
+// How long a client waits for the game loop before giving up.
+const REPLY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+const NO_GAME: &str = "error: no game running";
+
+#[derive(Copy, Clone)]
+pub enum Request {
+    Pause,
+    Resume,
+    State,
+    Turn(MoveDirection),
+    Speed(u64),
+    Dump,
+}
+
+impl Request {
+    fn parse(line: &str) -> Result<Request, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        match words.as_slice() {
+            ["pause"] => Ok(Request::Pause),
+            ["resume"] => Ok(Request::Resume),
+            ["state"] => Ok(Request::State),
+            ["dump"] => Ok(Request::Dump),
+            ["turn", direction] => match *direction {
+                "up" => Ok(Request::Turn(MoveDirection::Up)),
+                "down" => Ok(Request::Turn(MoveDirection::Down)),
+                "left" => Ok(Request::Turn(MoveDirection::Left)),
+                "right" => Ok(Request::Turn(MoveDirection::Right)),
+                "up_left" => Ok(Request::Turn(MoveDirection::UpLeft)),
+                "up_right" => Ok(Request::Turn(MoveDirection::UpRight)),
+                "down_left" => Ok(Request::Turn(MoveDirection::DownLeft)),
+                "down_right" => Ok(Request::Turn(MoveDirection::DownRight)),
+                _ => Err(format!("unknown direction {}", direction)),
+            },
+            ["speed", ms] => {
+                let range =
+                    crate::FASTEST_MOVE.as_millis() as u64..=crate::SLOWEST_MOVE.as_millis() as u64;
+                match ms.parse() {
+                    Ok(ms) if range.contains(&ms) => Ok(Request::Speed(ms)),
+                    _ => Err(format!(
+                        "invalid speed {}, it takes {} to {}",
+                        ms,
+                        range.start(),
+                        range.end()
+                    )),
+                }
+            }
+            _ => Err(format!("unknown command {}", line.trim())),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct State {
+    #[serde(flatten)]
+    snapshot: crate::net::Snapshot,
+    paused: bool,
+    tick_ms: u64,
+}
+
+pub fn state(game: &Game, paused: bool, tick: std::time::Duration) -> String {
+    let state = State {
+        snapshot: crate::net::Snapshot::new(game),
+        paused,
+        tick_ms: tick.as_millis() as u64,
+    };
+    serde_json::to_string(&state).unwrap()
+}
+
+type Pending = (Request, std::sync::mpsc::Sender<String>);
+
+pub struct Control {
+    requests: std::sync::mpsc::Receiver<Pending>,
+    #[cfg(unix)]
+    path: std::path::PathBuf,
+}
+
+impl Control {
+    #[cfg(unix)]
+    pub fn listen(path: &std::path::Path) -> Result<Self, std::io::Error> {
+        use std::io::{BufRead, Write};
+        use std::os::unix::fs::FileTypeExt;
+
+        // Left behind by a snake that didn't get to clean up, but don't
+        // remove anything that isn't a socket.
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            if metadata.file_type().is_socket() {
+                std::fs::remove_file(path)?;
+            }
+        }
+
+        let listener = std::os::unix::net::UnixListener::bind(path)?;
+        let (sender, requests) = std::sync::mpsc::channel::<Pending>();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let sender = sender.clone();
+                std::thread::spawn(move || {
+                    let Ok(reader) = stream.try_clone() else {
+                        return;
+                    };
+                    let mut writer = stream;
+
+                    for line in std::io::BufReader::new(reader).lines() {
+                        let Ok(line) = line else {
+                            return;
+                        };
+                        if line.trim().is_empty() {
+                            continue;
+                        }
+
+                        let reply = match Request::parse(&line) {
+                            Ok(request) => {
+                                let (reply_sender, reply) = std::sync::mpsc::channel();
+                                if sender.send((request, reply_sender)).is_err() {
+                                    return;
+                                }
+                                reply
+                                    .recv_timeout(REPLY_TIMEOUT)
+                                    .unwrap_or_else(|_| NO_GAME.to_string())
+                            }
+                            Err(e) => format!("error: {}", e),
+                        };
+
+                        if writeln!(writer, "{}", reply).is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+
+        Ok(Control {
+            requests,
+            path: path.to_path_buf(),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn listen(_path: &std::path::Path) -> Result<Self, std::io::Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "control sockets need a unix system",
+        ))
+    }
+
+    // Answers every command sent since the last call with `handle`. Never
+    // blocks.
+    pub fn handle(&self, mut handle: impl FnMut(Request) -> String) {
+        for (request, reply) in self.requests.try_iter() {
+            let _ = reply.send(handle(request));
+        }
+    }
+
+    // For when there's no game to drive.
+    pub fn refuse(&self) {
+        self.handle(|_| NO_GAME.to_string());
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Control {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
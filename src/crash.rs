@@ -0,0 +1,138 @@
+// Crash reports, for attaching to bug reports. The game loop keeps the last
+// inputs and a summary of the game here as it goes, and when snake panics or
+// a game fails, they're written to a file in the crashes directory together
+// with what went wrong.
+
+use crate::game::Game;
+use crate::input::InputEvent;
+
+const MAX_INPUTS: usize = 1000;
+
+// What's needed to tell what the game looked like, without keeping a copy of
+// all of it every tick.
+struct Summary {
+    mode: crate::game::Mode,
+    seed: u64,
+    tick: u32,
+    board_size: (u16, u16),
+    players: Vec<String>,
+    food: usize,
+}
+
+#[derive(Default)]
+struct Log {
+    inputs: std::collections::VecDeque<(u32, InputEvent)>,
+    game: Option<Summary>,
+}
+
+static LOG: std::sync::Mutex<Option<Log>> = std::sync::Mutex::new(None);
+
+fn with_log(f: impl FnOnce(&mut Log)) {
+    // A panic while the log was held leaves it poisoned, but what's in it is
+    // still worth reporting.
+    let mut log = LOG.lock().unwrap_or_else(|e| e.into_inner());
+    f(log.get_or_insert_with(Log::default));
+}
+
+pub fn input(tick: u32, event: InputEvent) {
+    with_log(|log| {
+        if log.inputs.len() == MAX_INPUTS {
+            log.inputs.pop_front();
+        }
+        log.inputs.push_back((tick, event));
+    });
+}
+
+// Remembers `game` as the one being played, after every tick.
+pub fn game(game: &Game) {
+    let players = game
+        .players
+        .iter()
+        .map(|p| {
+            format!(
+                "{} cells, head {} going {:?}, score {}, {} lives, {}",
+                p.segments.len(),
+                p.head(),
+                p.move_direction,
+                p.score,
+                p.lives,
+                if p.alive { "alive" } else { "dead" }
+            )
+        })
+        .collect();
+
+    with_log(|log| {
+        log.game = Some(Summary {
+            mode: game.mode,
+            seed: game.seed,
+            tick: game.tick,
+            board_size: game.board_size,
+            players,
+            food: game.food.len(),
+        })
+    });
+}
+
+// Writes a report of `reason` with everything logged so far.
+pub fn report(reason: &str) -> Result<std::path::PathBuf, std::io::Error> {
+    let mut report = format!(
+        "snake {} crashed: {}\n\n",
+        env!("CARGO_PKG_VERSION"),
+        reason
+    );
+    // Panicking while logging leaves the log locked on this thread, waiting
+    // for it would never end.
+    let log = match LOG.try_lock() {
+        Ok(log) => Some(log),
+        Err(std::sync::TryLockError::Poisoned(e)) => Some(e.into_inner()),
+        Err(std::sync::TryLockError::WouldBlock) => None,
+    };
+    match log.as_ref().and_then(|log| log.as_ref()) {
+        Some(log) => {
+            match &log.game {
+                Some(game) => {
+                    report += &format!(
+                        "mode {:?}, seed {}, tick {}, board {}x{}, {} food\n",
+                        game.mode,
+                        game.seed,
+                        game.tick,
+                        game.board_size.0,
+                        game.board_size.1,
+                        game.food
+                    );
+                    for (index, player) in game.players.iter().enumerate() {
+                        report += &format!("player {}: {}\n", index + 1, player);
+                    }
+                }
+                None => report += "no game was being played\n",
+            }
+
+            report += &format!("\nlast {} inputs, oldest first:\n", log.inputs.len());
+            for (tick, event) in &log.inputs {
+                report += &format!("  tick {}: {:?}\n", tick, event);
+            }
+        }
+        None => report += "nothing was logged\n",
+    }
+    drop(log);
+
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let path = crate::paths::crash_dir().join(format!("crash-{}.txt", seconds));
+    std::fs::create_dir_all(crate::paths::crash_dir())?;
+    std::fs::write(&path, report)?;
+    Ok(path)
+}
+
+// Writes a report for every panic, then carries on as panics usually do.
+pub fn install() {
+    let default = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default(info);
+        match report(&info.to_string()) {
+            Ok(path) => eprintln!("A crash report was saved to {}", path.display()),
+            Err(e) => eprintln!("Could not save a crash report: {}", e),
+        }
+    }));
+}
@@ -0,0 +1,210 @@
+// `snake daemon` hosts everyone's games in one process: over telnet, and over
+// SSH too when built with it. Every session runs the menu on a thread of its
+// own, with its own theme and cheats, and is kept track of here. The daemon
+// listens on a unix socket in the data directory for `snake sessions`, one
+// command per line:
+//
+//   list        a line for every session, "<id> <frontend> <peer> <seconds>",
+//               then an empty line
+//   kill <id>   disconnects the session, answered with "ok" or "error: ..."
+
+use std::io::{BufRead, Write};
+use std::sync::{Arc, Mutex};
+
+pub const DEFAULT_TELNET_PORT: u16 = 2323;
+
+struct Session {
+    frontend: &'static str,
+    peer: String,
+    started: std::time::Instant,
+    // Cuts the connection, which ends the session's thread the next time it
+    // draws.
+    kill: Box<dyn Fn() + Send>,
+}
+
+#[derive(Default)]
+pub struct Sessions {
+    next: std::sync::atomic::AtomicU64,
+    sessions: Mutex<std::collections::BTreeMap<u64, Session>>,
+}
+
+// Takes a session off the list when its thread ends, however it does.
+pub struct Registered {
+    sessions: Arc<Sessions>,
+    id: u64,
+}
+
+impl Drop for Registered {
+    fn drop(&mut self) {
+        self.sessions.lock().remove(&self.id);
+    }
+}
+
+impl Sessions {
+    fn lock(&self) -> std::sync::MutexGuard<'_, std::collections::BTreeMap<u64, Session>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Keeps track of a session until what this returns is dropped.
+    pub fn register(
+        self: &Arc<Self>,
+        frontend: &'static str,
+        peer: String,
+        kill: Box<dyn Fn() + Send>,
+    ) -> Registered {
+        let id = self.next.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+        let session = Session {
+            frontend,
+            peer,
+            started: std::time::Instant::now(),
+            kill,
+        };
+        self.lock().insert(id, session);
+        Registered {
+            sessions: Arc::clone(self),
+            id,
+        }
+    }
+
+    fn list(&self) -> Vec<String> {
+        self.lock()
+            .iter()
+            .map(|(id, session)| {
+                format!(
+                    "{} {} {} {}",
+                    id,
+                    session.frontend,
+                    session.peer,
+                    session.started.elapsed().as_secs()
+                )
+            })
+            .collect()
+    }
+
+    fn kill(&self, id: u64) -> bool {
+        match self.lock().get(&id) {
+            Some(session) => {
+                (session.kill)();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn answer(&self, line: &str) -> String {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["list"] => self.list().iter().map(|s| s.clone() + "\n").collect(),
+            ["kill", id] => match id.parse().map(|id| self.kill(id)) {
+                Ok(true) => "ok\n".to_string(),
+                Ok(false) => format!("error: no session {}\n", id),
+                Err(_) => format!("error: {} isn't a session\n", id),
+            },
+            _ => format!("error: unknown command {}\n", line.trim()),
+        }
+    }
+}
+
+// Runs every frontend until the process is stopped.
+pub fn run(
+    config: crate::config::Config,
+    telnet_port: u16,
+    ssh_port: Option<u16>,
+) -> Result<(), String> {
+    let sessions = Arc::new(Sessions::default());
+    listen_for_admin(&crate::paths::daemon_socket(), Arc::clone(&sessions))?;
+
+    #[cfg(feature = "ssh")]
+    if let Some(port) = ssh_port {
+        let (config, sessions) = (config.clone(), Arc::clone(&sessions));
+        std::thread::spawn(move || {
+            if let Err(e) = crate::ssh::serve(port, config, sessions) {
+                eprintln!("Could not serve SSH on port {}: {}", port, e);
+            }
+        });
+    }
+    #[cfg(not(feature = "ssh"))]
+    if ssh_port.is_some() {
+        return Err("snake was built without SSH".to_string());
+    }
+
+    crate::telnet::serve(telnet_port, config, sessions)
+}
+
+#[cfg(unix)]
+fn listen_for_admin(path: &std::path::Path, sessions: Arc<Sessions>) -> Result<(), String> {
+    use std::os::unix::fs::FileTypeExt;
+
+    let error = |e: std::io::Error| format!("{}: {}", path.display(), e);
+    // Left behind by a daemon that didn't get to clean up, but don't remove
+    // anything that isn't a socket.
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if metadata.file_type().is_socket() {
+            std::fs::remove_file(path).map_err(error)?;
+        }
+    }
+    let listener = std::os::unix::net::UnixListener::bind(path).map_err(error)?;
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let sessions = Arc::clone(&sessions);
+            std::thread::spawn(move || {
+                let Ok(reader) = stream.try_clone() else {
+                    return;
+                };
+                let mut writer = stream;
+                for line in std::io::BufReader::new(reader).lines() {
+                    let Ok(line) = line else {
+                        return;
+                    };
+                    // Lists end with an empty line, which is also the
+                    // answer to an empty list.
+                    let mut answer = sessions.answer(&line);
+                    if line.trim() == "list" {
+                        answer.push('\n');
+                    }
+                    if writer.write_all(answer.as_bytes()).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn listen_for_admin(_path: &std::path::Path, _sessions: Arc<Sessions>) -> Result<(), String> {
+    Err("snake daemon needs a unix system".to_string())
+}
+
+// `snake sessions`: sends `command` to the daemon and prints the answer.
+#[cfg(unix)]
+pub fn admin(command: &str) -> Result<(), String> {
+    let path = crate::paths::daemon_socket();
+    let error = |e: std::io::Error| format!("{}: {}", path.display(), e);
+    let stream = std::os::unix::net::UnixStream::connect(&path).map_err(error)?;
+    let mut writer = stream.try_clone().map_err(error)?;
+    writeln!(writer, "{}", command).map_err(error)?;
+
+    let mut lines = std::io::BufReader::new(stream).lines();
+    while let Some(line) = lines.next().transpose().map_err(error)? {
+        if line.starts_with("error: ") {
+            return Err(line.trim_start_matches("error: ").to_string());
+        }
+        // The end of a list, or the only line of anything else.
+        if line.is_empty() || command != "list" {
+            if !line.is_empty() {
+                println!("{}", line);
+            }
+            return Ok(());
+        }
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn admin(_command: &str) -> Result<(), String> {
+    Err("snake daemon needs a unix system".to_string())
+}
@@ -0,0 +1,14 @@
+// A slow cycle of days and nights for games played with `day_night` in the
+// config, counted in ticks from the start. It only changes how the board is
+// drawn: darker, and only around the snakes at night.
+
+pub const DAY_TICKS: u32 = 300;
+pub const NIGHT_TICKS: u32 = 120;
+// How far from a head the board can be seen at night.
+pub const NIGHT_VISION: u16 = 6;
+
+// Whether it's night on `tick`, which it is for the last `NIGHT_TICKS` of
+// every cycle.
+pub fn night(tick: u32) -> bool {
+    tick % (DAY_TICKS + NIGHT_TICKS) >= DAY_TICKS
+}
@@ -0,0 +1,137 @@
+// The overlay F3 toggles during a game, for seeing what the loop is up to:
+// frames per second, how long the last tick took against how long it had,
+// what the last read from the terminal held and how much is on the board.
+// With it up, D dumps the whole game to a JSON file in the dumps directory,
+// for attaching to bug reports, which reads back into the same `Game`.
+
+use crate::game::Game;
+use crate::input::InputEvent;
+use std::io::Write;
+
+const WIDTH: u16 = 26;
+
+#[derive(Default)]
+pub struct Overlay {
+    pub shown: bool,
+    // When the frames of the last second started.
+    frames: std::collections::VecDeque<std::time::Instant>,
+    tick_time: std::time::Duration,
+    // Everything read from the terminal in the last frame anything was.
+    last_input: Vec<u8>,
+    // What the last dump was saved as, or why it wasn't.
+    dumped: Option<String>,
+}
+
+impl Overlay {
+    // Takes F3 out of `events`, toggling the overlay for every press, and
+    // remembers what `input` was.
+    pub fn handle_input(&mut self, input: &[u8], events: &mut Vec<InputEvent>) {
+        events.retain(|event| match event {
+            InputEvent::F3 => {
+                self.shown = !self.shown;
+                false
+            }
+            _ => true,
+        });
+
+        if !input.is_empty() {
+            self.last_input = input.to_vec();
+        }
+    }
+
+    pub fn frame(&mut self) {
+        let now = std::time::Instant::now();
+        self.frames.push_back(now);
+        while self
+            .frames
+            .front()
+            .is_some_and(|f| now - *f > std::time::Duration::from_secs(1))
+        {
+            self.frames.pop_front();
+        }
+    }
+
+    pub fn dump(&mut self, game: &Game) {
+        self.dumped = Some(match dump(game) {
+            Ok(path) => path
+                .file_name()
+                .map_or(String::new(), |name| name.to_string_lossy().into_owned()),
+            Err(e) => e,
+        });
+    }
+
+    pub fn ticked(&mut self, took: std::time::Duration) {
+        self.tick_time = took;
+    }
+
+    pub fn render(
+        &self,
+        screen: &mut dyn Write,
+        screen_size: (u16, u16),
+        game: &Game,
+        move_duration: std::time::Duration,
+    ) -> Result<(), std::io::Error> {
+        if !self.shown {
+            return Ok(());
+        }
+
+        let input = self
+            .last_input
+            .iter()
+            .map(|b| match *b as char {
+                c if c.is_ascii_graphic() => format!("{}({})", b, c),
+                _ => b.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let lines = [
+            format!("fps     {}", self.frames.len()),
+            format!(
+                "tick    {} {}us/{}ms",
+                game.tick,
+                self.tick_time.as_micros(),
+                move_duration.as_millis()
+            ),
+            format!("queued  {} bytes", self.last_input.len()),
+            format!("input   {}", input),
+            format!(
+                "snakes  {} ({} cells)",
+                game.players.len(),
+                game.players.iter().map(|p| p.segments.len()).sum::<usize>()
+            ),
+            format!("food    {}", game.food.len()),
+            format!(
+                "dump    {}",
+                self.dumped.as_deref().unwrap_or("D saves one")
+            ),
+        ];
+
+        let x = screen_size.0.saturating_sub(WIDTH).max(1);
+        for (index, line) in lines.iter().enumerate() {
+            let line: String = line.chars().take(WIDTH as usize - 1).collect();
+            write!(
+                screen,
+                "{}{}{:<width$}{}",
+                termion::cursor::Goto(x, 1 + index as u16),
+                termion::style::Invert,
+                line,
+                termion::style::Reset,
+                width = WIDTH as usize - 1,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+// Writes `game` as it is now to a new file, named for when and which tick.
+pub fn dump(game: &Game) -> Result<std::path::PathBuf, String> {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let path = crate::paths::dump_dir().join(format!("dump-{}-{}.json", seconds, game.tick));
+    let contents = serde_json::to_string_pretty(game).map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(crate::paths::dump_dir()).map_err(|e| e.to_string())?;
+    std::fs::write(&path, contents).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(path)
+}
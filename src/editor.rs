@@ -0,0 +1,520 @@
+// `snake edit <level>`, for making levels with the keyboard and mouse. The
+// cursor moves with hjkl like the snake and the other keys change whatever is
+// under it. Clicking paints walls, and a region picked with the right button
+// can be filled all at once.
+
+use crate::game::{Coordinate, Game, Mode, MoveDirection};
+use crate::input::{InputEvent, Mouse, MouseButton};
+use crate::l10n::{fill, tr};
+use crate::level::{Door, Gate, Level, Spawn, Terrain, TerrainKind, Topology, Zone};
+use std::io::Write;
+
+const HELP: &[&str] = &[
+    "hjkl  move the cursor",
+    "w     wall",
+    "p     portal, press again at the other end",
+    "1 2   spawn point for a player, facing the last move",
+    "f     food zone, press again at the opposite corner",
+    "o     one-way gate, facing the last move",
+    "d     key, press again on every cell of its door",
+    "x     erase",
+    "u     puzzle level, played a move at a time",
+    "e     edges: crash, wrap, or wrap left and right",
+    "g     ground: boost strip, mud, ice, water, bridge or nothing",
+    "t     test play",
+    "S     save",
+    "r     revert to the saved level",
+    "q     quit",
+    "click wall, or clear one, dragging paints",
+    "right drag to select, then w x or f fills it",
+];
+
+// Button presses and drags, with SGR coordinates so wide terminals work.
+const MOUSE_ON: &str = "\x1b[?1002h\x1b[?1006h";
+const MOUSE_OFF: &str = "\x1b[?1002l\x1b[?1006l";
+
+// The first end of a portal or corner of a food zone, waiting for the second,
+// or the key of the door being given cells.
+#[derive(Copy, Clone)]
+enum Pending {
+    Portal(Coordinate),
+    Zone(Coordinate),
+    Door(Coordinate),
+}
+
+struct Editor {
+    level: Level,
+    cursor: Coordinate,
+    direction: MoveDirection,
+    pending: Option<Pending>,
+    // Whether dragging with the left button sets walls or clears them.
+    painting: Option<bool>,
+    selection: Option<Zone>,
+    changed: bool,
+    status: String,
+}
+
+impl Editor {
+    fn move_cursor(&mut self, direction: MoveDirection) {
+        let Coordinate(x, y) = self.cursor;
+        let (w, h) = self.level.size;
+
+        let (dx, dy) = direction.delta();
+        self.cursor = Coordinate(
+            x.saturating_add_signed(dx).clamp(1, w),
+            y.saturating_add_signed(dy).clamp(1, h),
+        );
+        self.direction = direction;
+    }
+
+    // Clears the cell under the cursor of anything but what's being placed.
+    fn erase(&mut self) {
+        self.erase_at(self.cursor);
+    }
+
+    fn erase_at(&mut self, cell: Coordinate) {
+        self.level.walls.retain(|c| *c != cell);
+        self.level.portals.retain(|(a, b)| *a != cell && *b != cell);
+        self.level.spawns.retain(|s| s.head != cell);
+        self.level.food_zones.retain(|z| !z.contains(&cell));
+        self.level.food_weights.retain(|w| !w.zone.contains(&cell));
+        self.clear_terrain(cell);
+        self.level.bridge_power_ups.retain(|c| *c != cell);
+        self.level.gates.retain(|g| g.cell != cell);
+        self.level.doors.retain(|d| d.key != cell);
+        for door in &mut self.level.doors {
+            door.cells.retain(|c| *c != cell);
+        }
+        self.changed = true;
+    }
+
+    fn toggle_wall(&mut self) {
+        self.set_wall(self.cursor, !self.level.is_wall(&self.cursor));
+    }
+
+    fn set_wall(&mut self, cell: Coordinate, wall: bool) {
+        if !wall {
+            self.level.walls.retain(|c| *c != cell);
+        } else if !self.level.is_wall(&cell) {
+            self.erase_at(cell);
+            self.level.walls.push(cell);
+        }
+        self.changed = true;
+    }
+
+    // Every cell of `zone`, row by row.
+    fn cells(zone: Zone) -> impl Iterator<Item = Coordinate> {
+        let Zone(Coordinate(x1, y1), Coordinate(x2, y2)) = zone;
+        (y1.min(y2)..=y1.max(y2))
+            .flat_map(move |y| (x1.min(x2)..=x1.max(x2)).map(move |x| Coordinate(x, y)))
+    }
+
+    // What w, x and f do to a selection instead of the cell under the cursor.
+    fn fill_selection(&mut self, zone: Zone, key: u8) {
+        match key {
+            119 => Self::cells(zone).for_each(|c| self.set_wall(c, true)),
+            120 => Self::cells(zone).for_each(|c| self.erase_at(c)),
+            _ => {
+                self.level.food_zones.push(zone);
+                self.changed = true;
+            }
+        }
+    }
+
+    // The board cell under a terminal column and row, or the nearest one when
+    // it's off the board.
+    fn cell_at(&self, column: u16, row: u16) -> Coordinate {
+        let (w, h) = crate::cell_size();
+        let (width, height) = self.level.size;
+        Coordinate(
+            ((column.max(1) - 1) / w + 1).min(width),
+            ((row.max(1) - 1) / h + 1).min(height),
+        )
+    }
+
+    fn mouse(&mut self, mouse: Mouse) {
+        match mouse {
+            Mouse::Press(MouseButton::Left, column, row) => {
+                let cell = self.cell_at(column, row);
+                let wall = !self.level.is_wall(&cell);
+                self.cursor = cell;
+                self.selection = None;
+                self.painting = Some(wall);
+                self.set_wall(cell, wall);
+            }
+            Mouse::Drag(MouseButton::Left, column, row) => {
+                // Drags are reported as often as the terminal likes, so the
+                // cells between two reports are painted too.
+                if let Some(wall) = self.painting {
+                    let Coordinate(x, y) = self.cell_at(column, row);
+                    while self.cursor != Coordinate(x, y) {
+                        let Coordinate(cx, cy) = self.cursor;
+                        self.cursor = Coordinate(
+                            cx + (x > cx) as u16 - (x < cx) as u16,
+                            cy + (y > cy) as u16 - (y < cy) as u16,
+                        );
+                        self.set_wall(self.cursor, wall);
+                    }
+                }
+            }
+            Mouse::Press(MouseButton::Right, column, row) => {
+                let cell = self.cell_at(column, row);
+                self.cursor = cell;
+                self.selection = Some(Zone(cell, cell));
+            }
+            Mouse::Drag(MouseButton::Right, column, row) => {
+                if let Some(Zone(start, _)) = self.selection {
+                    let cell = self.cell_at(column, row);
+                    self.cursor = cell;
+                    self.selection = Some(Zone(start, cell));
+                }
+            }
+            Mouse::Release(..) => self.painting = None,
+            Mouse::Press(MouseButton::Middle, ..) | Mouse::Drag(MouseButton::Middle, ..) => {}
+        }
+    }
+
+    fn toggle_puzzle(&mut self) {
+        self.level.puzzle = !self.level.puzzle;
+        self.status = match self.level.puzzle {
+            true => tr("Puzzle level").to_string(),
+            false => tr("Not a puzzle level").to_string(),
+        };
+        self.changed = true;
+    }
+
+    fn cycle_topology(&mut self) {
+        let (topology, status) = match self.level.topology {
+            Topology::Bounded => (Topology::Toroidal, "Edges wrap round"),
+            Topology::Toroidal => (Topology::Cylinder, "Edges wrap left and right"),
+            Topology::Cylinder => (Topology::Bounded, "Edges are crashed into"),
+        };
+        self.level.topology = topology;
+        self.status = tr(status).to_string();
+        self.changed = true;
+    }
+
+    // Terrain covering more than `cell` goes with it, like food zones do.
+    fn clear_terrain(&mut self, cell: Coordinate) {
+        self.level.terrain.retain(|t| !t.zone.contains(&cell));
+    }
+
+    fn cycle_terrain(&mut self) {
+        let (kind, status) = match self.level.terrain_at(&self.cursor) {
+            None => (Some(TerrainKind::Boost), "Boost strip"),
+            Some(TerrainKind::Boost) => (Some(TerrainKind::Mud), "Mud"),
+            Some(TerrainKind::Mud) => (Some(TerrainKind::Ice), "Ice"),
+            Some(TerrainKind::Ice) => (Some(TerrainKind::Water), "Water"),
+            Some(TerrainKind::Water) => (Some(TerrainKind::Bridge), "Bridge"),
+            Some(TerrainKind::Bridge) => (None, "No terrain"),
+        };
+        self.clear_terrain(self.cursor);
+        if let Some(kind) = kind {
+            self.level.terrain.push(Terrain {
+                zone: Zone(self.cursor, self.cursor),
+                kind,
+            });
+        }
+        self.status = tr(status).to_string();
+        self.changed = true;
+    }
+
+    fn door(&mut self) {
+        self.erase();
+        let door = match self.pending {
+            Some(Pending::Door(key)) => self.level.doors.iter_mut().find(|d| d.key == key),
+            _ => None,
+        };
+        match door {
+            Some(door) => door.cells.push(self.cursor),
+            None => {
+                self.level.doors.push(Door {
+                    key: self.cursor,
+                    cells: Vec::new(),
+                });
+                self.pending = Some(Pending::Door(self.cursor));
+            }
+        }
+        self.changed = true;
+    }
+
+    fn gate(&mut self) {
+        self.erase();
+        self.level.gates.push(Gate {
+            cell: self.cursor,
+            direction: self.direction,
+        });
+        self.changed = true;
+    }
+
+    fn portal(&mut self) {
+        match self.pending {
+            Some(Pending::Portal(start)) if start != self.cursor => {
+                self.erase();
+                self.level.portals.push((start, self.cursor));
+                self.pending = None;
+                self.changed = true;
+            }
+            _ => self.pending = Some(Pending::Portal(self.cursor)),
+        }
+    }
+
+    fn zone(&mut self) {
+        match self.pending {
+            Some(Pending::Zone(corner)) => {
+                self.level.food_zones.push(Zone(corner, self.cursor));
+                self.pending = None;
+                self.changed = true;
+            }
+            _ => self.pending = Some(Pending::Zone(self.cursor)),
+        }
+    }
+
+    fn spawn(&mut self, player: usize) {
+        let spawn = Spawn {
+            head: self.cursor,
+            direction: self.direction,
+        };
+        self.level.spawns.retain(|s| s.head != self.cursor);
+
+        // Player 2's spawn point needs player 1's to be in the right place.
+        if player > self.level.spawns.len() {
+            self.status = tr("Place player 1's spawn point first").to_string();
+            return;
+        }
+
+        match self.level.spawns.get_mut(player) {
+            Some(existing) => *existing = spawn,
+            None => self.level.spawns.push(spawn),
+        }
+        self.changed = true;
+    }
+
+    fn render(&self, screen: &mut dyn Write) -> Result<(), std::io::Error> {
+        let (w, h) = self.level.size;
+
+        write!(
+            screen,
+            "{}",
+            termion::color::Bg(termion::color::Rgb(0, 64, 0))
+        )?;
+        for zone in &self.level.food_zones {
+            for y in 1..=h {
+                for x in 1..=w {
+                    if zone.contains(&Coordinate(x, y)) {
+                        crate::draw_cell(screen, Coordinate(x, y), ' ')?;
+                    }
+                }
+            }
+        }
+        write!(screen, "{}", termion::color::Bg(termion::color::Reset))?;
+        crate::render_level(screen, &self.level)?;
+
+        let colors = crate::theme::current().snakes.map(|(head, _)| head);
+        for (spawn, color) in self.level.spawns.iter().zip(colors) {
+            let arrow = match spawn.direction {
+                MoveDirection::Up => '^',
+                MoveDirection::Down => 'v',
+                MoveDirection::Left => '<',
+                MoveDirection::Right => '>',
+                MoveDirection::UpLeft => '↖',
+                MoveDirection::UpRight => '↗',
+                MoveDirection::DownLeft => '↙',
+                MoveDirection::DownRight => '↘',
+            };
+            write!(screen, "{}", termion::color::Fg(color))?;
+            crate::draw_cell(screen, spawn.head, arrow)?;
+            write!(screen, "{}", termion::color::Fg(termion::color::Reset))?;
+        }
+
+        if let Some(Pending::Portal(c) | Pending::Zone(c)) = self.pending {
+            crate::draw_cell(screen, c, '*')?;
+        }
+
+        if let Some(selection) = self.selection {
+            write!(
+                screen,
+                "{}",
+                termion::color::Bg(termion::color::Rgb(0, 64, 128))
+            )?;
+            for cell in Self::cells(selection) {
+                let glyph = match self.level.is_wall(&cell) {
+                    true => '#',
+                    false => ' ',
+                };
+                crate::draw_cell(screen, cell, glyph)?;
+            }
+            write!(screen, "{}", termion::color::Bg(termion::color::Reset))?;
+        }
+
+        let status = match self.pending {
+            Some(Pending::Portal(_)) => tr("Move to the other end and press p"),
+            Some(Pending::Zone(_)) => tr("Move to the opposite corner and press f"),
+            Some(Pending::Door(_)) => tr("Press d on every cell of the door"),
+            None if self.selection.is_some() => tr("w, x or f to fill the selection"),
+            None if self.status.is_empty() => tr("? for help"),
+            None => &self.status,
+        };
+        let bottom = crate::board_screen_size(self.level.size).1;
+        let (cursor_x, cursor_y) = crate::cell_position(self.cursor);
+        write!(
+            screen,
+            "{} {},{} {}{} {}",
+            termion::cursor::Goto(2, bottom),
+            self.cursor.0,
+            self.cursor.1,
+            if self.changed { tr("[modified] ") } else { "" },
+            status,
+            termion::cursor::Goto(cursor_x, cursor_y),
+        )?;
+
+        Ok(())
+    }
+}
+
+// A game on `level` and how it went, for test play and `snake play`.
+pub fn play(
+    screen: &mut dyn crate::term::Terminal,
+    level: &Level,
+    effects: &mut crate::effects::Effects,
+) {
+    let game = Game::with_level(Mode::Classic, rand::random(), level.clone());
+    let pace = match level.puzzle {
+        true => crate::Pace::Puzzle,
+        false => crate::Pace::Normal,
+    };
+    let (game, _) = crate::play_game(screen, game, None, None, effects, None, pace);
+    let score = match level.par {
+        Some(par) => fill("Score: {} (par {})", &[&game.players[0].score, &par]),
+        None => fill("Score: {}", &[&game.players[0].score]),
+    };
+    crate::message(screen, &[tr("Test over."), &score]);
+}
+
+pub fn edit(
+    screen: &mut dyn crate::term::Terminal,
+    path: &std::path::Path,
+    effects: &mut crate::effects::Effects,
+) {
+    let level = if path.exists() {
+        match Level::read(path) {
+            Ok(level) => level,
+            Err(e) => return crate::message(screen, &[&format!("{}: {}", path.display(), e)]),
+        }
+    } else {
+        Level::empty(crate::board_size_for(screen.size()))
+    };
+
+    let mut editor = Editor {
+        level,
+        cursor: Coordinate(1, 1),
+        direction: MoveDirection::Right,
+        pending: None,
+        painting: None,
+        selection: None,
+        changed: false,
+        status: String::new(),
+    };
+    let mut quitting = false;
+    write!(screen, "{}", MOUSE_ON).unwrap();
+
+    'editor: loop {
+        crate::clear(screen).unwrap();
+
+        for event in crate::input::parse_input(&screen.read_input()) {
+            let b = match event {
+                InputEvent::Key(b) => b,
+                InputEvent::Arrow(MoveDirection::Left) => 104,
+                InputEvent::Arrow(MoveDirection::Up) => 107,
+                InputEvent::Arrow(MoveDirection::Down) => 106,
+                InputEvent::Arrow(MoveDirection::Right) => 108,
+                InputEvent::Arrow(_) => continue,
+                InputEvent::Mouse(mouse) => {
+                    quitting = false;
+                    editor.pending = None;
+                    editor.status.clear();
+                    editor.mouse(mouse);
+                    continue;
+                }
+                InputEvent::F3 | InputEvent::Unknown => continue,
+            };
+            if b != 113 {
+                quitting = false;
+            }
+            // Moving is how the other end of a portal or zone is chosen.
+            if !matches!(b, 104 | 106 | 107 | 108 | 112 | 102 | 100) {
+                editor.pending = None;
+            }
+            editor.status.clear();
+
+            if let Some(selection) = editor.selection.take() {
+                if matches!(b, 119 | 120 | 102) {
+                    editor.fill_selection(selection, b);
+                    continue;
+                }
+            }
+
+            match b {
+                104 => editor.move_cursor(MoveDirection::Left),
+                107 => editor.move_cursor(MoveDirection::Up),
+                106 => editor.move_cursor(MoveDirection::Down),
+                108 => editor.move_cursor(MoveDirection::Right),
+                119 => editor.toggle_wall(),
+                112 => editor.portal(),
+                102 => editor.zone(),
+                49 => editor.spawn(0),
+                50 => editor.spawn(1),
+                120 => editor.erase(),
+                117 => editor.toggle_puzzle(),
+                101 => editor.cycle_topology(),
+                103 => editor.cycle_terrain(),
+                111 => editor.gate(),
+                100 => editor.door(),
+                116 => match editor.level.validate() {
+                    Ok(()) => {
+                        write!(screen, "{}", MOUSE_OFF).unwrap();
+                        play(screen, &editor.level, effects);
+                        write!(screen, "{}", MOUSE_ON).unwrap();
+                    }
+                    Err(e) => editor.status = fill("Can't play this yet: {}", &[&e]),
+                },
+                83 => match editor.level.save(path) {
+                    Ok(()) => {
+                        editor.changed = false;
+                        editor.status = match editor.level.validate() {
+                            Ok(()) => fill("Saved {}", &[&path.display()]),
+                            Err(e) => fill("Saved, but it can't be played yet: {}", &[&e]),
+                        };
+                    }
+                    Err(e) => editor.status = fill("Could not save: {}", &[&e]),
+                },
+                114 if path.exists() => match Level::read(path) {
+                    Ok(level) => {
+                        editor.level = level;
+                        editor.changed = false;
+                        editor.status = tr("Reverted").to_string();
+                    }
+                    Err(e) => editor.status = fill("Could not load: {}", &[&e]),
+                },
+                63 => {
+                    let help: Vec<&str> = HELP.iter().map(|line| tr(line)).collect();
+                    crate::message(screen, &help);
+                }
+                113 if !editor.changed || quitting => {
+                    write!(screen, "{}", MOUSE_OFF).unwrap();
+                    break 'editor;
+                }
+                113 => {
+                    quitting = true;
+                    editor.status = tr("Unsaved changes, q again to quit anyway").to_string();
+                }
+                _ => {}
+            }
+        }
+
+        editor.render(screen).unwrap();
+        screen.flush().unwrap();
+
+        screen.wait_for_input(crate::IDLE_TIMEOUT);
+    }
+}
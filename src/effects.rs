@@ -0,0 +1,171 @@
+// Feedback for things happening in the game, so eating and dying can be
+// noticed without watching the snake, the music, audio cues for playing with
+// little or no sight, and slow motion after a near miss. Works off the game
+// after every tick, and never from inside the game itself, with
+// `snake::reactions` deciding what a tick calls for. One `Effects` lasts for
+// all the games in a session, so muting sticks.
+
+use crate::game::Game;
+use snake::reactions::{Feedback, Reactions};
+use std::io::Write;
+
+// How long the screen stays inverted for a flash.
+const FLASH_DURATION: std::time::Duration = std::time::Duration::from_millis(80);
+const DEFAULT_VOLUME: u8 = 5;
+// Cues are a little shorter than a tick at the usual speed, so they don't run
+// into each other.
+const CUE_DURATION: std::time::Duration = std::time::Duration::from_millis(60);
+// The food ping goes up a semitone for every cell closer, from `FAR_NOTE` as
+// MIDI note numbers.
+const FAR_NOTE: u8 = 60;
+const NEAR_NOTE: u8 = 96;
+const WARNING_FREQUENCY: f32 = 110.0;
+
+pub struct Effects {
+    reactions: Reactions,
+    flash_until: Option<std::time::Instant>,
+    move_duration: std::time::Duration,
+    music_enabled: bool,
+    cues: bool,
+    volume: u8,
+    muted: bool,
+    sound: Option<crate::audio::Sound>,
+}
+
+impl Effects {
+    pub fn new(config: &crate::config::Config) -> Self {
+        Effects {
+            reactions: Reactions::new(
+                config.feedback,
+                config.reduced_motion,
+                config.slow_motion,
+                crate::move_duration(),
+            ),
+            flash_until: None,
+            move_duration: crate::move_duration(),
+            music_enabled: config.music,
+            cues: config.audio_cues,
+            volume: config.volume.unwrap_or(DEFAULT_VOLUME),
+            muted: false,
+            sound: None,
+        }
+    }
+
+    pub fn start(&mut self) {
+        if (self.music_enabled || self.cues) && self.sound.is_none() {
+            self.sound = crate::audio::Sound::start(self.volume, self.music_on());
+        }
+    }
+
+    // Keys for the sound: m mutes the music, [ and ] change the volume of
+    // everything. Returns whether the key was one of them.
+    pub fn handle_key(&mut self, key: u8) -> bool {
+        match key {
+            109 => self.muted = !self.muted,
+            91 => self.volume = self.volume.saturating_sub(1),
+            93 => self.volume = (self.volume + 1).min(crate::audio::MAX_VOLUME),
+            _ => return false,
+        }
+
+        if let Some(sound) = &self.sound {
+            sound.set_music(self.music_on());
+            sound.set_volume(self.volume);
+        }
+        true
+    }
+
+    // Speeds the music up along with the game.
+    pub fn set_move_duration(&mut self, move_duration: std::time::Duration) {
+        self.move_duration = move_duration;
+        self.reactions.set_move_duration(move_duration);
+        if let Some(sound) = &self.sound {
+            sound.set_speed(crate::move_duration().as_secs_f32() / move_duration.as_secs_f32());
+        }
+    }
+
+    fn music_on(&self) -> bool {
+        self.music_enabled && !self.muted
+    }
+
+    // Reacts to the tick that just happened.
+    pub fn handle(&mut self, screen: &mut dyn Write, game: &Game) -> std::io::Result<()> {
+        if self.cues {
+            self.cue(game);
+        }
+        match self.reactions.handle(game) {
+            Some(Feedback::Bell) => write!(screen, "\x07"),
+            Some(Feedback::Flash) => {
+                self.flash_until = Some(std::time::Instant::now() + FLASH_DURATION);
+                write!(screen, "\x1b[?5h")
+            }
+            Some(Feedback::Off) | None => Ok(()),
+        }
+    }
+
+    // A ping for the nearest food, higher the closer it is and from the side
+    // it's on, and a low tone when the snake is about to crash. Only for the
+    // first player, two pings at once can't be told apart.
+    fn cue(&self, game: &Game) {
+        let Some(sound) = &self.sound else {
+            return;
+        };
+        let Some(player) = game.players.first() else {
+            return;
+        };
+        let head = player.head();
+
+        if let Some(food) = player.nearest_food(&game.food) {
+            let distance = head.0.abs_diff(food.0) + head.1.abs_diff(food.1);
+            let note = NEAR_NOTE.saturating_sub(distance.min(255) as u8);
+            let offset = food.0 as f32 - head.0 as f32;
+            sound.cue(crate::audio::Cue {
+                frequency: crate::audio::frequency(note.max(FAR_NOTE)).unwrap(),
+                duration: CUE_DURATION,
+                pan: offset / (game.board_size.0 as f32 / 2.0),
+            });
+        }
+
+        if player.alive && game.crash_ahead(0) {
+            sound.cue(crate::audio::Cue {
+                frequency: WARNING_FREQUENCY,
+                duration: CUE_DURATION,
+                pan: 0.0,
+            });
+        }
+    }
+
+    // How many times longer than usual the next tick should take.
+    pub fn time_scale(&mut self) -> u32 {
+        self.reactions.time_scale()
+    }
+
+    // Whether a flash is due to end, so the frame has to be drawn even if
+    // nothing else changed.
+    pub fn needs_render(&self) -> bool {
+        self.flash_until
+            .is_some_and(|until| until <= std::time::Instant::now())
+    }
+
+    // Ends a flash once it's been on long enough.
+    pub fn render(&mut self, screen: &mut dyn Write) -> std::io::Result<()> {
+        match self.flash_until {
+            Some(until) if until <= std::time::Instant::now() => {
+                self.flash_until = None;
+                write!(screen, "\x1b[?5l")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Stops the sound once the game is over, and ends a flash early, otherwise
+    // the terminal would stay inverted.
+    pub fn finish(&mut self, screen: &mut dyn Write) -> std::io::Result<()> {
+        self.sound = None;
+        self.reactions.finish();
+
+        match self.flash_until.take() {
+            Some(_) => write!(screen, "\x1b[?5l"),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,151 @@
+// `snake engine` plays by the real rules without a terminal, for bots written
+// in anything that can read and write lines of JSON. Every command on stdin
+// gets exactly one line back on stdout:
+//
+//   {"cmd": "new", "mode": "classic", "seed": 1234, "width": 40, "height": 20}
+//   {"cmd": "move", "player": 0, "direction": "up"}
+//   {"cmd": "tick"}
+//   {"cmd": "state"}
+//
+// Everything but `cmd` is optional for "new"; the mode defaults to classic,
+// the seed to a random one and the board to 40x20. "move" defaults to player
+// 0. The answer is the game's state, the same as spectators get plus whether
+// the game is over:
+//
+//   {"tick": 3, "board_size": [40, 20], "players": [...], "food": [...], "over": false}
+//
+// or {"error": "..."} when a command makes no sense, after which the engine
+// carries on reading.
+
+use crate::game::{Game, Input, Mode, MoveDirection};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "lowercase", deny_unknown_fields)]
+enum Command {
+    New {
+        #[serde(default = "default_mode")]
+        mode: Mode,
+        seed: Option<u64>,
+        #[serde(default = "default_width")]
+        width: u16,
+        #[serde(default = "default_height")]
+        height: u16,
+    },
+    Move {
+        #[serde(default)]
+        player: usize,
+        direction: MoveDirection,
+    },
+    Tick,
+    State,
+}
+
+fn default_mode() -> Mode {
+    Mode::Classic
+}
+
+fn default_width() -> u16 {
+    40
+}
+
+fn default_height() -> u16 {
+    20
+}
+
+#[derive(Serialize)]
+struct State {
+    #[serde(flatten)]
+    snapshot: crate::net::Snapshot,
+    over: bool,
+}
+
+#[derive(Serialize)]
+struct Error {
+    error: String,
+}
+
+struct Engine {
+    game: Option<Game>,
+    over: bool,
+}
+
+impl Engine {
+    fn handle(
+        &mut self,
+        command: Command,
+        spectators: Option<&crate::spectate::Spectators>,
+    ) -> Result<String, String> {
+        match command {
+            Command::New {
+                mode,
+                seed,
+                width,
+                height,
+            } => {
+                let (min_width, min_height) = crate::level::MIN_SIZE;
+                if width < min_width || height < min_height {
+                    return Err(format!(
+                        "the board has to be at least {}x{}",
+                        min_width, min_height
+                    ));
+                }
+                let seed = seed.unwrap_or_else(rand::random);
+                self.game = Some(Game::new(mode, seed, (width, height)));
+                self.over = false;
+            }
+            Command::Move { player, direction } => {
+                let game = self.game.as_mut().ok_or("no game, send \"new\" first")?;
+                if player >= game.players.len() {
+                    return Err(format!("there is no player {}", player));
+                }
+                game.handle_input(player, Input::Turn(direction));
+            }
+            Command::Tick => {
+                let game = self.game.as_mut().ok_or("no game, send \"new\" first")?;
+                if self.over {
+                    return Err("the game is over".to_string());
+                }
+                self.over = game.tick();
+
+                if let Some(spectators) = spectators {
+                    spectators.broadcast(&crate::net::Snapshot::new(game));
+                }
+            }
+            Command::State => {}
+        }
+
+        let game = self.game.as_ref().ok_or("no game, send \"new\" first")?;
+        let state = State {
+            snapshot: crate::net::Snapshot::new(game),
+            over: self.over,
+        };
+        Ok(serde_json::to_string(&state).unwrap())
+    }
+}
+
+pub fn run(spectators: Option<&crate::spectate::Spectators>) -> Result<(), std::io::Error> {
+    let mut engine = Engine {
+        game: None,
+        over: false,
+    };
+    let mut stdout = std::io::stdout().lock();
+
+    for line in std::io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let reply = serde_json::from_str(&line)
+            .map_err(|e| e.to_string())
+            .and_then(|command| engine.handle(command, spectators))
+            .unwrap_or_else(|error| serde_json::to_string(&Error { error }).unwrap());
+
+        writeln!(stdout, "{}", reply)?;
+        stdout.flush()?;
+    }
+
+    Ok(())
+}
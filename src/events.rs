@@ -0,0 +1,162 @@
+// Things that happen now and then for a while and then stop, like a frenzy of
+// food. One goes at a time, picked by weight out of `Game::random_events`.
+// Adding one is a matter of implementing `RandomEvent` and putting it at the
+// end of `ALL`, which games are saved with indices into.
+
+use crate::game::Game;
+use crate::random::Rng;
+use serde::{Deserialize, Serialize};
+
+// There's a chance of one starting on one tick in this many, when there isn't
+// one going on.
+pub const CHANCE: u32 = 500;
+
+// Ten seconds at the usual speed.
+pub const FRENZY_TICKS: u32 = 67;
+// Moves take this much of the usual time in a speed-up.
+const SPEED_UP_SCALE: f64 = 0.6;
+// How far from a head the board can be seen in the dark.
+pub const DARK_VISION: u16 = 3;
+
+pub trait RandomEvent: Sync {
+    // Shown on the board while it lasts, with its spaces.
+    fn announcement(&self) -> &'static str;
+
+    // How likely it is to be picked next to the others.
+    fn weight(&self) -> u32;
+
+    // How many ticks it lasts.
+    fn ticks(&self) -> u32;
+
+    // Called once it's in `Game::random_event`.
+    fn start(&self, _game: &mut Game) {}
+
+    // Called once it's over, to take away whatever `start` left.
+    fn end(&self, _game: &mut Game) {}
+
+    // How much of the usual time moves take while it lasts.
+    fn time_scale(&self) -> f64 {
+        1.0
+    }
+
+    // Whether the keys turn the opposite way.
+    fn reversed(&self) -> bool {
+        false
+    }
+
+    // How far around the heads anything can be seen, if not everywhere.
+    fn vision(&self) -> Option<u16> {
+        None
+    }
+}
+
+pub struct Frenzy;
+
+impl RandomEvent for Frenzy {
+    fn announcement(&self) -> &'static str {
+        " Frenzy! Double points "
+    }
+
+    fn weight(&self) -> u32 {
+        3
+    }
+
+    fn ticks(&self) -> u32 {
+        FRENZY_TICKS
+    }
+
+    fn start(&self, game: &mut Game) {
+        game.drop_frenzy_food();
+    }
+
+    fn end(&self, game: &mut Game) {
+        game.food.retain(|f| !f.frenzy);
+    }
+}
+
+pub struct SpeedUp;
+
+impl RandomEvent for SpeedUp {
+    fn announcement(&self) -> &'static str {
+        " Speed up! "
+    }
+
+    fn weight(&self) -> u32 {
+        2
+    }
+
+    fn ticks(&self) -> u32 {
+        80
+    }
+
+    fn time_scale(&self) -> f64 {
+        SPEED_UP_SCALE
+    }
+}
+
+pub struct Darkness;
+
+impl RandomEvent for Darkness {
+    fn announcement(&self) -> &'static str {
+        " Darkness "
+    }
+
+    fn weight(&self) -> u32 {
+        2
+    }
+
+    fn ticks(&self) -> u32 {
+        40
+    }
+
+    fn vision(&self) -> Option<u16> {
+        Some(DARK_VISION)
+    }
+}
+
+pub struct Reversed;
+
+impl RandomEvent for Reversed {
+    fn announcement(&self) -> &'static str {
+        " Controls reversed "
+    }
+
+    fn weight(&self) -> u32 {
+        1
+    }
+
+    fn ticks(&self) -> u32 {
+        40
+    }
+
+    fn reversed(&self) -> bool {
+        true
+    }
+}
+
+pub static ALL: [&dyn RandomEvent; 4] = [&Frenzy, &SpeedUp, &Darkness, &Reversed];
+
+// The one going on, by its index in `ALL`.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Running {
+    pub index: usize,
+    pub ends: u32,
+}
+
+// One of `indices` into `ALL`, the heavier ones more often. With only one to
+// pick there's nothing random, which keeps games from before there was a
+// choice the same.
+pub fn choose(indices: &[usize], rng: &mut dyn Rng) -> usize {
+    if let [index] = indices {
+        return *index;
+    }
+    let total: u32 = indices.iter().map(|&i| ALL[i].weight()).sum();
+    let mut roll = rng.below(total);
+    for &index in indices {
+        match roll.checked_sub(ALL[index].weight()) {
+            Some(rest) => roll = rest,
+            None => return index,
+        }
+    }
+    unreachable!("the roll is below the total")
+}
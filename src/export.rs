@@ -0,0 +1,188 @@
+// Turns a replay into something to share: an asciinema cast of the escape
+// codes the terminal would have got, or a GIF drawn cell by cell. Both play
+// the replay back on their own, without a terminal.
+
+use crate::game::{Coordinate, Game};
+use std::io::Write;
+
+// Terminal cells are about twice as tall as they are wide.
+const CELL_WIDTH: u16 = 4;
+const CELL_HEIGHT: u16 = 8;
+
+// The colors the terminal draws with, in the order of the indices below.
+const PALETTE: [(u8, u8, u8); 10] = [
+    (0, 0, 0),
+    (0, 255, 0),
+    (255, 255, 255),
+    (0, 128, 255),
+    (128, 200, 255),
+    (255, 0, 0),
+    (128, 128, 128),
+    (160, 0, 255),
+    (0, 200, 200),
+    (255, 160, 0),
+];
+const HEADS: [u8; 2] = [1, 3];
+const BODIES: [u8; 2] = [2, 4];
+const FOOD: u8 = 5;
+const WALL: u8 = 6;
+const PORTALS: [u8; 3] = [7, 8, 9];
+
+#[derive(Copy, Clone)]
+pub enum Format {
+    Cast,
+    Gif,
+}
+
+impl Format {
+    pub fn from_path(path: &std::path::Path) -> Option<Format> {
+        match path.extension()?.to_str()? {
+            "cast" => Some(Format::Cast),
+            "gif" => Some(Format::Gif),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Cast => "cast",
+            Format::Gif => "gif",
+        }
+    }
+}
+
+pub fn export(
+    replay: &crate::replay::Replay,
+    format: Format,
+    path: &std::path::Path,
+) -> Result<(), String> {
+    let file = std::fs::File::create(path).map_err(|e| e.to_string())?;
+    let mut out = std::io::BufWriter::new(file);
+
+    match format {
+        Format::Cast => cast(replay, &mut out)?,
+        Format::Gif => gif(replay, &mut out)?,
+    }
+
+    out.flush().map_err(|e| e.to_string())
+}
+
+// Calls `frame` for the board before the first move and after every tick.
+fn frames(
+    replay: &crate::replay::Replay,
+    mut frame: impl FnMut(&Game) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut game = replay.game();
+    frame(&game)?;
+
+    while game.tick < replay.length {
+        for input in replay.inputs_at(game.tick) {
+            game.handle_input(0, input);
+        }
+        let over = game.tick();
+        frame(&game)?;
+
+        if over {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+// asciinema's v2 format: a header line, then one line of output per frame.
+fn cast(replay: &crate::replay::Replay, out: &mut dyn Write) -> Result<(), String> {
+    let (width, height) = crate::board_screen_size(replay.board_size);
+    writeln!(
+        out,
+        "{}",
+        serde_json::json!({"version": 2, "width": width, "height": height})
+    )
+    .map_err(|e| e.to_string())?;
+
+    let mut time = 0.0;
+    let mut move_duration = crate::replay_move_duration(replay);
+    frames(replay, |game| {
+        move_duration = replay
+            .speed_changes_at(game.tick)
+            .fold(move_duration, crate::change_speed);
+        let mut screen = Vec::new();
+        crate::clear(&mut screen).unwrap();
+        crate::render_game(&mut screen, game).unwrap();
+
+        let event = serde_json::json!([time, "o", String::from_utf8_lossy(&screen)]);
+        time += move_duration.as_secs_f64();
+        writeln!(out, "{}", event).map_err(|e| e.to_string())
+    })
+}
+
+fn gif(replay: &crate::replay::Replay, out: &mut dyn Write) -> Result<(), String> {
+    let (width, height) = replay.board_size;
+    let (image_width, image_height) = (width * CELL_WIDTH, height * CELL_HEIGHT);
+
+    // Palettes come in powers of two.
+    let mut palette: Vec<u8> = PALETTE.iter().flat_map(|&(r, g, b)| [r, g, b]).collect();
+    palette.resize(16 * 3, 0);
+
+    let mut encoder =
+        gif::Encoder::new(out, image_width, image_height, &palette).map_err(|e| e.to_string())?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(|e| e.to_string())?;
+
+    let mut move_duration = crate::replay_move_duration(replay);
+    frames(replay, |game| {
+        move_duration = replay
+            .speed_changes_at(game.tick)
+            .fold(move_duration, crate::change_speed);
+        let mut pixels = vec![0; image_width as usize * image_height as usize];
+        let mut fill = |Coordinate(x, y): Coordinate, color: u8| {
+            if x < 1 || y < 1 || x > width || y > height {
+                return;
+            }
+
+            let left = (x - 1) as usize * CELL_WIDTH as usize;
+            let top = (y - 1) as usize * CELL_HEIGHT as usize;
+            for row in top..top + CELL_HEIGHT as usize {
+                let start = row * image_width as usize + left;
+                pixels[start..start + CELL_WIDTH as usize].fill(color);
+            }
+        };
+
+        for wall in game
+            .level
+            .walls
+            .iter()
+            .copied()
+            .chain(game.level.mask_edge())
+        {
+            fill(wall, WALL);
+        }
+        for (index, (a, b)) in game.level.portals.iter().enumerate() {
+            fill(*a, PORTALS[index % PORTALS.len()]);
+            fill(*b, PORTALS[index % PORTALS.len()]);
+        }
+        for food in &game.food {
+            for cell in food.cells() {
+                fill(cell, FOOD);
+            }
+        }
+        for (index, player) in game.players.iter().enumerate() {
+            let index = index.min(HEADS.len() - 1);
+            for (segment, coordinate) in player.segments.iter().enumerate().rev() {
+                fill(
+                    *coordinate,
+                    if segment == 0 {
+                        HEADS[index]
+                    } else {
+                        BODIES[index]
+                    },
+                );
+            }
+        }
+
+        let mut frame = gif::Frame::from_indexed_pixels(image_width, image_height, pixels, None);
+        frame.delay = (move_duration.as_millis() / 10) as u16;
+        encoder.write_frame(&frame).map_err(|e| e.to_string())
+    })
+}
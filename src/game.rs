@@ -0,0 +1,1405 @@
+use crate::level::{Level, TerrainKind, Topology};
+use crate::random::Rng;
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MoveDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+    // Only in diagonal games, see `Game::diagonal`.
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl MoveDirection {
+    pub const STRAIGHT: [MoveDirection; 4] = [
+        MoveDirection::Up,
+        MoveDirection::Down,
+        MoveDirection::Left,
+        MoveDirection::Right,
+    ];
+    pub const ALL: [MoveDirection; 8] = [
+        MoveDirection::Up,
+        MoveDirection::Down,
+        MoveDirection::Left,
+        MoveDirection::Right,
+        MoveDirection::UpLeft,
+        MoveDirection::UpRight,
+        MoveDirection::DownLeft,
+        MoveDirection::DownRight,
+    ];
+
+    // How far a move goes across and down.
+    pub fn delta(self) -> (i16, i16) {
+        match self {
+            MoveDirection::Up => (0, -1),
+            MoveDirection::Down => (0, 1),
+            MoveDirection::Left => (-1, 0),
+            MoveDirection::Right => (1, 0),
+            MoveDirection::UpLeft => (-1, -1),
+            MoveDirection::UpRight => (1, -1),
+            MoveDirection::DownLeft => (-1, 1),
+            MoveDirection::DownRight => (1, 1),
+        }
+    }
+
+    pub fn from_delta(delta: (i16, i16)) -> Option<MoveDirection> {
+        MoveDirection::ALL.into_iter().find(|d| d.delta() == delta)
+    }
+
+    pub fn opposite(self) -> MoveDirection {
+        let (dx, dy) = self.delta();
+        MoveDirection::from_delta((-dx, -dy)).unwrap()
+    }
+
+    pub fn is_diagonal(self) -> bool {
+        let (dx, dy) = self.delta();
+        dx != 0 && dy != 0
+    }
+
+    // The straight moves across and up or down that a diagonal one cuts
+    // between.
+    pub fn parts(self) -> Option<(MoveDirection, MoveDirection)> {
+        let (dx, dy) = self.delta();
+        match self.is_diagonal() {
+            true => Some((
+                MoveDirection::from_delta((dx, 0))?,
+                MoveDirection::from_delta((0, dy))?,
+            )),
+            false => None,
+        }
+    }
+}
+
+// Boards start at 1,1 in the top left, so everything that has 0 in it is
+// off the board.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct Coordinate(pub u16, pub u16);
+
+impl Coordinate {
+    // The next cell over in `direction`. Stepping off the top or the left of
+    // the board ends up off the board too, rather than overflowing.
+    pub fn step(self, direction: MoveDirection) -> Coordinate {
+        let Coordinate(x, y) = self;
+        let (dx, dy) = direction.delta();
+        Coordinate(x.saturating_add_signed(dx), y.saturating_add_signed(dy))
+    }
+
+    // Which way `other` is from here, if it's right next to it.
+    pub fn direction_to(self, other: Coordinate) -> Option<MoveDirection> {
+        MoveDirection::ALL
+            .into_iter()
+            .find(|d| self.step(*d) == other && self != other)
+    }
+
+    pub fn on_board(self, (width, height): (u16, u16)) -> bool {
+        (1..=width).contains(&self.0) && (1..=height).contains(&self.1)
+    }
+}
+
+impl std::fmt::Display for Coordinate {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "({},{})", self.0, self.1)
+    }
+}
+
+// The cells of a board and how they connect. Everything that moves a snake
+// or looks ahead for one goes through `step`, so the edges work the same for
+// all of them.
+#[derive(Copy, Clone)]
+pub struct Board {
+    pub size: (u16, u16),
+    pub topology: Topology,
+}
+
+impl Board {
+    // The next cell over in `direction`, round to the other side over an
+    // edge that wraps, and off the board over one that doesn't.
+    pub fn step(&self, coordinate: Coordinate, direction: MoveDirection) -> Coordinate {
+        let Coordinate(x, y) = coordinate;
+        let (width, height) = self.size;
+        let (across, down) = self.topology.wraps();
+        let (dx, dy) = direction.delta();
+        let x = match dx {
+            -1 if across && x <= 1 => width,
+            1 if across && x >= width => 1,
+            _ => x.saturating_add_signed(dx),
+        };
+        let y = match dy {
+            -1 if down && y <= 1 => height,
+            1 if down && y >= height => 1,
+            _ => y.saturating_add_signed(dy),
+        };
+        Coordinate(x, y)
+    }
+}
+
+#[derive(Copy, Clone, Default, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FoodKind {
+    #[default]
+    Apple,
+    Cherry,
+    Mouse,
+    // Takes up a square of `FEAST_SIZE` cells across, and eating any of them
+    // eats all of it.
+    Feast,
+}
+
+const FEAST_SIZE: u16 = 2;
+// One food in this many that comes back after being eaten is a feast, when
+// there's room for one.
+const FEAST_CHANCE: u32 = 25;
+
+impl FoodKind {
+    pub fn points(self) -> u32 {
+        match self {
+            FoodKind::Apple => 1,
+            FoodKind::Cherry => 2,
+            FoodKind::Mouse => 3,
+            FoodKind::Feast => 10,
+        }
+    }
+
+    // How many cells across food of this kind is.
+    pub fn size(self) -> u16 {
+        match self {
+            FoodKind::Feast => FEAST_SIZE,
+            _ => 1,
+        }
+    }
+
+    // The more a kind is worth, the rarer it is: mice are one food in 20,
+    // cherries three.
+    fn random(rng: &mut dyn Rng, feasts: bool) -> FoodKind {
+        if feasts && rng.below(FEAST_CHANCE) == 0 {
+            return FoodKind::Feast;
+        }
+        match rng.below(20) {
+            0 => FoodKind::Mouse,
+            1..=3 => FoodKind::Cherry,
+            _ => FoodKind::Apple,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct Food {
+    pub location: Coordinate,
+    #[serde(default)]
+    pub kind: FoodKind,
+    // The tick food in a formation goes away on if it's still there. Eaten,
+    // it's gone rather than coming back somewhere else.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<u32>,
+    // Dropped in a frenzy, see `events::Frenzy`, and worth double. Goes when
+    // the frenzy does.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub frenzy: bool,
+}
+
+impl Food {
+    pub fn new(location: Coordinate) -> Self {
+        Food {
+            location,
+            kind: FoodKind::Apple,
+            expires: None,
+            frenzy: false,
+        }
+    }
+
+    pub fn in_formation(&self) -> bool {
+        self.expires.is_some() && !self.frenzy
+    }
+
+    pub fn points(&self) -> u32 {
+        match self.frenzy {
+            true => self.kind.points() * 2,
+            false => self.kind.points(),
+        }
+    }
+
+    // Every cell the food takes up, `location` being the top left one.
+    pub fn cells(&self) -> impl Iterator<Item = Coordinate> {
+        let Coordinate(x, y) = self.location;
+        let size = self.kind.size();
+        (0..size).flat_map(move |dy| (0..size).map(move |dx| Coordinate(x + dx, y + dy)))
+    }
+
+    pub fn covers(&self, coordinate: Coordinate) -> bool {
+        let Coordinate(x, y) = self.location;
+        let size = self.kind.size();
+        (x..x + size).contains(&coordinate.0) && (y..y + size).contains(&coordinate.1)
+    }
+}
+
+// Now and then food turns up in one of these shapes all at once, on top of
+// the usual food. Eating all of it before it goes is worth a bonus of a point
+// for every piece.
+const FORMATIONS: [&[&str]; 5] = [
+    &["#####"],
+    &["#", "#", "#", "#", "#"],
+    &["###", "# #", "###"],
+    &["###", "#  ", "###", "  #", "###"],
+    &["# #", "# #", "###", "# #", "# #"],
+];
+// Now and then the letters of `WORD` turn up all over the board, to be eaten
+// in order before they go. Spelling it is worth a bonus and a life, eating one
+// out of order and the rest are gone.
+pub const WORD: &str = "SNAKE";
+// Letters turn up on one tick in this many, when there aren't any.
+const LETTER_CHANCE: u32 = 300;
+const LETTER_TICKS: u32 = 200;
+pub const LETTER_BONUS: u32 = 25;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Letters {
+    // What's still to eat and where, the next one first.
+    pub left: Vec<(char, Coordinate)>,
+    // The tick they go away on if they're still there.
+    pub expires: u32,
+}
+
+// In wind games the wind pushes every snake a cell along on one tick in this
+// many, and turns on one in this many.
+const GUST_TICKS: u32 = 4;
+const WIND_TURN_TICKS: u32 = 60;
+
+// A frenzy drops this much food at once, worth double.
+pub const FRENZY_FOOD: usize = 12;
+
+// A formation turns up on one tick in this many, when there isn't one.
+const FORMATION_CHANCE: u32 = 150;
+const FORMATION_TICKS: u32 = 100;
+// How long a bridge power-up lets a snake cross water.
+pub const BRIDGE_TICKS: u32 = 40;
+
+// `segments` is public to be read, it only changes through the methods below
+// so it stays in sync with `occupied`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "PlayerFields")]
+pub struct Player {
+    pub move_direction: MoveDirection,
+    pub segments: std::collections::VecDeque<Coordinate>,
+    pub score: u32,
+    pub alive: bool,
+    // Crashes left before dying, counting the last one.
+    pub lives: u32,
+    // Ticks spent waiting in front of a crash, see `Game::grace`.
+    #[serde(skip_serializing_if = "is_zero")]
+    hesitated: u32,
+    // Cells still to grow by, see `grow`.
+    #[serde(skip_serializing_if = "is_zero")]
+    growth: u32,
+    // Ticks left that water can be crossed, from a bridge power-up.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub bridging: u32,
+    // The same cells as `segments`, so collisions don't have to go through
+    // the whole snake, with how many segments are on each. Only a bridge has
+    // more than one.
+    #[serde(skip)]
+    occupied: std::collections::HashMap<Coordinate, u32>,
+}
+
+// A player as it's saved and sent to spectators, without `occupied`.
+#[derive(Deserialize)]
+struct PlayerFields {
+    move_direction: MoveDirection,
+    segments: std::collections::VecDeque<Coordinate>,
+    score: u32,
+    alive: bool,
+    #[serde(default = "default_lives")]
+    lives: u32,
+    #[serde(default)]
+    hesitated: u32,
+    #[serde(default)]
+    growth: u32,
+    #[serde(default)]
+    bridging: u32,
+}
+
+fn default_lives() -> u32 {
+    1
+}
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+impl From<PlayerFields> for Player {
+    fn from(fields: PlayerFields) -> Self {
+        let mut player = Player::from_segments(fields.move_direction, fields.segments);
+        player.score = fields.score;
+        player.alive = fields.alive;
+        player.lives = fields.lives;
+        player.hesitated = fields.hesitated;
+        player.growth = fields.growth;
+        player.bridging = fields.bridging;
+        player
+    }
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Player::new()
+    }
+}
+
+impl Player {
+    pub fn new() -> Self {
+        Player::at(Coordinate(4, 1), MoveDirection::Right, 4)
+    }
+
+    // A straight snake with its head at `head`, trailing away from `direction`.
+    pub fn at(head: Coordinate, direction: MoveDirection, length: u16) -> Self {
+        let (dx, dy) = direction.opposite().delta();
+        let segments = (0..length as i16)
+            .map(|i| {
+                Coordinate(
+                    head.0.saturating_add_signed(dx * i),
+                    head.1.saturating_add_signed(dy * i),
+                )
+            })
+            .collect();
+
+        Player::from_segments(direction, segments)
+    }
+
+    // A snake made of `segments`, head first.
+    pub fn from_segments(
+        move_direction: MoveDirection,
+        segments: std::collections::VecDeque<Coordinate>,
+    ) -> Self {
+        let mut occupied = std::collections::HashMap::new();
+        for segment in &segments {
+            *occupied.entry(*segment).or_insert(0) += 1;
+        }
+        Player {
+            move_direction,
+            occupied,
+            segments,
+            score: 0,
+            alive: true,
+            lives: default_lives(),
+            hesitated: 0,
+            growth: 0,
+            bridging: 0,
+        }
+    }
+
+    pub fn head(&self) -> Coordinate {
+        self.segments[0]
+    }
+
+    pub fn next_head(&self, board: &Board) -> Coordinate {
+        board.step(self.head(), self.move_direction)
+    }
+
+    // The closest cell with food on it, counting moves rather than as the
+    // crow flies and leaving out portals.
+    pub fn nearest_food(&self, food: &[Food]) -> Option<Coordinate> {
+        let head = self.head();
+        food.iter()
+            .flat_map(|f| f.cells())
+            .min_by_key(|c| head.0.abs_diff(c.0) + head.1.abs_diff(c.1))
+    }
+
+    // Turning back into its own neck is ignored.
+    pub fn change_direction(&mut self, new_direction: MoveDirection) {
+        if new_direction != self.move_direction.opposite() {
+            self.move_direction = new_direction;
+        }
+    }
+
+    pub fn check_collisions(&self, coord: &Coordinate, screen_size: &(u16, u16)) -> bool {
+        !coord.on_board(*screen_size) || self.occupied.contains_key(coord)
+    }
+
+    // How many of its segments are on `coord`.
+    pub fn layers(&self, coord: &Coordinate) -> u32 {
+        self.occupied.get(coord).copied().unwrap_or(0)
+    }
+
+    // Makes the snake `cells` longer, one cell a move as its tail stays put.
+    pub fn grow(&mut self, cells: u32) {
+        self.growth = self.growth.saturating_add(cells);
+    }
+
+    // How snakes grew before `grow`: a cell stuck on behind the tail straight
+    // away, or nothing if that cell wasn't free. Only for old replays, which
+    // never had portals, so the tail is always a straight step.
+    fn grow_behind_tail(&mut self, screen_size: &(u16, u16)) {
+        let last_segment = *self.segments.back().unwrap();
+
+        let direction = match self.segments.iter().nth_back(1) {
+            Some(second_last) => match second_last.direction_to(last_segment) {
+                Some(direction) => direction,
+                None => return,
+            },
+            None => self.move_direction,
+        };
+        let new_segment = last_segment.step(direction);
+
+        if !self.check_collisions(&new_segment, screen_size) {
+            self.segments.push_back(new_segment);
+            *self.occupied.entry(new_segment).or_insert(0) += 1;
+        }
+    }
+
+    // The head doesn't have to end up next to where it was, for portals.
+    pub fn move_to(&mut self, head: Coordinate) {
+        if self.growth > 0 {
+            self.growth -= 1;
+        } else if let Some(tail) = self.segments.pop_back() {
+            if let Some(layers) = self.occupied.get_mut(&tail) {
+                *layers -= 1;
+                if *layers == 0 {
+                    self.occupied.remove(&tail);
+                }
+            }
+        }
+        self.segments.push_front(head);
+        *self.occupied.entry(head).or_insert(0) += 1;
+    }
+}
+
+// How food finds somewhere to go. It used to try random cells until one was
+// free, which takes longer and longer as the board fills up and never ends on
+// a full one, but replays from back then have to play out the same way.
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FoodPlacement {
+    Retry,
+    FreeCells,
+}
+
+// How snakes get longer, see `Player::grow`. Replays from before snakes grew
+// from the tail have to play out the same way.
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Growth {
+    BehindTail,
+    Pending,
+}
+
+// Where food `size` cells across can go, the cell in its top left, or none if
+// there's nowhere with enough room.
+fn food_location(
+    placement: FoodPlacement,
+    level: &Level,
+    players: &[Player],
+    food: &[Food],
+    size: u16,
+    rng: &mut dyn Rng,
+) -> Option<Coordinate> {
+    if placement == FoodPlacement::Retry {
+        return Some(retry_location(level, players, rng));
+    }
+
+    let (w, h) = level.size;
+    let is_free = |c: &Coordinate| {
+        c.on_board(level.size)
+            && level.allows_food(c)
+            && !players.iter().any(|p| p.check_collisions(c, &level.size))
+            && !food.iter().any(|f| f.covers(*c))
+    };
+    let free: Vec<Coordinate> = (1..=w)
+        .flat_map(|x| (1..=h).map(move |y| Coordinate(x, y)))
+        .filter(|Coordinate(x, y)| {
+            (0..size).all(|dx| (0..size).all(|dy| is_free(&Coordinate(x + dx, y + dy))))
+        })
+        .collect();
+
+    if level.food_weights.is_empty() {
+        return match free.len() {
+            0 => None,
+            n => Some(free[rng.below(n as u32) as usize]),
+        };
+    }
+
+    // Every cell takes up as many numbers as its weight, in order, and the
+    // one the picked number falls in gets the food.
+    let weights: Vec<u32> = free.iter().map(|c| level.food_weight(c)).collect();
+    let mut pick = match weights.iter().sum() {
+        0 => return None,
+        total => rng.below(total),
+    };
+    for (cell, weight) in free.iter().zip(weights) {
+        if pick < weight {
+            return Some(*cell);
+        }
+        pick -= weight;
+    }
+    None
+}
+
+fn retry_location(level: &Level, players: &[Player], rng: &mut dyn Rng) -> Coordinate {
+    let screen = &level.size;
+    let mut x = 0;
+    let mut y = 0;
+
+    while players
+        .iter()
+        .any(|p| p.check_collisions(&Coordinate(x, y), screen))
+        || !level.allows_food(&Coordinate(x, y))
+    {
+        x = 1 + rng.below(screen.0 as u32 - 1) as u16;
+        y = 1 + rng.below(screen.1 as u32 - 1) as u16;
+    }
+
+    Coordinate(x, y)
+}
+
+// `n` of `cells` drawn at random, or all of them in some order if there aren't
+// that many.
+fn pick(rng: &mut dyn Rng, mut cells: Vec<Coordinate>, n: usize) -> Vec<Coordinate> {
+    let n = n.min(cells.len());
+    (0..n)
+        .map(|_| cells.remove(rng.below(cells.len() as u32) as usize))
+        .collect()
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    Classic,
+    Versus,
+    // One player steering two snakes, one at a time.
+    Twins,
+    // Two players on the same side, scoring together.
+    Coop,
+}
+
+impl Mode {
+    pub fn id(&self) -> u8 {
+        match self {
+            Mode::Classic => 0,
+            Mode::Versus => 1,
+            Mode::Twins => 2,
+            Mode::Coop => 3,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Mode> {
+        match id {
+            0 => Some(Mode::Classic),
+            1 => Some(Mode::Versus),
+            2 => Some(Mode::Twins),
+            3 => Some(Mode::Coop),
+            _ => None,
+        }
+    }
+
+    // The same as it's serialized as.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Classic => "classic",
+            Mode::Versus => "versus",
+            Mode::Twins => "twins",
+            Mode::Coop => "coop",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Mode> {
+        (0..4)
+            .filter_map(Mode::from_id)
+            .find(|mode| mode.name() == name)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Input {
+    Turn(MoveDirection),
+    Elongate,
+    // Steer the other snake, in twins mode.
+    Swap,
+}
+
+// What a snake does at the end of a tick.
+#[derive(Copy, Clone)]
+enum Outcome {
+    Move,
+    Stay,
+    // Lost a life and starts over.
+    Respawn,
+}
+
+// Something that happened during a tick, for whatever wants to react to it
+// without being part of the rules, like scripts. `food` is an index into
+// `Game::food`, which has already been moved elsewhere.
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Event {
+    // `points` is what the food was worth, it's been replaced by the time
+    // this is seen.
+    FoodEaten {
+        player: usize,
+        food: usize,
+        points: u32,
+    },
+    // Food in a formation, which isn't replaced.
+    FormationFoodEaten {
+        player: usize,
+        points: u32,
+    },
+    // The last of a formation, eaten in time by `player`.
+    FormationEaten {
+        player: usize,
+        bonus: u32,
+    },
+    FormationExpired,
+    Died {
+        player: usize,
+    },
+    // There's nowhere left for food to go, which ends the game as a win. The
+    // food that was just eaten stays where it was.
+    BoardFull,
+    // Crashed, but had a life to spare and starts over where it first did.
+    LostLife {
+        player: usize,
+    },
+    // The next letter of `WORD`.
+    LetterEaten {
+        player: usize,
+        letter: char,
+    },
+    // The last letter of `WORD`, worth `LETTER_BONUS` and a life.
+    WordSpelled {
+        player: usize,
+    },
+    // A letter was eaten out of order, or they weren't eaten in time.
+    LettersLost,
+    // `index` is into `events::ALL`.
+    RandomEventStarted {
+        index: usize,
+    },
+    RandomEventEnded {
+        index: usize,
+    },
+    // Food dropped in a frenzy, which isn't replaced.
+    FrenzyFoodEaten {
+        player: usize,
+        points: u32,
+    },
+    // Got to the key of a door, which is open now.
+    DoorOpened {
+        player: usize,
+    },
+    // Crashed into something in bumper mode, and stopped instead of dying.
+    Bumped {
+        player: usize,
+    },
+}
+
+// Everything needed to run a game without a terminal. Given the same mode,
+// seed, board size and inputs on the same ticks, a game always plays out the
+// same way, which is what replays rely on. Serialized with where its random
+// numbers are up to, so a saved game carries on as it would have, unless
+// they came from something that can't be started again, see `Rng::state`.
+//
+// Saved games outlive the build that saved them, so fields added from now on
+// need a `#[serde(default)]` that plays the way games did without them, like
+// replays have versions for. Renaming or removing one breaks older saves.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Game {
+    pub mode: Mode,
+    pub seed: u64,
+    pub board_size: (u16, u16),
+    pub players: Vec<Player>,
+    pub food: Vec<Food>,
+    pub tick: u32,
+    pub level: Level,
+    // What happened during the last tick.
+    pub events: Vec<Event>,
+    pub food_placement: FoodPlacement,
+    // Easy mode: snakes stop in front of whatever they would crash into, and
+    // can turn away and carry on.
+    pub bumper: bool,
+    // How many ticks a snake about to crash waits for a turn first, so one a
+    // little late at high speeds still counts. Holding the turn it has is the
+    // same as crashing, just later.
+    pub grace: u32,
+    pub growth: Growth,
+    // The snake that input for the first player goes to, in twins mode. The
+    // other one keeps going the way it was.
+    pub active: usize,
+    // Whether food comes in kinds worth more than 1, see `set_food_variants`.
+    pub food_variants: bool,
+    // Whether food turns up in formations now and then, off in older replays.
+    pub formations: bool,
+    // Whether eaten food can come back as a feast, off in older replays.
+    pub feasts: bool,
+    // Whether snakes can go diagonally too. They can't squeeze between two
+    // cells that are both taken, like across the bend of a snake.
+    #[serde(default)]
+    pub diagonal: bool,
+    // Which way the wind blows, in wind games, see `blow`.
+    #[serde(default)]
+    pub wind: Option<MoveDirection>,
+    // Whether letters turn up now and then, off in older replays.
+    #[serde(default)]
+    pub letter_rounds: bool,
+    #[serde(default)]
+    pub letters: Option<Letters>,
+    // Which of `events::ALL` can happen now and then, by index. None in older
+    // replays, and only frenzies in the ones from before there were others.
+    #[serde(default)]
+    pub random_events: Vec<usize>,
+    #[serde(default)]
+    pub random_event: Option<crate::events::Running>,
+    // How much food the last formation started with, which is its bonus.
+    formation_size: u32,
+    #[serde(serialize_with = "save_rng", deserialize_with = "load_rng")]
+    rng: Box<dyn Rng>,
+    // What kind food is and formations come from their own random numbers,
+    // so where the usual food goes is the same as it was before either.
+    #[serde(serialize_with = "save_rng", deserialize_with = "load_rng")]
+    food_rng: Box<dyn Rng>,
+}
+
+fn save_rng<R, S>(rng: &R, serializer: S) -> Result<S::Ok, S::Error>
+where
+    R: std::ops::Deref<Target = dyn Rng>,
+    S: serde::Serializer,
+{
+    rng.state().serialize(serializer)
+}
+
+// Numbers that can't be started again go on from somewhere else.
+fn load_rng<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Box<dyn Rng>, D::Error> {
+    Ok(match Option::<(u64, u64)>::deserialize(deserializer)? {
+        Some((seed, position)) => Box::new(crate::random::Seeded::resume(seed, position)),
+        None => Box::new(crate::random::Entropy),
+    })
+}
+
+impl Game {
+    pub fn new(mode: Mode, seed: u64, board_size: (u16, u16)) -> Self {
+        Game::with_level(mode, seed, Level::empty(board_size))
+    }
+
+    pub fn board(&self) -> Board {
+        Board {
+            size: self.board_size,
+            topology: self.level.topology,
+        }
+    }
+
+    pub fn with_level(mode: Mode, seed: u64, level: Level) -> Self {
+        Game::with_food_placement(mode, seed, level, FoodPlacement::FreeCells)
+    }
+
+    pub fn with_food_placement(
+        mode: Mode,
+        seed: u64,
+        level: Level,
+        food_placement: FoodPlacement,
+    ) -> Self {
+        let rng = Box::new(crate::random::Seeded::new(seed));
+        Game::with_rng(mode, seed, level, food_placement, rng)
+    }
+
+    // A game that gets its random numbers from `rng` instead of from `seed`,
+    // which is then only kept to be shown. Replays of it don't play back the
+    // same unless `rng` is seeded with it.
+    pub fn with_rng(
+        mode: Mode,
+        seed: u64,
+        level: Level,
+        food_placement: FoodPlacement,
+        mut rng: Box<dyn Rng>,
+    ) -> Self {
+        let board_size = level.size;
+        let mut food_rng = rng.split();
+        let count = match mode {
+            Mode::Classic => 1,
+            Mode::Versus | Mode::Twins | Mode::Coop => 2,
+        };
+        let players: Vec<Player> = (0..count)
+            .map(|index| {
+                let start = level.start(index);
+                Player::at(start.head, start.direction, 4)
+            })
+            .collect();
+        let mut food = Vec::new();
+        for _ in 0..level.food {
+            // Feasts only ever come back in place of eaten food.
+            match food_location(food_placement, &level, &players, &food, 1, rng.as_mut()) {
+                Some(location) => food.push(Food {
+                    kind: FoodKind::random(food_rng.as_mut(), false),
+                    ..Food::new(location)
+                }),
+                None => break,
+            }
+        }
+
+        Game {
+            mode,
+            seed,
+            board_size,
+            players,
+            food,
+            tick: 0,
+            level,
+            events: Vec::new(),
+            food_placement,
+            bumper: false,
+            grace: 0,
+            growth: Growth::Pending,
+            active: 0,
+            food_variants: true,
+            formations: true,
+            feasts: true,
+            diagonal: false,
+            wind: None,
+            letter_rounds: true,
+            letters: None,
+            random_events: (0..crate::events::ALL.len()).collect(),
+            random_event: None,
+            formation_size: 0,
+            rng,
+            food_rng,
+        }
+    }
+
+    // How many times every snake can crash, 1 unless set before the first
+    // tick.
+    pub fn set_lives(&mut self, lives: u32) {
+        for player in &mut self.players {
+            player.lives = lives.max(1);
+        }
+    }
+
+    // Food is all apples worth 1 when off, as it was in older replays. Has to
+    // be set before the first tick.
+    pub fn set_food_variants(&mut self, on: bool) {
+        self.food_variants = on;
+        if !on {
+            for food in &mut self.food {
+                food.kind = FoodKind::Apple;
+            }
+        }
+    }
+
+    // Random numbers from here on come from `seed`, the way they would have
+    // from the start of a game with it. Replays don't know, so don't play back
+    // the same.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = Box::new(crate::random::Seeded::new(seed));
+        self.food_rng = self.rng.split();
+    }
+
+    // Random numbers for something that isn't the rules, like a computer
+    // player's, which follow from the seed without changing what the game
+    // does with its own. A different `n` for everything that needs some.
+    pub fn stream(&self, n: u64) -> Box<dyn Rng> {
+        self.rng.stream(n)
+    }
+
+    pub fn handle_input(&mut self, player: usize, input: Input) {
+        let player = match self.mode {
+            Mode::Twins => self.active,
+            _ => player,
+        };
+
+        match input {
+            Input::Swap if self.mode == Mode::Twins => {
+                self.active = (self.active + 1) % self.players.len()
+            }
+            Input::Swap => {}
+            Input::Turn(direction) if direction.is_diagonal() && !self.diagonal => {}
+            Input::Turn(_) if !self.steerable(player) => {}
+            Input::Turn(direction) => self.players[player].change_direction(direction),
+            Input::Elongate => self.grow(player),
+        }
+    }
+
+    // Whether `player` can turn where its head is, which it can't on ice.
+    pub fn steerable(&self, player: usize) -> bool {
+        self.level
+            .terrain_at(&self.players[player].head())
+            .is_none_or(|t| t.steerable())
+    }
+
+    // Makes a snake one cell longer.
+    pub fn grow(&mut self, player: usize) {
+        let board_size = self.board_size;
+        let player = &mut self.players[player];
+
+        match self.growth {
+            Growth::BehindTail => player.grow_behind_tail(&board_size),
+            Growth::Pending => player.grow(1),
+        }
+    }
+
+    // Where every snake's head would go this tick.
+    fn intents(&self) -> Vec<Coordinate> {
+        self.players
+            .iter()
+            .map(|p| {
+                let head = p.next_head(&self.board());
+                self.level.portal_exit(&head).unwrap_or(head)
+            })
+            .collect()
+    }
+
+    // Decides which snakes get to move to where they want to, and what happens
+    // to the ones that would crash.
+    fn check(&mut self, heads: &[Coordinate]) -> Vec<Outcome> {
+        let mut outcomes = vec![Outcome::Move; self.players.len()];
+        for (index, outcome) in outcomes.iter_mut().enumerate() {
+            let crashed = self.crashes(heads, index);
+
+            let player = &mut self.players[index];
+            if !crashed {
+                player.hesitated = 0;
+                continue;
+            }
+            *outcome = Outcome::Stay;
+            if player.hesitated < self.grace {
+                // Gives a turn that was nearly in time the chance to arrive.
+                player.hesitated += 1;
+            } else if self.bumper {
+                self.events.push(Event::Bumped { player: index });
+            } else if player.lives > 1 {
+                player.lives -= 1;
+                self.events.push(Event::LostLife { player: index });
+                *outcome = Outcome::Respawn;
+            } else {
+                player.alive = false;
+                self.events.push(Event::Died { player: index });
+            }
+        }
+        outcomes
+    }
+
+    fn crashes(&self, heads: &[Coordinate], index: usize) -> bool {
+        let head = &heads[index];
+        // A bridge has room for a snake to go over another, or itself.
+        let (room, water) = match self.level.terrain_at(head) {
+            Some(TerrainKind::Bridge) => (1, false),
+            Some(TerrainKind::Water) => (0, self.players[index].bridging == 0),
+            _ => (0, false),
+        };
+        let layers: u32 = self.players.iter().map(|p| p.layers(head)).sum();
+
+        !head.on_board(self.board_size)
+            || layers > room
+            || water
+            || self.level.is_wall(head)
+            || !self
+                .level
+                .gate_allows(head, self.players[index].move_direction)
+            || heads
+                .iter()
+                .enumerate()
+                .any(|(other, h)| other != index && h == head)
+            || self.cuts_corner(index)
+    }
+
+    // Whether a diagonal move by `player` goes between two cells that are
+    // both taken, by snakes or walls.
+    fn cuts_corner(&self, player: usize) -> bool {
+        let player = &self.players[player];
+        let Some((across, up_down)) = player.move_direction.parts() else {
+            return false;
+        };
+        let board = self.board();
+        [across, up_down].into_iter().all(|direction| {
+            let cell = board.step(player.head(), direction);
+            self.level.is_wall(&cell) || self.players.iter().any(|p| p.layers(&cell) > 0)
+        })
+    }
+
+    // Whether `player` crashes on the next tick unless it turns.
+    pub fn crash_ahead(&self, player: usize) -> bool {
+        self.crashes(&self.intents(), player)
+    }
+
+    fn commit(&mut self, heads: Vec<Coordinate>, outcomes: &[Outcome]) {
+        for (index, head) in heads.into_iter().enumerate() {
+            match outcomes[index] {
+                Outcome::Move => self.players[index].move_to(head),
+                Outcome::Stay => {}
+                Outcome::Respawn => {
+                    let start = self.level.start(index);
+                    let player = &self.players[index];
+                    let mut respawned = Player::at(start.head, start.direction, 4);
+                    respawned.score = player.score;
+                    respawned.lives = player.lives;
+                    self.players[index] = respawned;
+                }
+            }
+        }
+    }
+
+    // Counts down every snake's bridge power-up, starts it again for one that
+    // picked up another, and opens the doors snakes got to the keys of.
+    fn pick_up(&mut self) {
+        for (index, player) in self.players.iter_mut().enumerate() {
+            player.bridging = player.bridging.saturating_sub(1);
+            let head = player.head();
+            if let Some(power_up) = self.level.bridge_power_ups.iter().position(|c| *c == head) {
+                self.level.bridge_power_ups.remove(power_up);
+                player.bridging = BRIDGE_TICKS;
+            }
+            if let Some(door) = self.level.doors.iter().position(|d| d.key == head) {
+                self.level.doors.remove(door);
+                self.events.push(Event::DoorOpened { player: index });
+            }
+        }
+    }
+
+    // Moves every snake one cell. Returns true when the game is over, which is
+    // as soon as any snake runs out of lives or the board is full.
+    pub fn tick(&mut self) -> bool {
+        self.tick += 1;
+        self.events.clear();
+
+        let heads = self.intents();
+        let outcomes = self.check(&heads);
+        if self.players.iter().any(|p| !p.alive) {
+            return true;
+        }
+        self.commit(heads, &outcomes);
+        self.blow();
+        self.pick_up();
+
+        for index in 0..self.food.len() {
+            let eaten = self.food[index];
+            let kind = eaten.kind;
+            if eaten.expires.is_some() || eaten.frenzy {
+                continue;
+            }
+            if let Some(eater) = self.players.iter().position(|p| eaten.covers(p.head())) {
+                self.players[eater].score += kind.points();
+                self.grow(eater);
+                self.events.push(Event::FoodEaten {
+                    player: eater,
+                    food: index,
+                    points: kind.points(),
+                });
+
+                let mut next = if self.food_variants {
+                    FoodKind::random(self.food_rng.as_mut(), self.feasts)
+                } else {
+                    kind
+                };
+                let mut location = self.free_location(next.size());
+                if location.is_none() && next == FoodKind::Feast {
+                    // No room for a feast, but maybe for something smaller.
+                    next = FoodKind::Apple;
+                    location = self.free_location(1);
+                }
+                match location {
+                    Some(location) => {
+                        self.food[index].location = location;
+                        self.food[index].kind = next;
+                    }
+                    None => {
+                        self.events.push(Event::BoardFull);
+                        return true;
+                    }
+                }
+            }
+        }
+
+        self.eat_formation();
+        if self.formations
+            && !self.food.iter().any(|f| f.in_formation())
+            && self.food_rng.below(FORMATION_CHANCE) == 0
+        {
+            self.place_formation();
+        }
+
+        self.eat_frenzy();
+        if self.random_event.is_some_and(|e| e.ends <= self.tick) {
+            self.end_random_event();
+        }
+        if self.random_event.is_none()
+            && !self.random_events.is_empty()
+            && self.food_rng.below(crate::events::CHANCE) == 0
+        {
+            let index = crate::events::choose(&self.random_events, self.food_rng.as_mut());
+            self.start_random_event(index);
+        }
+
+        self.eat_letters();
+        if self.letter_rounds && self.letters.is_none() && self.food_rng.below(LETTER_CHANCE) == 0 {
+            self.place_letters();
+        }
+
+        false
+    }
+
+    fn eat_frenzy(&mut self) {
+        let mut index = 0;
+        while index < self.food.len() {
+            let food = self.food[index];
+            let eater = self.players.iter().position(|p| p.head() == food.location);
+            match (food.frenzy, eater) {
+                (true, Some(eater)) => {
+                    self.players[eater].score += food.points();
+                    self.grow(eater);
+                    self.events.push(Event::FrenzyFoodEaten {
+                        player: eater,
+                        points: food.points(),
+                    });
+                    self.food.remove(index);
+                }
+                _ => index += 1,
+            }
+        }
+    }
+
+    // Whether the wind blows, starting out to the right.
+    pub fn set_wind(&mut self, on: bool) {
+        self.wind = on.then_some(MoveDirection::Right);
+    }
+
+    // Now and then the wind turns, and pushes every snake a cell its way
+    // unless it's going into the wind. Snakes that would be pushed into
+    // something are sheltered by it instead.
+    fn blow(&mut self) {
+        if self.wind.is_none() {
+            return;
+        }
+        if self.tick.is_multiple_of(WIND_TURN_TICKS) {
+            let ways = MoveDirection::STRAIGHT;
+            self.wind = Some(ways[self.food_rng.below(ways.len() as u32) as usize]);
+        }
+        let Some(wind) = self.wind.filter(|_| self.tick.is_multiple_of(GUST_TICKS)) else {
+            return;
+        };
+
+        let board = self.board();
+        for index in 0..self.players.len() {
+            let player = &self.players[index];
+            let direction = player.move_direction;
+            if !player.alive || direction == wind.opposite() {
+                continue;
+            }
+            let head = board.step(player.head(), wind);
+            let mut heads: Vec<Coordinate> = self.players.iter().map(|p| p.head()).collect();
+            heads[index] = self.level.portal_exit(&head).unwrap_or(head);
+
+            // Gates and corners are gone through the way the wind goes.
+            self.players[index].move_direction = wind;
+            let sheltered = self.crashes(&heads, index);
+            self.players[index].move_direction = direction;
+            if !sheltered {
+                self.players[index].move_to(heads[index]);
+            }
+        }
+    }
+
+    // The one going on, if there is one.
+    pub fn random_event(&self) -> Option<&'static dyn crate::events::RandomEvent> {
+        self.random_event.map(|e| crate::events::ALL[e.index])
+    }
+
+    // Starts `events::ALL[index]`, ending the one going on first.
+    pub fn start_random_event(&mut self, index: usize) {
+        if self.random_event.is_some() {
+            self.end_random_event();
+        }
+        let event = crate::events::ALL[index];
+        self.random_event = Some(crate::events::Running {
+            index,
+            ends: self.tick + event.ticks(),
+        });
+        event.start(self);
+        self.events.push(Event::RandomEventStarted { index });
+    }
+
+    pub fn end_random_event(&mut self) {
+        if let Some(running) = self.random_event.take() {
+            crate::events::ALL[running.index].end(self);
+            self.events.push(Event::RandomEventEnded {
+                index: running.index,
+            });
+        }
+    }
+
+    // Drops `FRENZY_FOOD` apples worth double on random free cells, or as
+    // many as there's room for, until the event going on ends.
+    pub fn drop_frenzy_food(&mut self) {
+        let ends = self.random_event.map(|e| e.ends);
+        let cells = self.free_cells();
+        let food = pick(self.food_rng.as_mut(), cells, FRENZY_FOOD);
+        self.food.extend(food.into_iter().map(|location| Food {
+            expires: ends,
+            frenzy: true,
+            ..Food::new(location)
+        }));
+    }
+
+    // Every cell food could go on that's free, column by column.
+    fn free_cells(&self) -> Vec<Coordinate> {
+        let (w, h) = self.board_size;
+        (1..=w)
+            .flat_map(|x| (1..=h).map(move |y| Coordinate(x, y)))
+            .filter(|cell| {
+                self.level.allows_food(cell)
+                    && !self
+                        .players
+                        .iter()
+                        .any(|p| p.check_collisions(cell, &self.board_size))
+                    && !self.food.iter().any(|f| f.covers(*cell))
+                    && !self
+                        .letters
+                        .iter()
+                        .flat_map(|l| &l.left)
+                        .any(|(_, c)| c == cell)
+            })
+            .collect()
+    }
+
+    fn eat_letters(&mut self) {
+        let Some(letters) = &mut self.letters else {
+            return;
+        };
+        for (player, snake) in self.players.iter_mut().enumerate() {
+            let head = snake.head();
+            match letters.left.iter().position(|(_, cell)| *cell == head) {
+                Some(0) => {
+                    let (letter, _) = letters.left.remove(0);
+                    self.events.push(Event::LetterEaten { player, letter });
+                    if letters.left.is_empty() {
+                        snake.score += LETTER_BONUS;
+                        snake.lives += 1;
+                        self.events.push(Event::WordSpelled { player });
+                    }
+                }
+                Some(_) => {
+                    letters.left.clear();
+                    self.events.push(Event::LettersLost);
+                }
+                None => {}
+            }
+        }
+
+        if letters.left.is_empty() {
+            self.letters = None;
+        } else if letters.expires <= self.tick {
+            self.letters = None;
+            self.events.push(Event::LettersLost);
+        }
+    }
+
+    // Puts every letter of `WORD` on a random free cell, if there are enough.
+    fn place_letters(&mut self) {
+        let free = self.free_cells();
+        if free.len() < WORD.len() {
+            return;
+        }
+
+        let cells = pick(self.food_rng.as_mut(), free, WORD.len());
+        let left = WORD.chars().zip(cells).collect();
+        self.letters = Some(Letters {
+            left,
+            expires: self.tick + LETTER_TICKS,
+        });
+    }
+
+    fn free_location(&mut self, size: u16) -> Option<Coordinate> {
+        food_location(
+            self.food_placement,
+            &self.level,
+            &self.players,
+            &self.food,
+            size,
+            self.rng.as_mut(),
+        )
+    }
+
+    fn eat_formation(&mut self) {
+        let mut eaten = None;
+        let mut index = 0;
+        while index < self.food.len() {
+            let food = self.food[index];
+            let eater = self.players.iter().position(|p| p.head() == food.location);
+            match (food.in_formation(), eater) {
+                (true, Some(eater)) => {
+                    self.players[eater].score += food.kind.points();
+                    self.grow(eater);
+                    self.events.push(Event::FormationFoodEaten {
+                        player: eater,
+                        points: food.kind.points(),
+                    });
+                    self.food.remove(index);
+                    eaten = Some(eater);
+                }
+                _ => index += 1,
+            }
+        }
+
+        let left = self.food.iter().filter(|f| f.in_formation()).count();
+        let expired = self
+            .food
+            .iter()
+            .filter(|f| f.in_formation())
+            .any(|f| f.expires.is_some_and(|tick| tick <= self.tick));
+        match eaten {
+            Some(player) if left == 0 => {
+                let bonus = self.formation_size;
+                self.players[player].score += bonus;
+                self.events.push(Event::FormationEaten { player, bonus });
+            }
+            _ if expired => {
+                self.food.retain(|f| !f.in_formation());
+                self.events.push(Event::FormationExpired);
+            }
+            _ => {}
+        }
+    }
+
+    // Puts a random formation somewhere it fits, if there's anywhere.
+    fn place_formation(&mut self) {
+        let shape = FORMATIONS[self.food_rng.below(FORMATIONS.len() as u32) as usize];
+        let cells: Vec<(u16, u16)> = shape
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.char_indices()
+                    .filter(|(_, c)| *c == '#')
+                    .map(move |(x, _)| (x as u16, y as u16))
+            })
+            .collect();
+
+        let (w, h) = self.board_size;
+        let fits = |Coordinate(left, top): Coordinate| {
+            cells.iter().all(|(x, y)| {
+                let cell = Coordinate(left + x, top + y);
+                cell.on_board(self.board_size)
+                    && self.level.allows_food(&cell)
+                    && !self
+                        .players
+                        .iter()
+                        .any(|p| p.check_collisions(&cell, &self.board_size))
+                    && !self.food.iter().any(|f| f.covers(cell))
+            })
+        };
+        let corners: Vec<Coordinate> = (1..=w)
+            .flat_map(|x| (1..=h).map(move |y| Coordinate(x, y)))
+            .filter(|c| fits(*c))
+            .collect();
+        if corners.is_empty() {
+            return;
+        }
+
+        let Coordinate(left, top) = corners[self.food_rng.below(corners.len() as u32) as usize];
+        for (x, y) in &cells {
+            self.food.push(Food {
+                location: Coordinate(left + x, top + y),
+                kind: FoodKind::Apple,
+                expires: Some(self.tick + FORMATION_TICKS),
+                frenzy: false,
+            });
+        }
+        self.formation_size = cells.len() as u32;
+    }
+}
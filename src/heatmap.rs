@@ -0,0 +1,161 @@
+// Where the player's snakes have crashed, a line each in the deaths file:
+//
+//   <x> <y> <board width> <board height>
+//
+// and the stats screen, which shows them as a heatmap of the board, boards of
+// every size stretched to the same one. Crashes that cost a life count as
+// much as the last one.
+
+use crate::game::{Coordinate, Event, Game};
+use crate::l10n::{fill, tr};
+use std::io::Write;
+
+const COLD: (u8, u8, u8) = (20, 20, 60);
+const WARM: (u8, u8, u8) = (255, 200, 0);
+const HOT: (u8, u8, u8) = (255, 0, 0);
+
+// Where every snake's head is, to be given to `record` after the tick.
+pub fn heads(game: &Game) -> Vec<Coordinate> {
+    game.players.iter().map(|player| player.head()).collect()
+}
+
+// Notes down where any of `players` crashed on the last tick, from `heads`
+// before it, as snakes that lost a life are already back at the start.
+pub fn record(game: &Game, heads: &[Coordinate], players: &[usize]) {
+    let mut lines = String::new();
+    for event in &game.events {
+        if let Event::Died { player } | Event::LostLife { player } = event {
+            if players.contains(player) {
+                let Coordinate(x, y) = heads[*player];
+                let (width, height) = game.board_size;
+                lines.push_str(&format!("{} {} {} {}\n", x, y, width, height));
+            }
+        }
+    }
+    if lines.is_empty() {
+        return;
+    }
+
+    // Failing to save was already reported when creating the data directory.
+    let _ = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(crate::paths::deaths_file())
+        .and_then(|mut file| file.write_all(lines.as_bytes()));
+}
+
+struct Death {
+    x: u16,
+    y: u16,
+    board_size: (u16, u16),
+}
+
+fn load() -> Vec<Death> {
+    let contents = std::fs::read_to_string(crate::paths::deaths_file()).unwrap_or_default();
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut numbers = line.split(' ').map(|n| n.parse::<u16>().ok());
+            let death = Death {
+                x: numbers.next()??,
+                y: numbers.next()??,
+                board_size: (numbers.next()??, numbers.next()??),
+            };
+            let on_board = (1..=death.board_size.0).contains(&death.x)
+                && (1..=death.board_size.1).contains(&death.y);
+            on_board.then_some(death)
+        })
+        .collect()
+}
+
+// How many deaths fell in every cell of a grid of `size`, row by row.
+fn count(deaths: &[Death], (width, height): (u16, u16)) -> Vec<u32> {
+    let mut counts = vec![0; width as usize * height as usize];
+    for death in deaths {
+        let x = (death.x - 1) as usize * width as usize / death.board_size.0 as usize;
+        let y = (death.y - 1) as usize * height as usize / death.board_size.1 as usize;
+        counts[y * width as usize + x] += 1;
+    }
+    counts
+}
+
+// From cold through warm to hot as `heat` goes from 0 to 1.
+fn color(heat: f64) -> termion::color::Rgb {
+    let mix = |a: (u8, u8, u8), b: (u8, u8, u8), t: f64| {
+        let channel = |a: u8, b: u8| (f64::from(a) + (f64::from(b) - f64::from(a)) * t) as u8;
+        termion::color::Rgb(channel(a.0, b.0), channel(a.1, b.1), channel(a.2, b.2))
+    };
+    match heat < 0.5 {
+        true => mix(COLD, WARM, heat * 2.0),
+        false => mix(WARM, HOT, heat * 2.0 - 1.0),
+    }
+}
+
+fn render(
+    screen: &mut dyn Write,
+    screen_size: (u16, u16),
+    deaths: &[Death],
+    rating: &crate::ratings::Rating,
+) -> Result<(), std::io::Error> {
+    crate::ui::write_centered(screen, &screen_size, 1, tr("Where you crashed"))?;
+    let summary = fill(
+        "{} crashes, versus rating {}",
+        &[&deaths.len(), &rating.rating],
+    );
+    crate::ui::write_centered(screen, &screen_size, 2, &summary)?;
+
+    // A line for the titles and one for the legend, and a border around it.
+    let size = (
+        screen_size.0.saturating_sub(2).max(1),
+        screen_size.1.saturating_sub(6).max(1),
+    );
+    let counts = count(deaths, size);
+    let most = counts.iter().copied().max().unwrap_or(0).max(1);
+    let left = (screen_size.0 - size.0) / 2 + 1;
+
+    for (row, cells) in counts.chunks(size.0 as usize).enumerate() {
+        write!(screen, "{}", termion::cursor::Goto(left, 4 + row as u16))?;
+        for &cell in cells {
+            // The cube root, so a few deaths somewhere still show up next to
+            // many somewhere else.
+            let heat = (f64::from(cell) / f64::from(most)).cbrt();
+            write!(screen, "{} ", termion::color::Bg(color(heat)))?;
+        }
+        write!(screen, "{}", termion::color::Bg(termion::color::Reset))?;
+    }
+
+    let legend_y = 4 + size.1 + 1;
+    let (fewer, more) = (tr("fewer"), tr("more"));
+    let width = crate::ui::text_width(fewer) + crate::ui::text_width(more) + 12;
+    let x = crate::ui::centered_x(&screen_size, width);
+    write!(screen, "{}{} ", termion::cursor::Goto(x, legend_y), fewer)?;
+    for step in 0..10 {
+        write!(
+            screen,
+            "{} ",
+            termion::color::Bg(color(f64::from(step) / 9.0))
+        )?;
+    }
+    write!(
+        screen,
+        "{} {}",
+        termion::color::Bg(termion::color::Reset),
+        more
+    )?;
+    crate::ui::write_centered(screen, &screen_size, screen_size.1, tr("q to go back"))
+}
+
+pub fn show(screen: &mut dyn crate::term::Terminal, config: &crate::config::Config) {
+    let deaths = load();
+    let rating = crate::ratings::of(config);
+
+    screen.read_input();
+    while !screen.read_input().contains(&113) {
+        let screen_size = screen.size();
+        crate::clear(screen).unwrap();
+        render(screen, screen_size, &deaths, &rating).unwrap();
+        screen.flush().unwrap();
+
+        screen.wait_for_input(crate::IDLE_TIMEOUT);
+    }
+}
@@ -0,0 +1,155 @@
+// An experimental single player game on a grid of hexagons, where the snake
+// has six ways to go instead of four. It's kept apart from `game` rather than
+// teaching every mode, replay and computer player about hexagons first.
+//
+// Cells are numbered in rows like the usual board, 1-based, with every other
+// row pushed half a cell to the right, starting with the second:
+//
+//    1,1 2,1 3,1
+//      1,2 2,2 3,2
+//    1,3 2,3 3,3
+//
+// so which cells are next to one above and below depends on its row.
+
+use crate::game::Coordinate;
+use crate::random::Rng;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum HexDirection {
+    UpLeft,
+    UpRight,
+    Left,
+    Right,
+    DownLeft,
+    DownRight,
+}
+
+impl HexDirection {
+    pub fn opposite(self) -> HexDirection {
+        match self {
+            HexDirection::UpLeft => HexDirection::DownRight,
+            HexDirection::UpRight => HexDirection::DownLeft,
+            HexDirection::Left => HexDirection::Right,
+            HexDirection::Right => HexDirection::Left,
+            HexDirection::DownLeft => HexDirection::UpRight,
+            HexDirection::DownRight => HexDirection::UpLeft,
+        }
+    }
+}
+
+// Whether row `y` is one of the ones pushed to the right.
+pub fn shifted(y: u16) -> bool {
+    y.is_multiple_of(2)
+}
+
+// The cell next to `coordinate` in `direction`. Off the top or the left ends
+// up off the board, like `Coordinate::step`.
+pub fn step(Coordinate(x, y): Coordinate, direction: HexDirection) -> Coordinate {
+    // Up and down one row goes half a cell left or right, which is the same
+    // column or the one next to it depending on the row.
+    let (left, right) = match shifted(y) {
+        true => (x, x.saturating_add(1)),
+        false => (x.saturating_sub(1), x),
+    };
+    match direction {
+        HexDirection::UpLeft => Coordinate(left, y.saturating_sub(1)),
+        HexDirection::UpRight => Coordinate(right, y.saturating_sub(1)),
+        HexDirection::Left => Coordinate(x.saturating_sub(1), y),
+        HexDirection::Right => Coordinate(x.saturating_add(1), y),
+        HexDirection::DownLeft => Coordinate(left, y.saturating_add(1)),
+        HexDirection::DownRight => Coordinate(right, y.saturating_add(1)),
+    }
+}
+
+pub struct HexGame {
+    pub board_size: (u16, u16),
+    // Head first.
+    pub segments: std::collections::VecDeque<Coordinate>,
+    pub direction: HexDirection,
+    pub food: Coordinate,
+    pub score: u32,
+    pub alive: bool,
+    pub tick: u32,
+    growth: u32,
+    rng: Box<dyn Rng>,
+}
+
+impl HexGame {
+    pub fn new(seed: u64, board_size: (u16, u16)) -> Self {
+        HexGame::with_rng(board_size, Box::new(crate::random::Seeded::new(seed)))
+    }
+
+    // Starting in the middle going right, with food wherever `rng` says.
+    pub fn with_rng(board_size: (u16, u16), rng: Box<dyn Rng>) -> Self {
+        let (x, y) = (board_size.0.div_ceil(2), board_size.1.div_ceil(2));
+        let mut game = HexGame {
+            board_size,
+            segments: (0..4).map(|i| Coordinate(x.saturating_sub(i), y)).collect(),
+            direction: HexDirection::Right,
+            food: Coordinate(0, 0),
+            score: 0,
+            alive: true,
+            tick: 0,
+            growth: 0,
+            rng,
+        };
+        game.place_food();
+        game
+    }
+
+    pub fn head(&self) -> Coordinate {
+        self.segments[0]
+    }
+
+    // Turning back into its own neck is ignored.
+    pub fn turn(&mut self, direction: HexDirection) {
+        if direction != self.direction.opposite() {
+            self.direction = direction;
+        }
+    }
+
+    // Moves the snake a cell. Returns true when the game is over, which is
+    // when it crashes or there's nowhere left for food.
+    pub fn tick(&mut self) -> bool {
+        self.tick += 1;
+        let next = step(self.head(), self.direction);
+        // The tail moves out of the way in time unless the snake is growing.
+        let body = match self.growth {
+            0 => self.segments.len() - 1,
+            _ => self.segments.len(),
+        };
+        if !next.on_board(self.board_size) || self.segments.iter().take(body).any(|s| *s == next) {
+            self.alive = false;
+            return true;
+        }
+
+        match self.growth {
+            0 => {
+                self.segments.pop_back();
+            }
+            _ => self.growth -= 1,
+        }
+        self.segments.push_front(next);
+
+        if next == self.food {
+            self.score += 1;
+            self.growth += 1;
+            return !self.place_food();
+        }
+        false
+    }
+
+    // Puts the food on a random free cell, returning false if there isn't one.
+    fn place_food(&mut self) -> bool {
+        let (w, h) = self.board_size;
+        let free: Vec<Coordinate> = (1..=h)
+            .flat_map(|y| (1..=w).map(move |x| Coordinate(x, y)))
+            .filter(|cell| !self.segments.contains(cell))
+            .collect();
+        if free.is_empty() {
+            return false;
+        }
+        self.food = free[self.rng.below(free.len() as u32) as usize];
+        true
+    }
+}
@@ -0,0 +1,130 @@
+// The screen for the hex grid game, see `snake::hex`. Every hexagon is two
+// columns wide, and the rows pushed to the right are pushed by one column,
+// so every cell touches two above and two below it. u and i go up left and
+// right, h and l left and right, n and m down left and right.
+
+use crate::l10n::{fill, tr};
+use snake::game::Coordinate;
+use snake::hex::{HexDirection, HexGame};
+use std::io::Write;
+
+fn direction(key: u8) -> Option<HexDirection> {
+    match key {
+        117 => Some(HexDirection::UpLeft),
+        105 => Some(HexDirection::UpRight),
+        104 => Some(HexDirection::Left),
+        108 => Some(HexDirection::Right),
+        110 => Some(HexDirection::DownLeft),
+        109 => Some(HexDirection::DownRight),
+        _ => None,
+    }
+}
+
+// As many cells as fit, leaving a column for the pushed rows and a row for
+// the score.
+fn board_size((width, height): (u16, u16)) -> (u16, u16) {
+    (width.saturating_sub(1) / 2, height.saturating_sub(1))
+}
+
+fn cell_position(Coordinate(x, y): Coordinate) -> (u16, u16) {
+    ((x - 1) * 2 + 1 + u16::from(snake::hex::shifted(y)), y)
+}
+
+fn render(screen: &mut dyn Write, game: &HexGame) -> Result<(), std::io::Error> {
+    let (w, h) = game.board_size;
+    write!(
+        screen,
+        "{}",
+        termion::color::Fg(termion::color::Rgb(80, 80, 80))
+    )?;
+    for y in 1..=h {
+        for x in 1..=w {
+            let (column, row) = cell_position(Coordinate(x, y));
+            write!(screen, "{}·", termion::cursor::Goto(column, row))?;
+        }
+    }
+    write!(screen, "{}", termion::color::Fg(termion::color::Reset))?;
+
+    let food = &crate::theme::current().food[0];
+    let (column, row) = cell_position(game.food);
+    write!(
+        screen,
+        "{}{}{}{}{}",
+        termion::cursor::Goto(column, row),
+        termion::color::Bg(food.color),
+        termion::color::Fg(termion::color::Rgb(0, 0, 0)),
+        food.letter,
+        termion::style::Reset
+    )?;
+
+    let (head, body) = crate::theme::snake(0);
+    for (index, segment) in game.segments.iter().enumerate() {
+        let color = match index {
+            0 => head,
+            _ => body,
+        };
+        let (column, row) = cell_position(*segment);
+        write!(
+            screen,
+            "{}{}  ",
+            termion::cursor::Goto(column, row),
+            termion::color::Bg(color)
+        )?;
+    }
+    write!(
+        screen,
+        "{}{} {} ",
+        termion::color::Bg(termion::color::Reset),
+        termion::cursor::Goto(2, h + 1),
+        fill("Score: {}", &[&game.score])
+    )
+}
+
+pub fn play(screen: &mut dyn crate::term::Terminal) {
+    let size = board_size(screen.size());
+    let (min_width, min_height) = snake::level::MIN_SIZE;
+    if size.0 < min_width || size.1 < min_height {
+        return crate::message(screen, &[tr("The terminal is too small for a hex board")]);
+    }
+    let mut game = HexGame::new(rand::random(), size);
+    let move_duration = crate::move_duration();
+
+    screen.read_input();
+    let mut next_frame = std::time::Instant::now();
+    let mut prev_move_update = std::time::Instant::now();
+    let mut dirty = true;
+    let mut quit = false;
+    loop {
+        for b in screen.read_input() {
+            quit |= b == 113;
+            if let Some(direction) = direction(b) {
+                game.turn(direction);
+            }
+        }
+
+        if quit {
+            break;
+        }
+        if prev_move_update.elapsed() > move_duration {
+            prev_move_update = std::time::Instant::now();
+            dirty = true;
+            if game.tick() {
+                break;
+            }
+        }
+
+        if dirty {
+            dirty = false;
+            crate::clear(screen).unwrap();
+            render(screen, &game).unwrap();
+            screen.flush().unwrap();
+        }
+        crate::limit_fps(&mut next_frame);
+    }
+
+    let score = fill("Game over! Score: {}", &[&game.score]);
+    match game.alive && !quit {
+        true => crate::message(screen, &[tr("The board is full, you win!"), &score]),
+        false => crate::message(screen, &[&score]),
+    }
+}
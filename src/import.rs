@@ -0,0 +1,125 @@
+// Levels from other terminal snake games, for `snake import`. Two kinds of
+// text file are understood:
+//
+// nsnake levels, a few key=value lines and then the map between a line that
+// says `start` and one that says `end`. # is a wall and @ or 1 is where the
+// snake starts:
+//
+//   name=Box
+//   author=someone
+//   start
+//   ##########
+//   #  @     #
+//   ##########
+//   end
+//
+// and QBasic Nibbles layouts, the screen as it was drawn. Nibbles drew two
+// rows of the board to a line of text with half blocks, so ▀ is a wall in the
+// top one, ▄ in the bottom one and █ or # in both.
+//
+// Snakes in nsnake levels start going right, and Nibbles layouts don't say,
+// so those start where they would on an empty board.
+
+use crate::game::{Coordinate, MoveDirection};
+use crate::level::{Level, Spawn};
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Source {
+    Nsnake,
+    Nibbles,
+}
+
+impl Source {
+    // nsnake levels are the ones with a map between start and end.
+    pub fn detect(contents: &str) -> Self {
+        match contents.lines().any(|line| line.trim() == "start") {
+            true => Source::Nsnake,
+            false => Source::Nibbles,
+        }
+    }
+}
+
+pub fn import(contents: &str) -> Result<Level, String> {
+    match Source::detect(contents) {
+        Source::Nsnake => nsnake(contents),
+        Source::Nibbles => nibbles(contents),
+    }
+}
+
+pub fn nsnake(contents: &str) -> Result<Level, String> {
+    let mut lines = contents.lines();
+    let mut level = Level::empty((0, 0));
+
+    for line in lines.by_ref() {
+        let line = line.trim();
+        if line == "start" {
+            break;
+        }
+        match line.split_once('=') {
+            Some(("name", name)) => level.name = Some(name.trim().to_string()),
+            Some(("author", author)) => level.author = Some(author.trim().to_string()),
+            // Dates, comments and anything else there's nowhere to keep.
+            _ => {}
+        }
+    }
+
+    let mut map = Vec::new();
+    for line in lines.by_ref() {
+        if line.trim() == "end" {
+            break;
+        }
+        map.push(line);
+    }
+    if map.is_empty() {
+        return Err("the map between start and end is empty".to_string());
+    }
+
+    for (y, line) in (1..).zip(&map) {
+        for (x, c) in (1..).zip(line.chars()) {
+            let cell = Coordinate(x, y);
+            match c {
+                '#' => level.walls.push(cell),
+                '@' | '1' if level.spawns.is_empty() => level.spawns.push(Spawn {
+                    head: cell,
+                    direction: MoveDirection::Right,
+                }),
+                _ => {}
+            }
+        }
+    }
+    level.size = size(&map, 1)?;
+    Ok(level)
+}
+
+pub fn nibbles(contents: &str) -> Result<Level, String> {
+    let map: Vec<&str> = contents.trim_end().lines().collect();
+    let mut level = Level::empty(size(&map, 2)?);
+
+    for (row, line) in (0..).zip(&map) {
+        for (x, c) in (1..).zip(line.chars()) {
+            let (top, bottom) = (Coordinate(x, row * 2 + 1), Coordinate(x, row * 2 + 2));
+            match c {
+                '▀' => level.walls.push(top),
+                '▄' => level.walls.push(bottom),
+                '█' | '#' => level.walls.extend([top, bottom]),
+                _ => {}
+            }
+        }
+    }
+    Ok(level)
+}
+
+// The board for a map as wide as its longest line, with `rows` of the board
+// to a line.
+fn size(map: &[&str], rows: u16) -> Result<(u16, u16), String> {
+    let width = map
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    let height = map.len() * rows as usize;
+    match (u16::try_from(width), u16::try_from(height)) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(format!("a {}x{} map can't be a level", width, height)),
+    }
+}
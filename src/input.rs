@@ -0,0 +1,146 @@
+// Turns what the terminal sends into keys. Most keys are one byte, the rest
+// are escape sequences starting with ESC: "ESC [" followed by numbers and a
+// final letter, or "ESC O" and one more byte for the keys xterm sends
+// differently in application mode. With mouse reporting on, clicks come as
+// "ESC [ <" and the button, column and row, see `Mouse`. Anything at all can
+// come in, pasted text
+// or bytes from a broken connection, so parsing never fails and never looks
+// at any byte more than once.
+
+use crate::game::MoveDirection;
+
+const ESC: u8 = 0x1b;
+// Longer sequences than this are given up on, nothing real sends them.
+const MAX_SEQUENCE: usize = 32;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum InputEvent {
+    // A key that sends a single byte: letters, digits, space, enter, and the
+    // escape key when nothing follows it.
+    Key(u8),
+    Arrow(MoveDirection),
+    F3,
+    Mouse(Mouse),
+    // A sequence for a key nothing uses, or a broken one.
+    Unknown,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+}
+
+// Where the mouse is, in columns and rows of the terminal from 1,1 in the top
+// left. Moving without a button held isn't reported.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Mouse {
+    Press(MouseButton, u16, u16),
+    Drag(MouseButton, u16, u16),
+    Release(u16, u16),
+}
+
+pub fn parse_input(input: &[u8]) -> Vec<InputEvent> {
+    let mut events = Vec::new();
+    let mut rest = input;
+
+    while let Some((&first, after)) = rest.split_first() {
+        rest = after;
+        if first != ESC {
+            events.push(InputEvent::Key(first));
+            continue;
+        }
+
+        let event = match rest.split_first() {
+            Some((b'[', after)) => {
+                let (length, event) = control_sequence(after);
+                rest = &after[length..];
+                event
+            }
+            Some((b'O', after)) => match after.split_first() {
+                Some((&key, after)) => {
+                    rest = after;
+                    ss3(key)
+                }
+                None => {
+                    rest = after;
+                    InputEvent::Unknown
+                }
+            },
+            // Alt and a key, or escape on its own.
+            _ => InputEvent::Key(ESC),
+        };
+        events.push(event);
+    }
+
+    events
+}
+
+// Reads "ESC [" sequences: parameter bytes, intermediate bytes and a final
+// byte. Returns how much of `input` the sequence took.
+fn control_sequence(input: &[u8]) -> (usize, InputEvent) {
+    let parameters = input
+        .iter()
+        .take(MAX_SEQUENCE)
+        .take_while(|b| (0x20..=0x3f).contains(*b))
+        .count();
+
+    match input.get(parameters) {
+        Some(&last @ 0x40..=0x7e) => {
+            let event = match (&input[..parameters], last) {
+                (b"", b'A') => InputEvent::Arrow(MoveDirection::Up),
+                (b"", b'B') => InputEvent::Arrow(MoveDirection::Down),
+                (b"", b'C') => InputEvent::Arrow(MoveDirection::Right),
+                (b"", b'D') => InputEvent::Arrow(MoveDirection::Left),
+                (b"13", b'~') => InputEvent::F3,
+                ([b'<', parameters @ ..], b'M' | b'm') => mouse(parameters, last == b'm'),
+                _ => InputEvent::Unknown,
+            };
+            (parameters + 1, event)
+        }
+        // Cut off, too long, or broken by a byte that can't be in one. The
+        // bytes after what was read are keys again.
+        _ => (parameters, InputEvent::Unknown),
+    }
+}
+
+// The SGR mouse protocol: "<button;column;row", then M for a press or drag
+// and m for a release. The button has 32 added while dragging, and 64 for the
+// wheel, which nothing uses.
+fn mouse(parameters: &[u8], released: bool) -> InputEvent {
+    let numbers: Option<Vec<u16>> = std::str::from_utf8(parameters)
+        .ok()
+        .map(|p| p.split(';').map(|n| n.parse().ok()).collect())
+        .unwrap_or_default();
+    let Some(&[code, column, row]) = numbers.as_deref() else {
+        return InputEvent::Unknown;
+    };
+    if released {
+        return InputEvent::Mouse(Mouse::Release(column, row));
+    }
+
+    let button = match code & 0b11 {
+        0 => MouseButton::Left,
+        1 => MouseButton::Middle,
+        2 => MouseButton::Right,
+        _ => return InputEvent::Unknown,
+    };
+    // Without the bits for shift, alt and control.
+    match code & !0b11100 {
+        0..=2 => InputEvent::Mouse(Mouse::Press(button, column, row)),
+        32..=34 => InputEvent::Mouse(Mouse::Drag(button, column, row)),
+        _ => InputEvent::Unknown,
+    }
+}
+
+fn ss3(key: u8) -> InputEvent {
+    match key {
+        b'A' => InputEvent::Arrow(MoveDirection::Up),
+        b'B' => InputEvent::Arrow(MoveDirection::Down),
+        b'C' => InputEvent::Arrow(MoveDirection::Right),
+        b'D' => InputEvent::Arrow(MoveDirection::Left),
+        b'R' => InputEvent::F3,
+        _ => InputEvent::Unknown,
+    }
+}
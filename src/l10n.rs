@@ -0,0 +1,78 @@
+// Translations of everything the game shows. The English text in the code is
+// the key, so a missing translation shows up in English rather than as
+// nothing. Catalogs are TOML files of English = translation:
+//
+//   "Play" = "Spelen"
+//   "Score: {}" = "Punten: {}"
+//
+// where every {} is filled in with a number or a name, in the same order as
+// in English. The language comes from `language` in the config, or else from
+// LANG, and its catalog is read from locales/<language>.toml in the config
+// directory. locales/template.toml in the source has every key there is.
+
+const ENGLISH: &str = "en";
+
+static CATALOG: std::sync::OnceLock<std::collections::HashMap<String, String>> =
+    std::sync::OnceLock::new();
+
+// The language asked for, without the country or encoding: "nl" for
+// "nl_NL.UTF-8".
+fn language(config: &crate::config::Config) -> String {
+    let locale = config.language.clone().or_else(|| {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+    });
+
+    match locale {
+        Some(locale) if locale != "C" && locale != "POSIX" => locale
+            .split(['_', '.', '@', '-'])
+            .next()
+            .unwrap_or(ENGLISH)
+            .to_lowercase(),
+        _ => ENGLISH.to_string(),
+    }
+}
+
+// Picks the catalog, before anything is drawn. A catalog that can't be read
+// is reported and the game carries on in English.
+pub fn init(config: &crate::config::Config) -> Result<(), String> {
+    let language = language(config);
+    if language == ENGLISH {
+        return Ok(());
+    }
+
+    let path = crate::paths::locale_file(&language);
+    let catalog = match std::fs::read_to_string(&path) {
+        Ok(contents) => {
+            toml::from_str(&contents).map_err(|e| format!("{}: {}", path.display(), e.message()))?
+        }
+        // No translation yet, the same as English.
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    let _ = CATALOG.set(catalog);
+    Ok(())
+}
+
+pub fn tr(text: &str) -> &str {
+    CATALOG
+        .get()
+        .and_then(|catalog| catalog.get(text))
+        .filter(|translation| !translation.is_empty())
+        .map_or(text, |translation| translation.as_str())
+}
+
+// `text` translated, with its {}s filled in from `values`.
+pub fn fill(text: &str, values: &[&dyn std::fmt::Display]) -> String {
+    let mut parts = tr(text).split("{}");
+    let mut filled = parts.next().unwrap_or_default().to_string();
+    for (index, part) in parts.enumerate() {
+        if let Some(value) = values.get(index) {
+            filled += &value.to_string();
+        }
+        filled += part;
+    }
+    filled
+}
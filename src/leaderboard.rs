@@ -0,0 +1,82 @@
+// Client for an online leaderboard, only built with the `online` feature and
+// only used when `leaderboard_url` is set in the config. The server is
+// expected to offer:
+//
+//   POST <url>/scores  {"name": "jsw", "score": 12, "mode": "classic",
+//                       "seed": 1234, "replay_hash": "cbf29ce484222325",
+//                       "checksum": "af63bd4c8601b7df"}
+//   GET  <url>/scores  [{"name": "jsw", "score": 12}, ...], best first
+//   GET  <url>/replays/<replay hash>  the replay file
+//
+// The seed and replay hash let the server ask for the replay later, and the
+// checksum, the one in the replay, lets it check that the run really played
+// out like that by playing it back. Scores can also say which "mode" they're
+// from, the "date" they were set as seconds since 1970, and the
+// "replay_hash" of a replay it has for them, which the high scores screen
+// offers to watch.
+
+use crate::scores::Entry;
+use serde::Serialize;
+
+const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+#[derive(Serialize)]
+struct Submission<'a> {
+    name: &'a str,
+    score: u32,
+    mode: &'static str,
+    seed: u64,
+    replay_hash: String,
+    checksum: String,
+}
+
+fn agent() -> ureq::Agent {
+    ureq::Agent::config_builder()
+        .timeout_global(Some(TIMEOUT))
+        .build()
+        .into()
+}
+
+fn scores_url(url: &str) -> String {
+    format!("{}/scores", url.trim_end_matches('/'))
+}
+
+pub fn submit(
+    url: &str,
+    name: &str,
+    score: u32,
+    replay: &crate::replay::Replay,
+) -> Result<(), String> {
+    let submission = Submission {
+        name,
+        score,
+        mode: replay.mode.name(),
+        seed: replay.seed,
+        replay_hash: replay.hash(),
+        checksum: format!("{:016x}", replay.checksum.unwrap_or(0)),
+    };
+
+    agent()
+        .post(scores_url(url))
+        .send_json(&submission)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+pub fn fetch(url: &str) -> Result<Vec<Entry>, String> {
+    agent()
+        .get(scores_url(url))
+        .call()
+        .and_then(|mut response| response.body_mut().read_json())
+        .map_err(|e| e.to_string())
+}
+
+pub fn fetch_replay(url: &str, hash: &str) -> Result<crate::replay::Replay, String> {
+    let data = agent()
+        .get(format!("{}/replays/{}", url.trim_end_matches('/'), hash))
+        .call()
+        .and_then(|mut response| response.body_mut().read_to_vec())
+        .map_err(|e| e.to_string())?;
+
+    crate::replay::Replay::read_from(&mut &data[..]).map_err(|e| e.to_string())
+}
@@ -0,0 +1,645 @@
+// A hand-made board, made with `snake edit` and saved as RON, or as TOML when
+// the file name ends in .toml. Walls are crashed into like the edges, a snake
+// entering either end of a portal comes out of the other, and food only
+// appears inside the food zones if there are any. Food weights make food come
+// up more often in their zone than elsewhere: every cell counts once, and one
+// in a weight's zone that many times. An empty level is the usual
+// board. In a puzzle level the snake only moves when a key is pressed, and
+// moves can be undone. A mask shapes the board as a circle, a cross or a
+// donut fitted to its size, with everything outside it a wall, and the
+// snakes starting inside it unless they have spawns. The topology is what
+// the edges do: a bounded board is crashed into at every edge, a toroidal
+// one comes back round on the other side of every edge, and a cylinder only
+// does that left and right. Terrain changes how the game plays while the
+// snake's head is on it: a boost strip makes moves come twice as fast, mud
+// twice as slow, and on ice the snake slides on the way it was going, turning
+// only once it's off. Water is crashed into, unless the snake has picked up
+// one of the bridge power-ups and it hasn't run out yet, and a bridge can be
+// crossed over snakes, its own body too, with one on top of the other. A
+// one-way gate can only be gone onto the way it faces, and is crashed into
+// from anywhere else. A door is walls that open for good once a snake gets to
+// its key.
+//
+// Everything but the size is optional. In TOML a level looks like:
+//
+//   name = "Tunnels"
+//   author = "jsw"
+//   par = 20
+//   puzzle = true
+//   size = [40, 20]
+//   food = 2
+//   walls = [[10, 5], [11, 5]]
+//   portals = [[[1, 10], [40, 10]]]
+//   food_zones = [[[15, 5], [25, 15]]]
+//   food_weights = [{ zone = [[19, 9], [21, 11]], weight = 10 }]
+//   mask = "circle"
+//   topology = "cylinder"
+//   terrain = [{ zone = [[5, 3], [35, 3]], kind = "boost" }]
+//   bridge_power_ups = [[3, 18]]
+//   gates = [{ cell = [20, 5], direction = "left" }]
+//   doors = [{ key = [3, 3], cells = [[30, 9], [30, 10], [30, 11]] }]
+//
+//   [[spawns]]
+//   head = [20, 10]
+//   direction = "up"
+
+use crate::game::{Coordinate, MoveDirection};
+use serde::{Deserialize, Serialize};
+
+// Smaller boards don't leave room for the snakes' starting positions.
+pub const MIN_SIZE: (u16, u16) = (8, 4);
+// Games over the network aren't played on anything smaller, whatever size
+// the other side says its screen is.
+pub const MIN_NETWORK_SIZE: (u16, u16) = (10, 6);
+pub const MAX_PLAYERS: usize = 2;
+const SNAKE_LENGTH: u16 = 4;
+pub const MAX_FOOD_WEIGHT: u32 = 100;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Level {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    // The score a good run should reach.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub par: Option<u32>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub puzzle: bool,
+    pub size: (u16, u16),
+    // How much food is on the board at once.
+    #[serde(default = "default_food")]
+    pub food: u16,
+    #[serde(default)]
+    pub walls: Vec<Coordinate>,
+    #[serde(default)]
+    pub portals: Vec<(Coordinate, Coordinate)>,
+    // Where the snakes start, in player order. Players without one start
+    // where they would on an empty board.
+    #[serde(default)]
+    pub spawns: Vec<Spawn>,
+    #[serde(default)]
+    pub food_zones: Vec<Zone>,
+    #[serde(default)]
+    pub food_weights: Vec<FoodWeight>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mask: Option<Mask>,
+    #[serde(default, skip_serializing_if = "Topology::is_bounded")]
+    pub topology: Topology,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub terrain: Vec<Terrain>,
+    // Gone once picked up, see `Game::tick`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bridge_power_ups: Vec<Coordinate>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gates: Vec<Gate>,
+    // Gone once opened, see `Game::tick`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub doors: Vec<Door>,
+}
+
+fn default_food() -> u16 {
+    4
+}
+
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Spawn {
+    pub head: Coordinate,
+    pub direction: MoveDirection,
+}
+
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Gate {
+    pub cell: Coordinate,
+    pub direction: MoveDirection,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Door {
+    pub key: Coordinate,
+    pub cells: Vec<Coordinate>,
+}
+
+// Every cell between two opposite corners, including the corners.
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Zone(pub Coordinate, pub Coordinate);
+
+impl Zone {
+    pub fn contains(&self, Coordinate(x, y): &Coordinate) -> bool {
+        let Zone(Coordinate(x1, y1), Coordinate(x2, y2)) = *self;
+
+        (x1.min(x2)..=x1.max(x2)).contains(x) && (y1.min(y2)..=y1.max(y2)).contains(y)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FoodWeight {
+    pub zone: Zone,
+    pub weight: u32,
+}
+
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Terrain {
+    pub zone: Zone,
+    pub kind: TerrainKind,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TerrainKind {
+    Boost,
+    Mud,
+    Ice,
+    Water,
+    Bridge,
+}
+
+impl TerrainKind {
+    // How much longer moves take with the head on it.
+    pub fn tick_scale(self) -> f64 {
+        match self {
+            TerrainKind::Boost => 0.5,
+            TerrainKind::Mud => 2.0,
+            TerrainKind::Ice | TerrainKind::Water | TerrainKind::Bridge => 1.0,
+        }
+    }
+
+    // Whether turns count with the head on it.
+    pub fn steerable(self) -> bool {
+        self != TerrainKind::Ice
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mask {
+    Circle,
+    Cross,
+    // A circle with a hole half as wide in the middle.
+    Donut,
+}
+
+impl Mask {
+    pub fn contains(&self, Coordinate(x, y): &Coordinate, (w, h): (u16, u16)) -> bool {
+        // From -1 to 1 across the board both ways, 0 in the middle.
+        let u = (f64::from(*x) - f64::from(w + 1) / 2.0) / (f64::from(w) / 2.0);
+        let v = (f64::from(*y) - f64::from(h + 1) / 2.0) / (f64::from(h) / 2.0);
+        let distance = u * u + v * v;
+
+        match self {
+            Mask::Circle => distance <= 1.0,
+            Mask::Cross => u.abs() <= 1.0 / 3.0 || v.abs() <= 1.0 / 3.0,
+            Mask::Donut => (0.25..=1.0).contains(&distance),
+        }
+    }
+
+    // Where player `index` starts when it has no spawn: across the middle,
+    // or for a donut across the top and the bottom of the ring.
+    fn start(&self, index: usize, (w, h): (u16, u16)) -> Spawn {
+        let (x, y) = (w.div_ceil(2), h.div_ceil(2));
+        let (top, bottom) = match self {
+            Mask::Donut => (y - h * 3 / 8, y + h * 3 / 8),
+            _ => (y, y + 1),
+        };
+        match index {
+            0 => Spawn {
+                head: Coordinate(x + 2, top),
+                direction: MoveDirection::Right,
+            },
+            _ => Spawn {
+                head: Coordinate(x - 1, bottom),
+                direction: MoveDirection::Left,
+            },
+        }
+    }
+}
+
+#[derive(Copy, Clone, Default, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Topology {
+    #[default]
+    Bounded,
+    Toroidal,
+    Cylinder,
+}
+
+impl Topology {
+    fn is_bounded(&self) -> bool {
+        *self == Topology::Bounded
+    }
+
+    // Whether going off the board left or right, and up or down, comes back
+    // on the other side.
+    pub fn wraps(&self) -> (bool, bool) {
+        match self {
+            Topology::Bounded => (false, false),
+            Topology::Toroidal => (true, true),
+            Topology::Cylinder => (true, false),
+        }
+    }
+}
+
+pub enum LevelError {
+    Io(std::io::Error),
+    // Not a level at all, with the line and column of the problem.
+    Syntax(String),
+    // A level, but not one that can be played.
+    Invalid(String),
+}
+
+impl std::fmt::Display for LevelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LevelError::Io(e) => write!(f, "{}", e),
+            LevelError::Syntax(e) => write!(f, "{}", e),
+            LevelError::Invalid(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for LevelError {
+    fn from(e: std::io::Error) -> Self {
+        LevelError::Io(e)
+    }
+}
+
+impl Level {
+    pub fn empty(size: (u16, u16)) -> Self {
+        Level {
+            name: None,
+            author: None,
+            par: None,
+            puzzle: false,
+            size,
+            food: default_food(),
+            walls: Vec::new(),
+            portals: Vec::new(),
+            spawns: Vec::new(),
+            food_zones: Vec::new(),
+            food_weights: Vec::new(),
+            mask: None,
+            topology: Topology::Bounded,
+            terrain: Vec::new(),
+            bridge_power_ups: Vec::new(),
+            gates: Vec::new(),
+            doors: Vec::new(),
+        }
+    }
+
+    pub fn is_wall(&self, coordinate: &Coordinate) -> bool {
+        self.walls.contains(coordinate)
+            || self.masked(coordinate)
+            || self.doors.iter().any(|d| d.cells.contains(coordinate))
+    }
+
+    // Whether `coordinate` is outside the mask, if there is one.
+    pub fn masked(&self, coordinate: &Coordinate) -> bool {
+        self.mask
+            .is_some_and(|mask| !mask.contains(coordinate, self.size))
+    }
+
+    // The cells outside the mask next to one inside it, which is all of it
+    // that needs drawing.
+    pub fn mask_edge(&self) -> Vec<Coordinate> {
+        let (w, h) = self.size;
+        (1..=h)
+            .flat_map(|y| (1..=w).map(move |x| Coordinate(x, y)))
+            .filter(|cell| self.masked(cell))
+            .filter(|cell| {
+                [
+                    MoveDirection::Up,
+                    MoveDirection::Down,
+                    MoveDirection::Left,
+                    MoveDirection::Right,
+                ]
+                .into_iter()
+                .map(|direction| cell.step(direction))
+                .any(|next| next.on_board(self.size) && !self.masked(&next))
+            })
+            .collect()
+    }
+
+    // Where a snake entering `coordinate` comes out, if it's a portal.
+    pub fn portal_exit(&self, coordinate: &Coordinate) -> Option<Coordinate> {
+        self.portals.iter().find_map(|(a, b)| match coordinate {
+            c if c == a => Some(*b),
+            c if c == b => Some(*a),
+            _ => None,
+        })
+    }
+
+    // Whether a snake going `direction` can move onto `coordinate`, which it
+    // can't onto a gate facing another way.
+    pub fn gate_allows(&self, coordinate: &Coordinate, direction: MoveDirection) -> bool {
+        self.gates
+            .iter()
+            .all(|gate| gate.cell != *coordinate || gate.direction == direction)
+    }
+
+    pub fn allows_food(&self, coordinate: &Coordinate) -> bool {
+        !self.is_wall(coordinate)
+            && self.portal_exit(coordinate).is_none()
+            && !self.bridge_power_ups.contains(coordinate)
+            && !self.doors.iter().any(|d| d.key == *coordinate)
+            && self.terrain_at(coordinate) != Some(TerrainKind::Water)
+            && (self.food_zones.is_empty()
+                || self.food_zones.iter().any(|z| z.contains(coordinate)))
+    }
+
+    // What `coordinate` is made of, if anything. Where terrain overlaps, the
+    // last one counts.
+    pub fn terrain_at(&self, coordinate: &Coordinate) -> Option<TerrainKind> {
+        self.terrain
+            .iter()
+            .rev()
+            .find(|t| t.zone.contains(coordinate))
+            .map(|t| t.kind)
+    }
+
+    // How many times over `coordinate` counts when picking where food goes.
+    // Where weights overlap, the last one counts.
+    pub fn food_weight(&self, coordinate: &Coordinate) -> u32 {
+        self.food_weights
+            .iter()
+            .rev()
+            .find(|w| w.zone.contains(coordinate))
+            .map_or(1, |w| w.weight)
+    }
+
+    // Where player `index` starts, trailing away from the direction it faces.
+    pub fn start(&self, index: usize) -> Spawn {
+        let (w, h) = self.size;
+        if let (None, Some(mask)) = (self.spawns.get(index), self.mask) {
+            return mask.start(index, self.size);
+        }
+
+        self.spawns.get(index).copied().unwrap_or(match index {
+            0 => Spawn {
+                head: Coordinate(4, 1),
+                direction: MoveDirection::Right,
+            },
+            _ => Spawn {
+                head: Coordinate(w - 3, h - 1),
+                direction: MoveDirection::Left,
+            },
+        })
+    }
+
+    // Everything that would make the game misbehave, like walls off the
+    // board or snakes starting inside walls. Reports the first problem found.
+    pub fn validate(&self) -> Result<(), String> {
+        let (w, h) = self.size;
+        if w < MIN_SIZE.0 || h < MIN_SIZE.1 {
+            return Err(format!(
+                "{}x{} board is too small, levels have to be at least {}x{}",
+                w, h, MIN_SIZE.0, MIN_SIZE.1
+            ));
+        }
+
+        let on_board = |what: &str, c: &Coordinate| {
+            if (1..=w).contains(&c.0) && (1..=h).contains(&c.1) {
+                Ok(())
+            } else {
+                Err(format!("{} at {} outside {}x{} board", what, c, w, h))
+            }
+        };
+
+        if self.food == 0 {
+            return Err("food has to be at least 1".to_string());
+        }
+
+        for wall in &self.walls {
+            on_board("wall", wall)?;
+        }
+
+        let mut portal_ends: Vec<Coordinate> = Vec::new();
+        for (a, b) in &self.portals {
+            for end in [a, b] {
+                on_board("portal", end)?;
+                if self.is_wall(end) {
+                    return Err(format!("portal at {} is inside a wall", end));
+                }
+                if portal_ends.contains(end) {
+                    return Err(format!("more than one portal at {}", end));
+                }
+                portal_ends.push(*end);
+            }
+        }
+
+        if self.spawns.len() > MAX_PLAYERS {
+            return Err(format!(
+                "{} spawn points, but there are at most {} players",
+                self.spawns.len(),
+                MAX_PLAYERS
+            ));
+        }
+
+        // Player 1 always plays, so its default start has to fit too.
+        let mut occupied: Vec<Coordinate> = Vec::new();
+        for index in 0..self.spawns.len().max(1) {
+            let Spawn { head, direction } = self.start(index);
+            let player = index + 1;
+            on_board(&format!("player {}'s spawn point", player), &head)?;
+
+            // Levels are played with and without diagonal moves.
+            if direction.is_diagonal() {
+                return Err(format!(
+                    "player {}'s snake can't start facing {}",
+                    player,
+                    direction_name(direction)
+                ));
+            }
+            let (dx, dy) = direction.opposite().delta();
+            let (dx, dy) = (i32::from(dx), i32::from(dy));
+            for i in 0..SNAKE_LENGTH as i32 {
+                let x = head.0 as i32 + dx * i;
+                let y = head.1 as i32 + dy * i;
+                if x < 1 || y < 1 || x > w as i32 || y > h as i32 {
+                    return Err(format!(
+                        "player {}'s snake at {} facing {} doesn't fit on the board",
+                        player,
+                        head,
+                        direction_name(direction)
+                    ));
+                }
+
+                let cell = Coordinate(x as u16, y as u16);
+                if self.is_wall(&cell) {
+                    return Err(format!(
+                        "player {}'s snake starts in a wall at {}",
+                        player, cell
+                    ));
+                }
+                if portal_ends.contains(&cell) {
+                    return Err(format!(
+                        "player {}'s snake starts on a portal at {}",
+                        player, cell
+                    ));
+                }
+                if occupied.contains(&cell) {
+                    return Err(format!("the snakes start on top of each other at {}", cell));
+                }
+                occupied.push(cell);
+            }
+        }
+
+        for Zone(a, b) in &self.food_zones {
+            on_board("food zone corner", a)?;
+            on_board("food zone corner", b)?;
+        }
+
+        for FoodWeight {
+            zone: Zone(a, b),
+            weight,
+        } in &self.food_weights
+        {
+            on_board("food weight corner", a)?;
+            on_board("food weight corner", b)?;
+            if !(1..=MAX_FOOD_WEIGHT).contains(weight) {
+                return Err(format!(
+                    "food weight {} at {} has to be from 1 to {}",
+                    weight, a, MAX_FOOD_WEIGHT
+                ));
+            }
+        }
+
+        for Terrain {
+            zone: Zone(a, b), ..
+        } in &self.terrain
+        {
+            on_board("terrain corner", a)?;
+            on_board("terrain corner", b)?;
+        }
+
+        let mut gates: Vec<Coordinate> = Vec::new();
+        for Gate { cell, direction } in &self.gates {
+            on_board("gate", cell)?;
+            if self.is_wall(cell) || portal_ends.contains(cell) {
+                return Err(format!("gate at {} is inside a wall or on a portal", cell));
+            }
+            if direction.is_diagonal() {
+                return Err(format!(
+                    "gate at {} can't face {}",
+                    cell,
+                    direction_name(*direction)
+                ));
+            }
+            if gates.contains(cell) {
+                return Err(format!("more than one gate at {}", cell));
+            }
+            gates.push(*cell);
+        }
+
+        for Door { key, cells } in &self.doors {
+            on_board("key", key)?;
+            if self.is_wall(key) {
+                return Err(format!("key at {} is inside a wall", key));
+            }
+            if cells.is_empty() {
+                return Err(format!("the door for the key at {} has no cells", key));
+            }
+            for cell in cells {
+                on_board("door", cell)?;
+            }
+        }
+
+        for power_up in &self.bridge_power_ups {
+            on_board("bridge power-up", power_up)?;
+            if self.is_wall(power_up) {
+                return Err(format!("bridge power-up at {} is inside a wall", power_up));
+            }
+        }
+
+        let free = (1..=w)
+            .flat_map(|x| (1..=h).map(move |y| Coordinate(x, y)))
+            .filter(|c| self.allows_food(c) && !occupied.contains(c))
+            .count();
+        if free < self.food as usize {
+            return Err(format!(
+                "only {} cells can have food, but there is food for {}",
+                free, self.food
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn load(path: &std::path::Path) -> Result<Self, LevelError> {
+        let level = Level::read(path)?;
+        level.validate().map_err(LevelError::Invalid)?;
+        Ok(level)
+    }
+
+    // Like `load`, but also takes levels that can't be played yet, for the
+    // editor.
+    pub fn read(path: &std::path::Path) -> Result<Self, LevelError> {
+        let contents = std::fs::read_to_string(path)?;
+
+        if is_toml(path) {
+            toml::from_str(&contents).map_err(|e| {
+                let position = e
+                    .span()
+                    .map(|span| line_and_column(&contents, span.start))
+                    .unwrap_or_default();
+                LevelError::Syntax(format!("{}{}", position, e.message()))
+            })
+        } else {
+            ron::from_str(&contents).map_err(|e| LevelError::Syntax(e.to_string()))
+        }
+    }
+
+    pub fn save(&self, path: &std::path::Path) -> Result<(), String> {
+        let contents = if is_toml(path) {
+            toml::to_string(self).map_err(|e| e.to_string())?
+        } else {
+            ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+                .map_err(|e| e.to_string())?
+        };
+        std::fs::write(path, contents).map_err(|e| e.to_string())
+    }
+}
+
+// `numerator / denominator` of `largest` on each side, but not smaller than
+// `MIN_NETWORK_SIZE` or bigger than `largest`.
+pub fn part_of(largest: (u16, u16), numerator: u32, denominator: u32) -> (u16, u16) {
+    let side = |n: u16, min: u16| {
+        let part = (u32::from(n) * numerator / denominator) as u16;
+        part.max(min).min(n)
+    };
+    (
+        side(largest.0, MIN_NETWORK_SIZE.0),
+        side(largest.1, MIN_NETWORK_SIZE.1),
+    )
+}
+
+fn is_toml(path: &std::path::Path) -> bool {
+    path.extension().is_some_and(|e| e == "toml")
+}
+
+// In the same "line:column: " form as RON's errors.
+fn line_and_column(contents: &str, offset: usize) -> String {
+    let before = &contents[..offset.min(contents.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+
+    format!("{}:{}: ", line, column)
+}
+
+fn direction_name(direction: MoveDirection) -> &'static str {
+    match direction {
+        MoveDirection::Up => "up",
+        MoveDirection::Down => "down",
+        MoveDirection::Left => "left",
+        MoveDirection::Right => "right",
+        MoveDirection::UpLeft => "up_left",
+        MoveDirection::UpRight => "up_right",
+        MoveDirection::DownLeft => "down_left",
+        MoveDirection::DownRight => "down_right",
+    }
+}
@@ -0,0 +1,15 @@
+// The parts of snake that know nothing about terminals or the network, so
+// they also build for the browser (see web/).
+
+pub mod daylight;
+pub mod events;
+pub mod game;
+pub mod hex;
+pub mod import;
+pub mod input;
+pub mod level;
+pub mod lockstep;
+pub mod predict;
+pub mod random;
+pub mod reactions;
+pub mod replay;
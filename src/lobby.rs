@@ -0,0 +1,307 @@
+// Where both players of a game over the network meet before it starts. The
+// host picks the mode, the speed and how much of the board to use, both pick
+// a color, and the game starts once both are ready. Changing a setting makes
+// nobody ready again, so nobody starts a game they didn't see.
+
+use crate::game::Mode;
+use crate::l10n::{fill, tr};
+use crate::net::Message;
+use std::io::Write;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Speed {
+    Slow,
+    Normal,
+    Fast,
+}
+
+impl Speed {
+    pub fn tick_ms(self) -> u64 {
+        match self {
+            Speed::Slow => 220,
+            Speed::Normal => 150,
+            Speed::Fast => 90,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Board {
+    // As big as the smaller screen, like without a lobby.
+    Full,
+    Medium,
+    Small,
+}
+
+impl Board {
+    // How much of `largest` to play on.
+    pub fn size(self, largest: (u16, u16)) -> (u16, u16) {
+        let (w, h) = largest;
+        match self {
+            Board::Full => (w, h),
+            Board::Medium => crate::level::part_of(largest, 3, 4),
+            Board::Small => crate::level::part_of(largest, 1, 2),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Settings {
+    pub mode: Mode,
+    pub speed: Speed,
+    pub board: Board,
+}
+
+const MODES: [(Mode, &str); 2] = [(Mode::Versus, "versus"), (Mode::Coop, "coop")];
+const SPEEDS: [(Speed, &str); 3] = [
+    (Speed::Slow, "slow"),
+    (Speed::Normal, "normal"),
+    (Speed::Fast, "fast"),
+];
+const BOARDS: [(Board, &str); 3] = [
+    (Board::Full, "full"),
+    (Board::Medium, "medium"),
+    (Board::Small, "small"),
+];
+
+// The entry after or before `value` in `options`, going round.
+fn cycle<T: Copy + PartialEq>(options: &[(T, &str)], value: T, back: bool) -> T {
+    let index = options.iter().position(|(v, _)| *v == value).unwrap_or(0);
+    let len = options.len();
+    let next = if back { index + len - 1 } else { index + 1 };
+    options[next % len].0
+}
+
+fn name<T: PartialEq>(options: &[(T, &'static str)], value: T) -> &'static str {
+    options
+        .iter()
+        .find(|(v, _)| *v == value)
+        .map_or("", |(_, name)| name)
+}
+
+fn parse<T: Copy>(options: &[(T, &str)], name: &str) -> Option<T> {
+    options.iter().find(|(_, n)| *n == name).map(|(v, _)| *v)
+}
+
+impl Settings {
+    pub fn new(mode: Mode) -> Self {
+        Settings {
+            mode,
+            speed: Speed::Normal,
+            board: Board::Full,
+        }
+    }
+
+    // <mode> <speed> <board>, by name.
+    pub fn encode(&self) -> String {
+        format!(
+            "{} {} {}",
+            name(&MODES, self.mode),
+            name(&SPEEDS, self.speed),
+            name(&BOARDS, self.board)
+        )
+    }
+
+    pub fn decode(s: &str) -> Option<Self> {
+        let mut names = s.split(' ');
+        Some(Settings {
+            mode: parse(&MODES, names.next()?)?,
+            speed: parse(&SPEEDS, names.next()?)?,
+            board: parse(&BOARDS, names.next()?)?,
+        })
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Row {
+    Mode,
+    Speed,
+    Board,
+    Color,
+    Ready,
+}
+
+// The host can change everything, the other player only their own seat.
+const HOST_ROWS: [Row; 5] = [Row::Mode, Row::Speed, Row::Board, Row::Color, Row::Ready];
+const GUEST_ROWS: [Row; 2] = [Row::Color, Row::Ready];
+
+#[derive(Copy, Clone)]
+pub struct Seat {
+    // An index into `theme::COLORS`.
+    pub color: usize,
+    pub ready: bool,
+    // See `ratings`, only known for the other player once they've said.
+    pub rating: Option<u32>,
+}
+
+pub struct Lobby {
+    pub settings: Settings,
+    // The host's, then the other player's.
+    pub seats: [Seat; 2],
+    // Which seat is this side's.
+    pub local: usize,
+    row: usize,
+}
+
+impl Lobby {
+    pub fn new(local: usize, settings: Settings, rating: u32) -> Self {
+        let mut seats = [0, 1].map(|color| Seat {
+            color,
+            ready: false,
+            rating: None,
+        });
+        seats[local].rating = Some(rating);
+        Lobby {
+            settings,
+            seats,
+            local,
+            row: 0,
+        }
+    }
+
+    fn rows(&self) -> &'static [Row] {
+        match self.local {
+            0 => &HOST_ROWS,
+            _ => &GUEST_ROWS,
+        }
+    }
+
+    pub fn ready(&self) -> bool {
+        self.seats.iter().all(|seat| seat.ready)
+    }
+
+    pub fn colors(&self) -> [usize; 2] {
+        self.seats.map(|seat| seat.color)
+    }
+
+    // j and k go between the rows, h, l and enter change the one that's
+    // selected. Returns what to tell the other side.
+    pub fn handle_key(&mut self, key: u8) -> Vec<Message> {
+        let rows = self.rows();
+        let back = match key {
+            106 => {
+                self.row = (self.row + 1) % rows.len();
+                return Vec::new();
+            }
+            107 => {
+                self.row = (self.row + rows.len() - 1) % rows.len();
+                return Vec::new();
+            }
+            104 => true,
+            108 | 13 => false,
+            _ => return Vec::new(),
+        };
+
+        let settings = &mut self.settings;
+        match rows[self.row] {
+            Row::Mode => settings.mode = cycle(&MODES, settings.mode, back),
+            Row::Speed => settings.speed = cycle(&SPEEDS, settings.speed, back),
+            Row::Board => settings.board = cycle(&BOARDS, settings.board, back),
+            Row::Color => {
+                let colors = crate::theme::COLORS.len();
+                let taken = self.seats[1 - self.local].color;
+                let step = if back { colors - 1 } else { 1 };
+                let mut color = (self.seats[self.local].color + step) % colors;
+                if color == taken {
+                    color = (color + step) % colors;
+                }
+                self.seats[self.local].color = color;
+                return vec![Message::Color(color)];
+            }
+            Row::Ready => {
+                let seat = &mut self.seats[self.local];
+                seat.ready = !seat.ready;
+                return vec![Message::Ready(seat.ready)];
+            }
+        }
+        self.unready();
+        vec![Message::Lobby(self.settings)]
+    }
+
+    // Takes what the other side said, returning what to say back.
+    pub fn receive(&mut self, message: &Message) -> Vec<Message> {
+        let other = 1 - self.local;
+        match *message {
+            Message::Lobby(settings) => {
+                self.settings = settings;
+                // In case the host heard we were ready after changing them.
+                let was_ready = self.seats[self.local].ready;
+                self.unready();
+                if was_ready {
+                    return vec![Message::Ready(false)];
+                }
+            }
+            Message::Color(color) => self.seats[other].color = color,
+            Message::Ready(ready) => self.seats[other].ready = ready,
+            Message::Rating(rating) => self.seats[other].rating = Some(rating),
+            _ => {}
+        }
+        Vec::new()
+    }
+
+    fn unready(&mut self) {
+        for seat in &mut self.seats {
+            seat.ready = false;
+        }
+    }
+
+    pub fn render(
+        &self,
+        screen: &mut dyn Write,
+        screen_size: (u16, u16),
+    ) -> Result<(), std::io::Error> {
+        let top = (screen_size.1 / 2).saturating_sub(6).max(1);
+        // Players are in their colors.
+        let mut lines: Vec<(String, Option<termion::color::Rgb>)> =
+            vec![(tr("Lobby").to_string(), None), (String::new(), None)];
+
+        for (index, seat) in self.seats.iter().enumerate() {
+            let who = match (index, self.local == index) {
+                (_, true) => tr("You"),
+                (0, false) => tr("The host"),
+                _ => tr("The other player"),
+            };
+            let (color, head, _) = crate::theme::COLORS[seat.color];
+            let ready = if seat.ready {
+                tr("ready")
+            } else {
+                tr("not ready")
+            };
+            let line = match seat.rating {
+                Some(rating) => format!("{}  ██ {}  {}  {}", who, tr(color), ready, rating),
+                None => format!("{}  ██ {}  {}", who, tr(color), ready),
+            };
+            lines.push((line, Some(head)));
+        }
+        lines.push((String::new(), None));
+
+        let seat = self.seats[self.local];
+        for row in [Row::Mode, Row::Speed, Row::Board, Row::Color, Row::Ready] {
+            let (label, value) = match row {
+                Row::Mode => ("Mode: {}", name(&MODES, self.settings.mode)),
+                Row::Speed => ("Speed: {}", name(&SPEEDS, self.settings.speed)),
+                Row::Board => ("Board: {}", name(&BOARDS, self.settings.board)),
+                Row::Color => ("Color: {}", crate::theme::COLORS[seat.color].0),
+                Row::Ready => ("Ready: {}", if seat.ready { "yes" } else { "no" }),
+            };
+            let line = fill(label, &[&tr(value)]);
+            let line = match self.rows().iter().position(|r| *r == row) {
+                Some(index) if index == self.row => format!("> {} <", line),
+                _ => line,
+            };
+            lines.push((line, None));
+        }
+        lines.push((String::new(), None));
+        let help = tr("j/k to choose, h/l to change, q to leave");
+        lines.push((help.to_string(), None));
+
+        for (y, (line, color)) in (top..).zip(&lines) {
+            if let Some(color) = color {
+                write!(screen, "{}", termion::color::Fg(*color))?;
+            }
+            crate::ui::write_centered(screen, &screen_size, y, line)?;
+            write!(screen, "{}", termion::color::Fg(termion::color::Reset))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+// Playing the same game on two computers without one of them deciding what
+// happens. Both start from the same seed, and a turn only counts on a tick
+// both sides agreed on: one pressed while `game.tick` is t is sent for tick
+// t + DELAY, so it has DELAY moves' time to get there before it's needed. A
+// side that hasn't heard what the other did on a tick waits for it instead
+// of guessing, so the games never drift apart.
+//
+// Every tick gets sent, even without turns on it, so waiting means something
+// is missing rather than that nothing happened.
+
+use crate::game::{Game, Input, MoveDirection};
+
+// How many ticks ahead turns are sent.
+pub const DELAY: u32 = 3;
+
+pub struct Lockstep {
+    // The snake steered from here, the other one is steered over there.
+    local: usize,
+    // Pressed since the last tick was sent.
+    pressed: Vec<MoveDirection>,
+    // Every snake's turns by the tick they're for, until it's played.
+    turns: Vec<std::collections::BTreeMap<u32, Vec<MoveDirection>>>,
+    // The first tick our turns haven't been sent for.
+    sent: u32,
+}
+
+impl Lockstep {
+    pub fn new(local: usize, snakes: usize) -> Self {
+        Lockstep {
+            local,
+            pressed: Vec::new(),
+            turns: vec![Default::default(); snakes],
+            // Nobody can have turned before the start.
+            sent: DELAY,
+        }
+    }
+
+    pub fn press(&mut self, direction: MoveDirection) {
+        self.pressed.push(direction);
+    }
+
+    // The turns to send for the next tick that hasn't been, while `tick` is
+    // being played. None when that tick is already DELAY ahead.
+    pub fn send(&mut self, tick: u32) -> Option<(u32, Vec<MoveDirection>)> {
+        if self.sent > tick + DELAY {
+            return None;
+        }
+        let turns = std::mem::take(&mut self.pressed);
+        self.turns[self.local].insert(self.sent, turns.clone());
+        self.sent += 1;
+        Some((self.sent - 1, turns))
+    }
+
+    // What `snake` on the other side did on `tick`.
+    pub fn receive(&mut self, snake: usize, tick: u32, turns: Vec<MoveDirection>) {
+        if let Some(snakes) = self.turns.get_mut(snake) {
+            snakes.insert(tick, turns);
+        }
+    }
+
+    // Whether every snake's turns for the tick `game` is on are known.
+    pub fn ready(&self, game: &Game) -> bool {
+        game.tick < DELAY || self.turns.iter().all(|t| t.contains_key(&game.tick))
+    }
+
+    // Plays the tick, with every snake's turns in the order of the snakes,
+    // the same on both sides. Returns whether the game is over, or None while
+    // waiting for the other side.
+    pub fn tick(&mut self, game: &mut Game) -> Option<bool> {
+        if !self.ready(game) {
+            return None;
+        }
+        for (snake, turns) in self.turns.iter_mut().enumerate() {
+            for direction in turns.remove(&game.tick).unwrap_or_default() {
+                game.handle_input(snake, Input::Turn(direction));
+            }
+        }
+        Some(game.tick())
+    }
+}
@@ -1,296 +1,3242 @@
 extern crate termion;
 
-use std::io::{stdout, Read, Write};
-use termion::async_stdin;
-use termion::raw::IntoRawMode;
+mod ai;
+mod analysis;
+mod audio;
+mod bench;
+mod browser;
+mod camera;
+mod chat;
+mod cheats;
+mod config;
+mod console;
+mod control;
+mod crash;
+mod daemon;
+mod debug;
+mod editor;
+mod effects;
+mod engine;
+mod export;
+mod heatmap;
+mod honeycomb;
+mod l10n;
+#[cfg(feature = "online")]
+mod leaderboard;
+mod lobby;
+mod minimap;
+mod net;
+#[cfg(feature = "neural")]
+mod neural;
+mod paths;
+#[cfg(feature = "plugins")]
+mod plugin;
+mod ratings;
+mod rules;
+mod scores;
+#[cfg(feature = "lua")]
+mod script;
+mod selfplay;
+mod server;
+mod spectate;
+#[cfg(feature = "ssh")]
+mod ssh;
+mod stats;
+mod telnet;
+mod term;
+mod theme;
+mod tourney;
+mod twitch;
+mod ui;
+mod usage;
+mod wire;
 
-const TARGET_FPS: u8 = 60;
-const FRAME_DURATION: std::time::Duration =
-    std::time::Duration::from_millis(1000 / TARGET_FPS as u64);
-const MOVE_DURATION: std::time::Duration = std::time::Duration::from_millis(150);
+use snake::{game, import, input, level, lockstep, predict, random, replay};
 
-trait Render {
-    fn render(
-        &self,
-        screen: &mut termion::raw::RawTerminal<std::io::Stdout>,
-    ) -> Result<(), std::io::Error>;
+use game::{Coordinate, Food, FoodKind, Game, Input, Mode, MoveDirection, Player};
+use l10n::{fill, tr};
+use std::io::Write;
+
+// Frames are only how often the loops look at the keyboard and draw, the
+// snakes still move once a tick whatever this is. Fewer frames mean fewer
+// bytes for slow links, but keys are picked up later, and below the tick rate
+// some moves are never seen.
+const DEFAULT_FPS: u32 = 60;
+const MAX_FPS: u32 = 1000;
+// Frames per second, set by `--fps` before anything is drawn. 0 for
+// uncapped, where the loops never wait between frames.
+static FPS: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(DEFAULT_FPS);
+// Sleeping tends to overshoot by about this much, so the rest is waited out
+// by spinning.
+const SPIN_DURATION: std::time::Duration = std::time::Duration::from_millis(1);
+// Screens that only change when a key is pressed still redraw this often, to
+// keep up with the terminal being resized.
+const IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(250);
+const DEFAULT_TICK_MS: u64 = 150;
+// Milliseconds between moves at the start of a game, the speed counted as
+// 100%, set by `--tick-ms` before anything is drawn.
+static TICK_MS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(DEFAULT_TICK_MS);
+// How many columns and rows of the terminal a cell of the board takes up, set
+// from the config before anything is drawn.
+static CELL_WIDTH: std::sync::atomic::AtomicU16 = std::sync::atomic::AtomicU16::new(1);
+static CELL_HEIGHT: std::sync::atomic::AtomicU16 = std::sync::atomic::AtomicU16::new(1);
+// Whether food is drawn as emoji, set from the config before anything is
+// drawn.
+static EMOJI_FOOD: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+// Whether games against the computer are played on a big board with a half
+// of the screen following each snake, set from the config.
+static SPLIT_SCREEN: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+// How many times as wide and as tall as the terminal that board is.
+const SPLIT_BOARD: u16 = 2;
+// Whether the HUD points to the nearest food, set from the config.
+static FOOD_HINT: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+// Whether night falls now and then in games, see `snake::daylight`, set from
+// the config of whoever's watching. Replays don't record it.
+static DAY_NIGHT: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+// Whether the dark only dims the board rather than hiding most of it, set
+// from `reduced_motion` in the config.
+static REDUCED_MOTION: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+// How far + and - change the time between moves, and how far they go.
+const SPEED_STEP: std::time::Duration = std::time::Duration::from_millis(25);
+const FASTEST_MOVE: std::time::Duration = std::time::Duration::from_millis(50);
+const SLOWEST_MOVE: std::time::Duration = std::time::Duration::from_millis(400);
+
+// See `Config::speed_curve`, set from the config before anything is drawn.
+static SPEED_CURVE: std::sync::OnceLock<config::SpeedCurve> = std::sync::OnceLock::new();
+
+fn move_duration() -> std::time::Duration {
+    std::time::Duration::from_millis(TICK_MS.load(std::sync::atomic::Ordering::Relaxed))
 }
 
-#[derive(PartialEq)]
-enum MoveDirection {
-    Up,
-    Down,
-    Left,
-    Right,
+// The fewest frames a second that still draw every tick.
+fn min_fps(tick_ms: u64) -> u64 {
+    1000_u64.div_ceil(tick_ms)
 }
 
-#[derive(Copy, Clone, PartialEq)]
-struct Coordinate(u16, u16);
+// How long moves took when `replay` started, or this side's speed for replays
+// that don't say.
+fn replay_move_duration(replay: &replay::Replay) -> std::time::Duration {
+    replay.tick_ms.map_or(move_duration(), |ms| {
+        std::time::Duration::from_millis(ms as u64)
+    })
+}
 
-struct Food {
-    location: Coordinate,
+// How a game moves on.
+#[derive(Copy, Clone, PartialEq)]
+enum Pace {
+    Normal,
+    // z rewinds, which makes the replay no good.
+    Practice,
+    // The snake only moves when a key is pressed, and z undoes moves, for
+    // puzzle levels. Not u, which turns up and right in diagonal games.
+    Puzzle,
 }
 
-struct Player {
-    move_direction: MoveDirection,
-    segments: std::collections::VecDeque<Coordinate>,
+// In practice games z goes back `REWIND_TICKS` moves, as far back as
+// `HISTORY` moves, and waits a moment before carrying on.
+const REWIND_TICKS: usize = 20;
+const HISTORY: usize = 100;
+const REWIND_PAUSE: std::time::Duration = std::time::Duration::from_millis(500);
+
+trait Render {
+    fn render(&self, screen: &mut dyn Write) -> Result<(), std::io::Error>;
 }
 
-impl Player {
-    fn new() -> Self {
-        let mut player = Player {
-            move_direction: MoveDirection::Right,
-            segments: std::collections::VecDeque::new(),
-        };
+impl Render for Player {
+    fn render(&self, screen: &mut dyn Write) -> Result<(), std::io::Error> {
+        let (head, body) = theme::snake(0);
+        render_snake(screen, self, head, body)
+    }
+}
 
-        for i in 1..5 {
-            player.segments.push_front(Coordinate(i, 1));
+fn render_snake(
+    screen: &mut dyn Write,
+    player: &Player,
+    head_color: termion::color::Rgb,
+    body_color: termion::color::Rgb,
+) -> Result<(), std::io::Error> {
+    // Colors stay set while the cursor moves, so every run of one color only
+    // needs setting once.
+    for (index, Coordinate(x, y)) in player.segments.iter().enumerate() {
+        match index {
+            0 => write!(screen, "{}", termion::color::Bg(head_color))?,
+            1 => write!(screen, "{}", termion::color::Bg(body_color))?,
+            _ => {}
         }
+        draw_cell(screen, Coordinate(*x, *y), ' ')?;
+    }
+    write!(screen, "{}", termion::color::Bg(termion::color::Reset))
+}
 
-        player
+fn render_food(screen: &mut dyn Write, food: &[Food]) -> Result<(), std::io::Error> {
+    let emoji = EMOJI_FOOD.load(std::sync::atomic::Ordering::Relaxed);
+    write!(
+        screen,
+        "{}",
+        termion::color::Fg(termion::color::Rgb(0, 0, 0))
+    )?;
+    for food in food {
+        let style = &theme::current().food[match food.kind {
+            FoodKind::Apple => 0,
+            FoodKind::Cherry => 1,
+            FoodKind::Mouse => 2,
+            FoodKind::Feast => 3,
+        }];
+        let size = food.kind.size();
+        if emoji {
+            write!(screen, "{}", termion::color::Bg(termion::color::Reset))?;
+            draw_block(screen, food.location, size, style.emoji)?;
+        } else {
+            write!(screen, "{}", termion::color::Bg(style.color))?;
+            draw_block(screen, food.location, size, style.letter)?;
+        }
     }
+    write!(
+        screen,
+        "{}{}",
+        termion::color::Bg(termion::color::Reset),
+        termion::color::Fg(termion::color::Reset),
+    )
+}
 
-    fn change_direction(&mut self, new_direction: MoveDirection) {
-        if match new_direction {
-            MoveDirection::Up => self.move_direction == MoveDirection::Down,
-            MoveDirection::Down => self.move_direction == MoveDirection::Up,
-            MoveDirection::Left => self.move_direction == MoveDirection::Right,
-            MoveDirection::Right => self.move_direction == MoveDirection::Left,
-        } {
-            return;
+fn cell_size() -> (u16, u16) {
+    (
+        CELL_WIDTH.load(std::sync::atomic::Ordering::Relaxed),
+        CELL_HEIGHT.load(std::sync::atomic::Ordering::Relaxed),
+    )
+}
+
+// Where the top left of a cell of the board is on the screen.
+fn cell_position(Coordinate(x, y): Coordinate) -> (u16, u16) {
+    let (w, h) = cell_size();
+    match camera::viewport() {
+        Some(camera::Viewport { origin, corner, .. }) => {
+            ((x - origin.0) * w + corner.0, (y - origin.1) * h + corner.1)
         }
+        None => ((x - 1) * w + 1, (y - 1) * h + 1),
+    }
+}
+
+// Fills a cell of the board with whatever colors are set, `glyph` in its top
+// left corner. Glyphs can be two columns wide, emoji and most CJK, so the rest
+// of the row is filled by what's left over. One that won't fit in the cell
+// would push everything after it along, so it's left out and only the colors
+// show.
+fn draw_cell(screen: &mut dyn Write, cell: Coordinate, glyph: char) -> Result<(), std::io::Error> {
+    draw_block(screen, cell, 1, glyph)
+}
 
-        self.move_direction = new_direction;
+// The same for a square `size` cells across with `cell` in its top left, drawn
+// as one block with a single glyph.
+fn draw_block(
+    screen: &mut dyn Write,
+    cell: Coordinate,
+    size: u16,
+    glyph: char,
+) -> Result<(), std::io::Error> {
+    let Coordinate(x, y) = cell;
+    // The cells of the block that are in the viewport, all of them without
+    // one. The glyph only goes with the top left.
+    let (mut columns, mut rows) = (x..x + size, y..y + size);
+    if let Some(camera::Viewport { origin, size, .. }) = camera::viewport() {
+        columns = columns.start.max(origin.0)..columns.end.min(origin.0 + size.0);
+        rows = rows.start.max(origin.1)..rows.end.min(origin.1 + size.1);
     }
+    if columns.is_empty() || rows.is_empty() {
+        return Ok(());
+    }
+    let whole = columns.start == x && rows.start == y;
 
-    fn check_collisions(&self, coord: &Coordinate, (screen_w, screen_h): &(u16, u16)) -> bool {
-        if coord.0 > *screen_w || coord.1 > *screen_h || coord.0 < 1 || coord.1 < 1 {
-            return true;
-        }
+    let (w, h) = cell_size();
+    let (w, h) = (w * columns.len() as u16, h * rows.len() as u16);
+    let (x, y) = cell_position(Coordinate(columns.start, rows.start));
+    let glyph_width = match unicode_width::UnicodeWidthChar::width(glyph) {
+        Some(width) if width as u16 <= w && whole => width as u16,
+        _ => 0,
+    };
 
-        self.segments.contains(coord)
+    for row in 0..h {
+        write!(screen, "{}", termion::cursor::Goto(x, y + row))?;
+        let mut column = 0;
+        if row == 0 && glyph_width > 0 {
+            write!(screen, "{}", glyph)?;
+            column = glyph_width;
+        }
+        for _ in column..w {
+            write!(screen, " ")?;
+        }
     }
+    Ok(())
+}
 
-    fn elongate(&mut self, screen_size: &(u16, u16)) {
-        let last_segment = *self.segments.back().unwrap();
+// How much of the screen a board this size takes up.
+fn board_screen_size(board_size: (u16, u16)) -> (u16, u16) {
+    let (w, h) = cell_size();
+    (board_size.0 * w, board_size.1 * h)
+}
 
-        let direction: &MoveDirection = if self.segments.len() >= 2 {
-            let second_last = self.segments.iter().nth_back(1).unwrap();
+// The biggest board that fits on a screen this size.
+fn board_size_for(screen_size: (u16, u16)) -> (u16, u16) {
+    let (w, h) = cell_size();
+    (screen_size.0 / w, screen_size.1 / h)
+}
 
-            match (
-                (last_segment.0 as i32 - second_last.0 as i32),
-                (last_segment.1 as i32 - second_last.1 as i32),
-            ) {
-                (1, 0) => &MoveDirection::Right,
-                (-1, 0) => &MoveDirection::Left,
-                (0, 1) => &MoveDirection::Down,
-                (0, -1) => &MoveDirection::Up,
-                _ => panic!("This shouldn't happen. Nonexisting movement direction."),
-            }
-        } else {
-            &self.move_direction
-        };
-        let new_segment = match direction {
-            MoveDirection::Up => Coordinate(last_segment.0, last_segment.1 - 1),
-            MoveDirection::Down => Coordinate(last_segment.0, last_segment.1 + 1),
-            MoveDirection::Left => Coordinate(last_segment.0 - 1, last_segment.1),
-            MoveDirection::Right => Coordinate(last_segment.0 + 1, last_segment.1),
-        };
+fn clear(screen: &mut dyn Write) -> Result<(), std::io::Error> {
+    write!(
+        screen,
+        "{}{}",
+        termion::cursor::Goto(1, 1),
+        termion::clear::All
+    )
+}
 
-        if !self.check_collisions(&new_segment, screen_size) {
-            self.segments.push_back(new_segment);
-        }
+fn change_speed(
+    move_duration: std::time::Duration,
+    change: replay::SpeedChange,
+) -> std::time::Duration {
+    match change {
+        replay::SpeedChange::Faster => move_duration.saturating_sub(SPEED_STEP),
+        replay::SpeedChange::Slower => move_duration + SPEED_STEP,
     }
+    .clamp(FASTEST_MOVE, SLOWEST_MOVE)
+}
 
-    fn update_pos(&mut self, screen_size: &(u16, u16)) -> bool {
-        let head = &self.segments[0];
+// How fast the game is going compared to usual, bottom right.
+fn render_speed(
+    screen: &mut dyn Write,
+    board_size: (u16, u16),
+    move_duration: std::time::Duration,
+) -> Result<(), std::io::Error> {
+    let speed = fill(
+        " Speed: {}% ",
+        &[&(crate::move_duration().as_secs_f64() / move_duration.as_secs_f64() * 100.0).round()],
+    );
+    let (w, h) = board_screen_size(board_size);
+    write!(
+        screen,
+        "{}{}",
+        termion::cursor::Goto(w.saturating_sub(ui::text_width(&speed)).max(1), h),
+        speed
+    )
+}
 
-        let new_coord = match self.move_direction {
-            MoveDirection::Up => Coordinate(head.0, head.1 - 1),
-            MoveDirection::Down => Coordinate(head.0, head.1 + 1),
-            MoveDirection::Left => Coordinate(head.0 - 1, head.1),
-            MoveDirection::Right => Coordinate(head.0 + 1, head.1),
-        };
+// Waits until `next_frame` is due, then moves it on a frame. Keeping to a
+// timetable means the time it takes to draw a frame doesn't add up, unlike
+// sleeping a frame's length after every one.
+fn limit_fps(next_frame: &mut std::time::Instant) {
+    let fps = FPS.load(std::sync::atomic::Ordering::Relaxed);
+    if fps == 0 {
+        *next_frame = std::time::Instant::now();
+        return;
+    }
+    let frame_duration = std::time::Duration::from_secs_f64(1.0 / fps as f64);
 
-        if self.check_collisions(&new_coord, screen_size) {
-            return true;
+    let now = std::time::Instant::now();
+    if *next_frame > now {
+        if let Some(sleep) = (*next_frame - now).checked_sub(SPIN_DURATION) {
+            std::thread::sleep(sleep);
+        }
+        while std::time::Instant::now() < *next_frame {
+            std::hint::spin_loop();
         }
+    } else if now - *next_frame > frame_duration {
+        // Rushing to catch up after falling behind only makes things jumpy.
+        *next_frame = now;
+    }
 
-        self.segments.push_front(new_coord);
-        self.segments.pop_back();
+    *next_frame += frame_duration;
+}
 
-        false
-    }
+// Games keep the board they started with, so a terminal shrinking below it
+// holds the game up until it's big enough again, rather than parts of the
+// board carrying on out of sight.
+fn fits(screen_size: (u16, u16), board_size: (u16, u16)) -> bool {
+    let board_size = board_screen_size(board_size);
+    screen_size.0 >= board_size.0 && screen_size.1 >= board_size.1
 }
 
-impl Render for Player {
-    fn render(
-        &self,
-        screen: &mut termion::raw::RawTerminal<std::io::Stdout>,
-    ) -> Result<(), std::io::Error> {
-        for (index, Coordinate(x, y)) in self.segments.iter().enumerate() {
-            let color = match index {
-                0 => termion::color::Rgb(0, 255, 0),
-                _ => termion::color::Rgb(255, 255, 255),
-            };
+// With a map of the board above the message, where there's room.
+fn render_too_small(
+    screen: &mut dyn Write,
+    screen_size: (u16, u16),
+    game: &Game,
+) -> Result<(), std::io::Error> {
+    let middle = screen_size.1 / 2;
+    at_night(dark(game), || {
+        minimap::render(
+            screen,
+            (screen_size.0, middle.saturating_sub(2)),
+            &game.level,
+            &game.players,
+            &game.food,
+        )
+    })?;
+    let board_size = board_screen_size(game.board_size);
+    ui::write_centered(screen, &screen_size, middle.saturating_sub(1), tr("Paused"))?;
+    ui::write_centered(
+        screen,
+        &screen_size,
+        middle,
+        &fill(
+            "Make the terminal {}x{} again",
+            &[&board_size.0, &board_size.1],
+        ),
+    )
+}
 
-            if let Err(e) = write!(
+// The letter to eat next stands out from the rest.
+fn render_letters(screen: &mut dyn Write, letters: &game::Letters) -> Result<(), std::io::Error> {
+    for (index, (letter, cell)) in letters.left.iter().enumerate() {
+        match index {
+            0 => write!(
                 screen,
-                "{}{} {}",
-                termion::cursor::Goto(*x, *y),
-                termion::color::Bg(color),
-                termion::color::Bg(termion::color::Reset),
-            ) {
-                return Err(e);
-            }
+                "{}{}",
+                termion::color::Bg(theme::shade(LETTER_COLOR)),
+                termion::color::Fg(termion::color::Rgb(0, 0, 0))
+            )?,
+            _ => write!(screen, "{}", termion::color::Fg(theme::shade(LETTER_COLOR)))?,
         }
-        Ok(())
+        draw_cell(screen, *cell, *letter)?;
+        write!(screen, "{}", termion::style::Reset)?;
     }
+    Ok(())
+}
+
+// Whether `cell` is no further than `vision` from any snake's head, across,
+// down or diagonally.
+fn in_sight(players: &[Player], cell: Coordinate, vision: u16) -> bool {
+    players
+        .iter()
+        .any(|p| p.head().0.abs_diff(cell.0).max(p.head().1.abs_diff(cell.1)) <= vision)
+}
+
+fn night(tick: u32) -> bool {
+    DAY_NIGHT.load(std::sync::atomic::Ordering::Relaxed) && snake::daylight::night(tick)
+}
+
+fn reduced_motion() -> bool {
+    REDUCED_MOTION.load(std::sync::atomic::Ordering::Relaxed)
 }
 
-impl Food {
-    fn new(screen_size: &(u16, u16), player: &Player) -> Self {
-        Food {
-            location: random_location(screen_size, player),
+// Whether `game` is drawn in the night's colors, which with reduced motion
+// is also how a random event's darkness looks.
+fn dark(game: &Game) -> bool {
+    night(game.tick)
+        || (reduced_motion() && game.random_event().is_some_and(|e| e.vision().is_some()))
+}
+
+// Draws in the night's colors when `dark`.
+fn at_night<T>(dark: bool, draw: impl FnOnce() -> T) -> T {
+    theme::set_night(dark);
+    let drawn = draw();
+    theme::set_night(false);
+    drawn
+}
+
+// How far around the heads the board can be seen on `tick`, if not
+// everywhere. Everywhere with reduced motion, which only dims it.
+fn night_vision(tick: u32) -> Option<u16> {
+    (night(tick) && !reduced_motion()).then_some(snake::daylight::NIGHT_VISION)
+}
+
+// The same at night or in a random event, whichever sees less.
+fn vision(game: &Game) -> Option<u16> {
+    let event = game
+        .random_event()
+        .and_then(|e| e.vision())
+        .filter(|_| !reduced_motion());
+    night_vision(game.tick).into_iter().chain(event).min()
+}
+
+// Blanks every cell out of sight, leaving the snakes to be drawn on top, and
+// returns the food that can still be seen.
+fn render_sight(
+    screen: &mut dyn Write,
+    board_size: (u16, u16),
+    players: &[Player],
+    food: &[Food],
+    vision: Option<u16>,
+) -> Result<Vec<Food>, std::io::Error> {
+    let Some(vision) = vision else {
+        return Ok(food.to_vec());
+    };
+    let (w, h) = board_size;
+    for cell in (1..=h).flat_map(|y| (1..=w).map(move |x| Coordinate(x, y))) {
+        if !in_sight(players, cell, vision) {
+            draw_cell(screen, cell, ' ')?;
         }
     }
+    Ok(food
+        .iter()
+        .filter(|f| in_sight(players, f.location, vision))
+        .copied()
+        .collect())
+}
 
-    fn check_eaten(&mut self, screen_size: &(u16, u16), player: &mut Player) -> bool {
-        if *player.segments.front().unwrap() != self.location {
-            return false;
+fn render_game(screen: &mut dyn Write, game: &Game) -> Result<(), std::io::Error> {
+    at_night(dark(game), || {
+        render_level(screen, &game.level)?;
+        if let Some(letters) = &game.letters {
+            render_letters(screen, letters)?;
+        }
+        let active = match game.mode {
+            Mode::Twins => Some(game.active),
+            _ => None,
         };
 
-        self.location = random_location(screen_size, player);
-        player.elongate(screen_size);
+        let food = render_sight(
+            screen,
+            game.board_size,
+            &game.players,
+            &game.food,
+            vision(game),
+        )?;
+        render_board(screen, game.board_size, &game.players, &food, active)?;
 
-        true
-    }
+        let board_size = board_screen_size(game.board_size);
+        if let Some(event) = game.random_event() {
+            ui::write_centered(screen, &board_size, 1, tr(event.announcement()))?;
+        }
+        // Which way the wind blows, above the speed.
+        if let Some(wind) = game.wind {
+            let middle = Coordinate(2, 2);
+            let label = fill(" Wind: {} ", &[&hint_arrow(middle, middle.step(wind))]);
+            let column = board_size.0.saturating_sub(ui::text_width(&label)).max(1);
+            write!(screen, "{}{}", termion::cursor::Goto(column, 1), label)?;
+        }
+        Ok(())
+    })
 }
-impl Render for Food {
-    fn render(
-        &self,
-        screen: &mut termion::raw::RawTerminal<std::io::Stdout>,
-    ) -> Result<(), std::io::Error> {
+
+// Both ends of a portal get the same color, so they can be told apart.
+const PORTAL_COLORS: [termion::color::Rgb; 3] = [
+    termion::color::Rgb(160, 0, 255),
+    termion::color::Rgb(0, 200, 200),
+    termion::color::Rgb(255, 160, 0),
+];
+
+const DOOR_COLORS: [termion::color::Rgb; 3] = [
+    termion::color::Rgb(200, 40, 40),
+    termion::color::Rgb(40, 160, 40),
+    termion::color::Rgb(60, 90, 220),
+];
+const LETTER_COLOR: termion::color::Rgb = termion::color::Rgb(255, 200, 0);
+const GATE_COLOR: termion::color::Rgb = termion::color::Rgb(255, 220, 0);
+
+fn terrain_color(kind: level::TerrainKind) -> termion::color::Rgb {
+    theme::shade(match kind {
+        level::TerrainKind::Boost => termion::color::Rgb(30, 60, 110),
+        level::TerrainKind::Mud => termion::color::Rgb(80, 55, 30),
+        level::TerrainKind::Ice => termion::color::Rgb(120, 170, 200),
+        level::TerrainKind::Water => termion::color::Rgb(10, 30, 160),
+        level::TerrainKind::Bridge => termion::color::Rgb(140, 100, 60),
+    })
+}
+
+fn render_level(screen: &mut dyn Write, level: &level::Level) -> Result<(), std::io::Error> {
+    let (w, h) = level.size;
+    for cell in (1..=h).flat_map(|y| (1..=w).map(move |x| Coordinate(x, y))) {
+        if let Some(kind) = level.terrain_at(&cell) {
+            write!(screen, "{}", termion::color::Bg(terrain_color(kind)))?;
+            draw_cell(screen, cell, ' ')?;
+        }
+    }
+
+    write!(screen, "{}", termion::color::Bg(theme::current().wall))?;
+    for wall in level.walls.iter().copied().chain(level.mask_edge()) {
+        draw_cell(screen, wall, ' ')?;
+    }
+    write!(screen, "{}", termion::color::Bg(termion::color::Reset))?;
+
+    for (index, (a, b)) in level.portals.iter().enumerate() {
+        write!(
+            screen,
+            "{}",
+            termion::color::Fg(theme::shade(PORTAL_COLORS[index % PORTAL_COLORS.len()])),
+        )?;
+        draw_cell(screen, *a, 'O')?;
+        draw_cell(screen, *b, 'O')?;
+    }
+
+    // Every door and its key in the same color.
+    for (index, door) in level.doors.iter().enumerate() {
+        let color = theme::shade(DOOR_COLORS[index % DOOR_COLORS.len()]);
+        write!(screen, "{}", termion::color::Bg(color))?;
+        for cell in &door.cells {
+            draw_cell(screen, *cell, ' ')?;
+        }
         write!(
             screen,
-            "{}{}{}'{}{}",
-            termion::cursor::Goto(self.location.0, self.location.1),
-            termion::color::Bg(termion::color::Rgb(255, 0, 0)),
-            termion::color::Fg(termion::color::Rgb(0, 0, 0)),
+            "{}{}",
             termion::color::Bg(termion::color::Reset),
-            termion::color::Fg(termion::color::Reset),
-        )
+            termion::color::Fg(color)
+        )?;
+        draw_cell(screen, door.key, 'k')?;
     }
+
+    write!(screen, "{}", termion::color::Fg(theme::shade(GATE_COLOR)))?;
+    for gate in &level.gates {
+        let arrow = hint_arrow(gate.cell, gate.cell.step(gate.direction));
+        draw_cell(screen, gate.cell, arrow)?;
+    }
+
+    let bridge = terrain_color(level::TerrainKind::Bridge);
+    write!(screen, "{}", termion::color::Fg(bridge))?;
+    for power_up in &level.bridge_power_ups {
+        draw_cell(screen, *power_up, '=')?;
+    }
+    write!(screen, "{}", termion::color::Fg(termion::color::Reset))
+}
+
+fn render_snakes(screen: &mut dyn Write, players: &[Player]) -> Result<(), std::io::Error> {
+    for (index, player) in players.iter().enumerate() {
+        match index {
+            0 => player.render(screen)?,
+            _ => {
+                let (head, body) = theme::snake(1);
+                render_snake(screen, player, head, body)?
+            }
+        }
+    }
+    Ok(())
+}
+
+// Draws the board through `viewport`, with the level when there is one.
+fn render_viewport(
+    screen: &mut dyn Write,
+    viewport: camera::Viewport,
+    level: Option<&level::Level>,
+    players: &[Player],
+    food: &[Food],
+) -> Result<(), std::io::Error> {
+    camera::set_viewport(Some(viewport));
+    let drawn = level
+        .map_or(Ok(()), |level| render_level(screen, level))
+        .and_then(|()| render_food(screen, food))
+        .and_then(|()| render_snakes(screen, players));
+    camera::set_viewport(None);
+    drawn
 }
 
-fn random_location(screen: &(u16, u16), player: &Player) -> Coordinate {
-    let mut x = 0;
-    let mut y = 0;
+// Versus on a board too big for the screen: every snake gets a pane of its
+// own following it around, side by side, with its score underneath.
+fn render_split(
+    screen: &mut dyn Write,
+    screen_size: (u16, u16),
+    game: &Game,
+) -> Result<(), std::io::Error> {
+    let (w, h) = cell_size();
+    let panes = game.players.len().max(1) as u16;
+    // Leaving a column between panes and a row for the scores.
+    let size = (
+        ((screen_size.0 + 1) / panes).saturating_sub(1) / w,
+        screen_size.1.saturating_sub(1) / h,
+    );
+    if size.0 == 0 || size.1 == 0 {
+        return Ok(());
+    }
+
+    for (index, player) in (0..).zip(&game.players) {
+        let corner = (1 + index * (size.0 * w + 1), 1);
+        let viewport = camera::Camera::following(index as usize).viewport(
+            &game.players,
+            game.board_size,
+            size,
+            corner,
+        );
+        camera::set_viewport(Some(viewport));
+        let drawn = at_night(dark(game), || {
+            render_level(screen, &game.level)?;
+            let food = render_sight(
+                screen,
+                game.board_size,
+                &game.players,
+                &game.food,
+                vision(game),
+            )?;
+            render_food(screen, &food)?;
+            render_snakes(screen, &game.players)
+        });
+        camera::set_viewport(None);
+        drawn?;
 
-    while player.check_collisions(&Coordinate(x, y), screen) {
-        x = rand::random_range(1..screen.0);
-        y = rand::random_range(1..screen.1);
+        write!(
+            screen,
+            "{} {}",
+            termion::cursor::Goto(corner.0, size.1 * h + 1),
+            fill("Score: {}", &[&player.score]),
+        )?;
+        if index > 0 {
+            for row in 1..=size.1 * h {
+                write!(screen, "{}│", termion::cursor::Goto(corner.0 - 1, row))?;
+            }
+        }
     }
+    Ok(())
+}
 
-    Coordinate(x, y)
+// A game going on somewhere else, which only nights hide parts of.
+fn render_snapshot(
+    screen: &mut dyn Write,
+    board_size: (u16, u16),
+    snapshot: &net::Snapshot,
+) -> Result<(), std::io::Error> {
+    at_night(night(snapshot.tick), || {
+        let (players, food) = (&snapshot.players, &snapshot.food);
+        let food = render_sight(
+            screen,
+            board_size,
+            players,
+            food,
+            night_vision(snapshot.tick),
+        )?;
+        render_board(screen, board_size, players, &food, None)
+    })
 }
 
-fn clear(screen: &mut termion::raw::RawTerminal<std::io::Stdout>) -> Result<(), std::io::Error> {
+fn render_board(
+    screen: &mut dyn Write,
+    board_size: (u16, u16),
+    players: &[Player],
+    food: &[Food],
+    // The snake being steered, when one player has more than one.
+    active: Option<usize>,
+) -> Result<(), std::io::Error> {
+    render_food(screen, food)?;
+    render_snakes(screen, players)?;
+
+    let hud = match (players, active) {
+        (_, Some(active)) => fill(
+            "Score: {}  Steering: {}",
+            &[
+                &players.iter().map(|p| p.score).sum::<u32>(),
+                &if active == 0 { tr("green") } else { tr("blue") },
+            ],
+        ),
+        ([player], _) if player.lives > 1 => {
+            fill("Score: {}  Lives: {}", &[&player.score, &player.lives])
+        }
+        ([player], _) => fill("Score: {}", &[&player.score]),
+        _ => {
+            let scores = [0, 1].map(|i| players.get(i).map_or(0, |p| p.score));
+            match theme::picked() {
+                Some(colors) => fill(
+                    "{}: {}  {}: {}",
+                    &[
+                        &tr(theme::COLORS[colors[0]].0),
+                        &scores[0],
+                        &tr(theme::COLORS[colors[1]].0),
+                        &scores[1],
+                    ],
+                ),
+                None => fill("Green: {}  Blue: {}", &[&scores[0], &scores[1]]),
+            }
+        }
+    };
+
+    // Only for a snake someone's steering alone.
+    let steered = match (players, active) {
+        (_, Some(snake)) => Some(snake),
+        ([_], None) => Some(0),
+        _ => None,
+    };
+    let hint = steered
+        .filter(|_| FOOD_HINT.load(std::sync::atomic::Ordering::Relaxed))
+        .and_then(|snake| {
+            let head = players[snake].head();
+            let food = players[snake].nearest_food(food)?;
+            Some(format!("  {}", hint_arrow(head, food)))
+        });
+
+    let bottom = board_screen_size(board_size).1;
     write!(
         screen,
-        "{}{}",
-        termion::cursor::Goto(1, 1),
-        termion::clear::All
+        "{} {}{}{} ",
+        termion::cursor::Goto(2, bottom),
+        hud,
+        hint.unwrap_or_default(),
+        termion::cursor::Goto(0, bottom),
     )
 }
 
-fn main() {
-    let mut screen = stdout().into_raw_mode().unwrap();
-    let mut stdin = async_stdin().bytes();
-    let mut screen_size = termion::terminal_size().unwrap();
-    clear(&mut screen).unwrap();
+// Which of eight ways `to` is from `from`, straight unless it's at least half
+// as far the other way.
+fn hint_arrow(from: Coordinate, to: Coordinate) -> char {
+    let (dx, dy) = (to.0 as i32 - from.0 as i32, to.1 as i32 - from.1 as i32);
+    let horizontal = if 2 * dx.abs() < dy.abs() {
+        0
+    } else {
+        dx.signum()
+    };
+    let vertical = if 2 * dy.abs() < dx.abs() {
+        0
+    } else {
+        dy.signum()
+    };
+    match (horizontal, vertical) {
+        (-1, -1) => '↖',
+        (0, -1) => '↑',
+        (1, -1) => '↗',
+        (-1, 0) => '←',
+        (1, 0) => '→',
+        (-1, 1) => '↙',
+        (0, 1) => '↓',
+        (1, 1) => '↘',
+        _ => '·',
+    }
+}
 
-    let mut player = Player::new();
-    let mut food = vec![
-        Food::new(&screen_size, &player),
-        Food::new(&screen_size, &player),
-        Food::new(&screen_size, &player),
-        Food::new(&screen_size, &player),
-    ];
-    let mut score: u8 = 0;
+// A game set up the way the config has it.
+fn new_game(config: &config::Config, mode: Mode, board_size: (u16, u16)) -> Game {
+    let mut game = Game::new(mode, rand::random(), board_size);
+    game.set_lives(config.lives.unwrap_or(1));
+    game.bumper = config.bumper;
+    game.diagonal = config.diagonal;
+    game.set_wind(config.wind);
+    game.grace = config.grace.unwrap_or(0);
+    game
+}
+
+fn play(
+    screen: &mut dyn term::Terminal,
+    spectators: Option<&spectate::Spectators>,
+    control: Option<&control::Control>,
+    effects: &mut effects::Effects,
+    mut game: Game,
+    cheats: &cheats::Cheats,
+    rules: Option<Box<dyn rules::Rules>>,
+) -> (u32, replay::Replay) {
+    let cheated = cheats.apply(&mut game);
+    let (game, mut replay) = play_game(
+        screen,
+        game,
+        spectators,
+        control,
+        effects,
+        rules,
+        Pace::Normal,
+    );
+    // Sealed again, the checksum covers the inputs too.
+    if !cheated.is_empty() {
+        replay.inputs.splice(0..0, cheated);
+        replay.seal(&game);
+    }
+    replay.assisted |= cheats.any();
+
+    // Losing the replay isn't worth interrupting the game over screen for.
+    let _ = replay.save(&paths::last_replay_file());
+
+    (game.players.iter().map(|p| p.score).sum(), replay)
+}
+
+// A game that doesn't count for anything, where mistakes can be rewound.
+fn practice(
+    screen: &mut dyn term::Terminal,
+    spectators: Option<&spectate::Spectators>,
+    control: Option<&control::Control>,
+    effects: &mut effects::Effects,
+    config: &config::Config,
+) {
+    let mut game = Game::new(Mode::Classic, rand::random(), board_size_for(screen.size()));
+    game.grace = config.grace.unwrap_or(0);
+    let (game, _) = play_game(
+        screen,
+        game,
+        spectators,
+        control,
+        effects,
+        None,
+        Pace::Practice,
+    );
+
+    message(
+        screen,
+        &[&fill("Practice over. Score: {}", &[&game.players[0].score])],
+    );
+}
+
+// Plays a single player game until it's over or given up on.
+fn play_game(
+    screen: &mut dyn term::Terminal,
+    mut game: Game,
+    spectators: Option<&spectate::Spectators>,
+    control: Option<&control::Control>,
+    effects: &mut effects::Effects,
+    mut rules: Option<Box<dyn rules::Rules>>,
+    pace: Pace,
+) -> (Game, replay::Replay) {
+    let mut screen_size = screen.size();
+    clear(screen).unwrap();
+
+    let mut replay = replay::Replay::new(&game);
+    let mut metrics = analysis::Metrics::new(&game);
+    effects.start();
+    let mut paused = false;
+    let curve = SPEED_CURVE.get();
+    let mut score: u32 = game.players.iter().map(|p| p.score).sum();
+    let mut move_duration = curve.map_or(crate::move_duration(), |curve| curve.at(score));
+    replay.tick_ms = Some(move_duration.as_millis() as u16);
+    effects.set_move_duration(move_duration);
+    // Between moves nothing changes, so most frames don't need drawing.
+    let mut dirty = true;
+    let mut overlay = debug::Overlay::default();
+    let mut console = console::Console::default();
+    // Steers towards food until the player turns, o turns it on and off. The
+    // game doesn't make the high scores once it has.
+    let mut autopilot: Option<Box<dyn ai::Controller>> = None;
+    let mut turned = false;
+    // The game before each of the last `HISTORY` moves, for rewinding, or
+    // every move of a puzzle, for undoing.
+    let mut history = std::collections::VecDeque::new();
+    // A puzzle waiting for the next key to move.
+    let mut step = false;
+    // A puzzle crashed into something, which can still be undone.
+    let mut crashed = false;
 
-    let mut prev_frame_time = std::time::Instant::now();
+    let mut next_frame = std::time::Instant::now();
     let mut prev_move_update = std::time::Instant::now();
     'game: loop {
-        screen_size = termion::terminal_size().unwrap();
-
-        // Clear screen
-        clear(&mut screen).unwrap();
+        overlay.frame();
 
         // Input handling
-        while let Some(Ok(b)) = stdin.next() {
-            write!(screen, "{}{}", termion::cursor::Goto(2, screen_size.1), b).unwrap();
-            match b {
+        let input = screen.read_input();
+        let mut events = input::parse_input(&input);
+        overlay.handle_input(&input, &mut events);
+        for event in events {
+            dirty = true;
+            crash::input(game.tick, event);
+            match console.handle(event) {
+                Some(Some(command)) => {
+                    console.replied(command.run(&mut game, &mut move_duration));
+                    effects.set_move_duration(move_duration);
+                    replay.assisted = true;
+                    continue;
+                }
+                Some(None) => continue,
+                None => {}
+            }
+            // Arrows are the same as hjkl.
+            let b = match event {
+                input::InputEvent::Key(b) => b,
+                input::InputEvent::Arrow(MoveDirection::Left) => 104,
+                input::InputEvent::Arrow(MoveDirection::Down) => 106,
+                input::InputEvent::Arrow(MoveDirection::Up) => 107,
+                input::InputEvent::Arrow(MoveDirection::Right) => 108,
+                input::InputEvent::Arrow(_)
+                | input::InputEvent::F3
+                | input::InputEvent::Mouse(_)
+                | input::InputEvent::Unknown => continue,
+            };
+            if effects.handle_key(b) {
+                continue;
+            }
+            let change = match b {
+                43 | 61 => Some(replay::SpeedChange::Faster),
+                45 => Some(replay::SpeedChange::Slower),
+                _ => None,
+            };
+            if let Some(change) = change {
+                replay.record_speed_change(game.tick, change);
+                move_duration = change_speed(move_duration, change);
+                effects.set_move_duration(move_duration);
+                continue;
+            }
+            if b == 122 && pace == Pace::Puzzle {
+                if let Some(earlier) = history.pop_back() {
+                    game = earlier;
+                    crashed = false;
+                }
+                continue;
+            }
+            if crashed && b != 113 {
+                continue;
+            }
+            // Moves a puzzle on without turning.
+            if b == 46 && pace == Pace::Puzzle {
+                step = true;
+                continue;
+            }
+            if b == 122 && pace == Pace::Practice {
+                let back = REWIND_TICKS.min(history.len());
+                if let Some(earlier) = history.drain(history.len() - back..).next() {
+                    game = earlier;
+                    prev_move_update = std::time::Instant::now() + REWIND_PAUSE;
+                }
+                continue;
+            }
+            if b == 68 && overlay.shown {
+                overlay.dump(&game);
+                continue;
+            }
+            if b == 111 {
+                autopilot = match autopilot {
+                    Some(_) => None,
+                    None => Some(ai::Difficulty::Hard.controller(&game, game.active)),
+                };
+                replay.assisted = true;
+                continue;
+            }
+            let input = match b {
                 113 => break 'game,
-                97 => player.elongate(&screen_size),
-                _ => {}
+                97 => Input::Elongate,
+                32 => Input::Swap,
+                104 => Input::Turn(MoveDirection::Left),
+                107 => Input::Turn(MoveDirection::Up),
+                106 => Input::Turn(MoveDirection::Down),
+                108 => Input::Turn(MoveDirection::Right),
+                121 if game.diagonal => Input::Turn(MoveDirection::UpLeft),
+                117 if game.diagonal => Input::Turn(MoveDirection::UpRight),
+                98 if game.diagonal => Input::Turn(MoveDirection::DownLeft),
+                110 if game.diagonal => Input::Turn(MoveDirection::DownRight),
+                _ => continue,
+            };
+            let input = match input {
+                Input::Turn(direction) if game.random_event().is_some_and(|e| e.reversed()) => {
+                    Input::Turn(direction.opposite())
+                }
+                input => input,
             };
 
-            player.change_direction(match b {
-                104 => MoveDirection::Left,
-                107 => MoveDirection::Up,
-                106 => MoveDirection::Down,
-                108 => MoveDirection::Right,
-                _ => continue,
-            })
+            turned |= matches!(input, Input::Turn(_));
+            step |= matches!(input, Input::Turn(_));
+            replay.record(game.tick, input);
+            game.handle_input(0, input);
+        }
+
+        if let Some(control) = control {
+            control.handle(|request| match request {
+                control::Request::Pause => {
+                    paused = true;
+                    dirty = true;
+                    "ok".to_string()
+                }
+                control::Request::Resume => {
+                    paused = false;
+                    dirty = true;
+                    "ok".to_string()
+                }
+                control::Request::State => control::state(&game, paused, move_duration),
+                control::Request::Turn(direction) => {
+                    replay.record(game.tick, Input::Turn(direction));
+                    game.handle_input(0, Input::Turn(direction));
+                    dirty = true;
+                    "ok".to_string()
+                }
+                control::Request::Speed(ms) => {
+                    // Replays only know about + and -, so it goes as far as
+                    // they would.
+                    let target = std::time::Duration::from_millis(ms);
+                    loop {
+                        let change = match target > move_duration {
+                            true => replay::SpeedChange::Slower,
+                            false => replay::SpeedChange::Faster,
+                        };
+                        let next = change_speed(move_duration, change);
+                        if next.abs_diff(target) >= move_duration.abs_diff(target) {
+                            break;
+                        }
+                        replay.record_speed_change(game.tick, change);
+                        move_duration = next;
+                    }
+                    effects.set_move_duration(move_duration);
+                    "ok".to_string()
+                }
+                control::Request::Dump => match debug::dump(&game) {
+                    Ok(path) => path.display().to_string(),
+                    Err(e) => format!("error: {}", e),
+                },
+            });
+        }
+
+        if screen.size() != screen_size {
+            screen_size = screen.size();
+            dirty = true;
         }
+        let too_small = !fits(screen_size, game.board_size);
+
+        let due = match pace {
+            Pace::Puzzle => std::mem::take(&mut step),
+            _ => {
+                // Terrain under the snake being steered speeds it up or
+                // slows it down, and so can a random event.
+                let head = game.players[game.active].head();
+                let terrain = game.level.terrain_at(&head).map_or(1.0, |t| t.tick_scale());
+                let event = game.random_event().map_or(1.0, |e| e.time_scale());
+                prev_move_update.elapsed()
+                    > (move_duration * effects.time_scale()).mul_f64(terrain * event)
+            }
+        };
 
         // Updating player position
-        if prev_move_update.elapsed() > MOVE_DURATION {
+        if paused || too_small || console.open {
             prev_move_update = std::time::Instant::now();
-            if player.update_pos(&screen_size) {
+        } else if due {
+            prev_move_update = std::time::Instant::now();
+            dirty = true;
+            // The player always has the last word.
+            if let (Some(autopilot), false) = (&mut autopilot, turned) {
+                let snake = match game.mode {
+                    Mode::Twins => game.active,
+                    _ => 0,
+                };
+                if let Some(direction) = autopilot.steer(&game, snake) {
+                    replay.record(game.tick, Input::Turn(direction));
+                    game.handle_input(0, Input::Turn(direction));
+                }
+            }
+            turned = false;
+            match pace {
+                Pace::Normal => {}
+                Pace::Practice => {
+                    if history.len() == HISTORY {
+                        history.pop_front();
+                    }
+                    history.push_back(game.clone());
+                }
+                Pace::Puzzle => history.push_back(game.clone()),
+            }
+            let started = std::time::Instant::now();
+            let heads = heatmap::heads(&game);
+            let mut over = game.tick();
+
+            if let Some(rules) = &mut rules {
+                match rules.after_tick(&mut game) {
+                    Ok(end) => over |= end,
+                    Err(e) => {
+                        let report = match crash::report(&format!("the custom rules failed: {}", e))
+                        {
+                            Ok(path) => fill("A report was saved to {}", &[&path.display()]),
+                            Err(e) => fill("Could not save a report: {}", &[&e]),
+                        };
+                        message(screen, &[tr("The custom rules failed"), &e, &report]);
+                        break 'game;
+                    }
+                }
+            }
+            overlay.ticked(started.elapsed());
+            heatmap::record(&game, &heads, &[0, 1]);
+            metrics.tick(&game, &heads, move_duration);
+            if let Some(curve) = curve {
+                let scored = game.players.iter().map(|p| p.score).sum();
+                if scored != score {
+                    score = scored;
+                    move_duration = curve.at(score);
+                    effects.set_move_duration(move_duration);
+                }
+            }
+            replay.checkpoint(&game);
+            crash::game(&game);
+            effects.handle(screen, &game).unwrap();
+            if let Some(spectators) = spectators {
+                spectators.broadcast(&net::Snapshot::new(&game));
+            }
+            if over && pace == Pace::Puzzle && !game.events.contains(&game::Event::BoardFull) {
+                crashed = true;
+            } else if over {
                 break 'game;
             }
         };
 
-        for i in &mut food {
-            // Checking if eaten
-            if i.check_eaten(&screen_size, &mut player) {
-                score += 1;
+        // The overlay's numbers change every frame.
+        dirty |= effects.needs_render() || overlay.shown;
+
+        if dirty {
+            dirty = false;
+            clear(screen).unwrap();
+            if too_small {
+                render_too_small(screen, screen_size, &game).unwrap();
+            } else {
+                render_game(screen, &game).unwrap();
+                render_speed(screen, game.board_size, move_duration).unwrap();
+            }
+            effects.render(screen).unwrap();
+            if paused && !too_small {
+                write!(
+                    screen,
+                    "{}{}",
+                    termion::cursor::Goto(2, screen_size.1),
+                    tr("Paused")
+                )
+                .unwrap();
+            }
+            let label = match (autopilot.is_some(), pace) {
+                _ if crashed => Some(" Crashed, z undoes and q gives up "),
+                (true, _) => Some(" Autopilot "),
+                (false, Pace::Practice) => Some(" Practice, z rewinds "),
+                (false, Pace::Puzzle) => Some(" Puzzle, z undoes and . waits "),
+                (false, Pace::Normal) => None,
             };
+            if let (Some(label), false) = (label, too_small) {
+                let board_size = board_screen_size(game.board_size);
+                ui::write_centered(screen, &board_size, board_size.1, tr(label)).unwrap();
+            }
+            overlay
+                .render(screen, screen_size, &game, move_duration)
+                .unwrap();
+            console.render(screen, screen_size).unwrap();
 
-            // Rendering
-            i.render(&mut screen).unwrap();
+            // Flushing to screen
+            screen.flush().unwrap();
         }
-        player.render(&mut screen).unwrap();
-        write!(
+
+        // Puzzles only move on a key.
+        if paused || too_small || console.open || pace == Pace::Puzzle {
+            screen.wait_for_input(IDLE_TIMEOUT);
+            next_frame = std::time::Instant::now();
+        } else {
+            limit_fps(&mut next_frame);
+        }
+    }
+
+    effects.finish(screen).unwrap();
+    if game.events.contains(&game::Event::BoardFull) {
+        message(screen, &[tr("The board is full, you win!")]);
+    }
+    replay.seal(&game);
+    if pace == Pace::Normal {
+        // Failing to save was already reported when creating the data directory.
+        let _ = analysis::save(&metrics, &game, &replay);
+        analysis::show(screen, &metrics);
+    }
+    (game, replay)
+}
+
+// Replays don't know about rules scripts, so a scripted game only plays back
+// the same way with the same script.
+fn watch(
+    screen: &mut dyn term::Terminal,
+    replay: &replay::Replay,
+    mut rules: Option<Box<dyn rules::Rules>>,
+) {
+    clear(screen).unwrap();
+
+    let mut game = replay.game();
+    let mut screen_size = screen.size();
+    let mut dirty = true;
+    let mut move_duration = replay
+        .speed_changes_at(0)
+        .fold(replay_move_duration(replay), change_speed);
+
+    let mut next_frame = std::time::Instant::now();
+    let mut prev_move_update = std::time::Instant::now();
+    while game.tick < replay.length {
+        if screen.read_input().contains(&113) {
+            return;
+        }
+
+        if screen.size() != screen_size {
+            screen_size = screen.size();
+            dirty = true;
+        }
+        let too_small = !fits(screen_size, game.board_size);
+
+        if too_small {
+            prev_move_update = std::time::Instant::now();
+        } else if prev_move_update.elapsed() > move_duration {
+            prev_move_update = std::time::Instant::now();
+            dirty = true;
+            for input in replay.inputs_at(game.tick) {
+                game.handle_input(0, input);
+            }
+            let mut over = game.tick();
+            move_duration = replay
+                .speed_changes_at(game.tick)
+                .fold(move_duration, change_speed);
+
+            if let Some(rules) = &mut rules {
+                match rules.after_tick(&mut game) {
+                    Ok(end) => over |= end,
+                    Err(e) => return message(screen, &[tr("The custom rules failed"), &e]),
+                }
+            }
+            if over {
+                break;
+            }
+        }
+
+        if dirty {
+            dirty = false;
+            clear(screen).unwrap();
+            if too_small {
+                render_too_small(screen, screen_size, &game).unwrap();
+            } else {
+                render_game(screen, &game).unwrap();
+                render_speed(screen, game.board_size, move_duration).unwrap();
+            }
+            screen.flush().unwrap();
+        }
+
+        limit_fps(&mut next_frame);
+    }
+
+    let score = fill("End of replay. Score: {}", &[&game.players[0].score]);
+    match replay.ended_as_recorded(&game) {
+        Some(false) => message(
             screen,
-            "{} Score: {}{} ",
-            termion::cursor::Goto(2, screen_size.1),
-            score,
-            termion::cursor::Goto(0, screen_size.1),
+            &[
+                &score,
+                tr("It didn't end the way it was recorded, so it was changed"),
+                tr("or played with different rules"),
+            ],
+        ),
+        _ => message(screen, &[&score]),
+    }
+}
+
+fn message(screen: &mut dyn term::Terminal, lines: &[&str]) {
+    screen.read_input();
+
+    while screen.read_input().is_empty() {
+        let screen_size = screen.size();
+        let top = (screen_size.1 / 2).saturating_sub(lines.len() as u16 / 2 + 1);
+        clear(screen).unwrap();
+
+        for (index, line) in lines.iter().enumerate() {
+            ui::write_centered(screen, &screen_size, top + index as u16, line).unwrap();
+        }
+        ui::write_centered(
+            screen,
+            &screen_size,
+            top + lines.len() as u16 + 1,
+            tr("Press any key"),
         )
         .unwrap();
-
-        // Flushing to screen
         screen.flush().unwrap();
 
-        // Limiting FPS
-        {
-            let frame_time = std::time::Instant::now() - prev_frame_time;
-            if frame_time < FRAME_DURATION {
-                std::thread::sleep(FRAME_DURATION - frame_time);
-            }
-            prev_frame_time = std::time::Instant::now();
-        }
+        screen.wait_for_input(IDLE_TIMEOUT);
     }
+}
 
-    write!(
-        screen,
-        "{}\nGame over! Score: {}",
-        termion::clear::BeforeCursor,
-        score
-    )
-    .unwrap();
+fn outcome(players: &[Player], me: usize) -> ratings::Outcome {
+    let won = players[me].alive;
+    let lost = players.iter().enumerate().any(|(i, p)| i != me && p.alive);
+
+    match (won, lost) {
+        (true, false) => ratings::Outcome::Win,
+        (false, true) => ratings::Outcome::Loss,
+        _ => ratings::Outcome::Draw,
+    }
+}
+
+fn versus_result(players: &[Player], me: usize) -> &'static str {
+    match outcome(players, me) {
+        ratings::Outcome::Win => tr("You win!"),
+        ratings::Outcome::Loss => tr("You lose!"),
+        ratings::Outcome::Draw => tr("Draw!"),
+    }
+}
+
+// Counts a versus game for the rating when the other side's is known, and
+// says what it is now.
+fn rate(
+    config: &config::Config,
+    opponent: Option<u32>,
+    outcome: ratings::Outcome,
+) -> Option<String> {
+    let (rating, change) = ratings::record(config, opponent?, outcome);
+    Some(fill(
+        "Rating: {} ({})",
+        &[&rating, &format!("{:+}", change)],
+    ))
+}
+
+// A versus game against the computer, which plays blue.
+fn rival(
+    screen: &mut dyn term::Terminal,
+    spectators: Option<&spectate::Spectators>,
+    opponent: ai::Opponent,
+    config: &config::Config,
+) {
+    let split = SPLIT_SCREEN.load(std::sync::atomic::Ordering::Relaxed);
+    let board_size = match split {
+        true => board_size_for((screen.size().0 * SPLIT_BOARD, screen.size().1 * SPLIT_BOARD)),
+        false => board_size_for(screen.size()),
+    };
+    let mut game = Game::new(Mode::Versus, rand::random(), board_size);
+    let mut controller = opponent.controller(&game, 1);
+    let mut screen_size = screen.size();
+    let mut dirty = true;
+
+    let mut next_frame = std::time::Instant::now();
+    let mut prev_move_update = std::time::Instant::now();
+    loop {
+        for b in screen.read_input() {
+            let direction = match b {
+                113 => return,
+                104 => MoveDirection::Left,
+                107 => MoveDirection::Up,
+                106 => MoveDirection::Down,
+                108 => MoveDirection::Right,
+                _ => continue,
+            };
+            dirty = true;
+            game.handle_input(0, Input::Turn(direction));
+        }
+
+        if screen.size() != screen_size {
+            screen_size = screen.size();
+            dirty = true;
+        }
+        // Split screen never has all of the board on the screen.
+        let too_small = !split && !fits(screen_size, game.board_size);
+
+        if too_small {
+            prev_move_update = std::time::Instant::now();
+        } else if prev_move_update.elapsed() > move_duration() {
+            prev_move_update = std::time::Instant::now();
+            dirty = true;
+            if let Some(direction) = controller.steer(&game, 1) {
+                game.handle_input(1, Input::Turn(direction));
+            }
+            let heads = heatmap::heads(&game);
+            let over = game.tick();
+            heatmap::record(&game, &heads, &[0]);
+
+            if let Some(spectators) = spectators {
+                spectators.broadcast(&net::Snapshot::new(&game));
+            }
+            if over {
+                break;
+            }
+        }
+
+        if dirty {
+            dirty = false;
+            clear(screen).unwrap();
+            if too_small {
+                render_too_small(screen, screen_size, &game).unwrap();
+            } else if split {
+                render_split(screen, screen_size, &game).unwrap();
+            } else {
+                render_game(screen, &game).unwrap();
+            }
+            screen.flush().unwrap();
+        }
+
+        if too_small {
+            screen.wait_for_input(IDLE_TIMEOUT);
+            next_frame = std::time::Instant::now();
+        } else {
+            limit_fps(&mut next_frame);
+        }
+    }
+
+    let rating = rate(config, opponent.rating(), outcome(&game.players, 0));
+    let mut lines = vec![versus_result(&game.players, 0), tr("You were green")];
+    lines.extend(rating.as_deref());
+    message(screen, &lines);
+}
+
+// Offers a neural net too when there's one in the config.
+fn choose_opponent(
+    screen: &mut dyn term::Terminal,
+    #[cfg_attr(not(feature = "neural"), allow(unused_variables))] config: &config::Config,
+) -> Option<ai::Opponent> {
+    let mut items: Vec<&str> = ai::Difficulty::ALL.iter().map(|d| d.name()).collect();
+    #[cfg(feature = "neural")]
+    if config.neural_net.is_some() {
+        items.push("Neural net");
+    }
+    items.push("Back");
+    let mut menu = ui::Menu::new(&items);
+
+    loop {
+        let screen_size = screen.size();
+        clear(screen).unwrap();
+
+        for b in screen.read_input() {
+            match menu.handle_key(b) {
+                #[cfg(feature = "neural")]
+                Some("Neural net") => {
+                    match neural::Net::load(config.neural_net.as_ref().unwrap()) {
+                        Ok(net) => return Some(ai::Opponent::Net(std::rc::Rc::new(net))),
+                        Err(e) => message(screen, &[tr("Could not load the neural net"), &e]),
+                    }
+                }
+                Some(name) => {
+                    let difficulty = ai::Difficulty::ALL.into_iter().find(|d| d.name() == name);
+                    return difficulty.map(ai::Opponent::Bot);
+                }
+                None if b == 113 => return None,
+                None => {}
+            }
+        }
+
+        menu.render(screen, &screen_size).unwrap();
+        screen.flush().unwrap();
+
+        screen.wait_for_input(IDLE_TIMEOUT);
+    }
+}
+
+// Versus or co-op with a player who joins on `port`.
+fn host(
+    screen: &mut dyn term::Terminal,
+    port: u16,
+    mode: Mode,
+    spectators: Option<&spectate::Spectators>,
+    config: &config::Config,
+) {
+    let listener = match std::net::TcpListener::bind(("0.0.0.0", port))
+        .and_then(|l| l.set_nonblocking(true).map(|_| l))
+    {
+        Ok(listener) => listener,
+        Err(e) => {
+            let error = fill("Could not listen on port {}: {}", &[&port, &e]);
+            return message(screen, &[&error]);
+        }
+    };
+
+    let mut next_frame = std::time::Instant::now();
+    let stream = loop {
+        match listener.accept() {
+            Ok((stream, _)) => break stream,
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+            Err(e) => return message(screen, &[&e.to_string()]),
+        }
+
+        if screen.read_input().contains(&113) {
+            return;
+        }
+
+        let screen_size = screen.size();
+        clear(screen).unwrap();
+        ui::write_centered(
+            screen,
+            &screen_size,
+            screen_size.1 / 2,
+            &fill("Waiting for a player on port {}...", &[&port]),
+        )
+        .unwrap();
+        ui::write_centered(
+            screen,
+            &screen_size,
+            screen_size.1 / 2 + 1,
+            tr("q to cancel"),
+        )
+        .unwrap();
+        screen.flush().unwrap();
+
+        limit_fps(&mut next_frame);
+    };
+
+    let mut connection = match stream
+        .set_nonblocking(false)
+        .and_then(|_| net::Connection::new(stream))
+    {
+        Ok(connection) => connection,
+        Err(e) => return message(screen, &[&e.to_string()]),
+    };
+
+    let (client_w, client_h) = loop {
+        match connection.receive() {
+            None => return message(screen, &[tr("The other player disconnected")]),
+            Some(messages) => {
+                if let Some(net::Message::Hello(w, h)) = messages.into_iter().next() {
+                    break (w, h);
+                }
+            }
+        }
+
+        limit_fps(&mut next_frame);
+    };
+
+    let rating = ratings::of(config).rating;
+    let mut lobby = lobby::Lobby::new(0, lobby::Settings::new(mode), rating);
+    if connection
+        .send(&net::Message::Lobby(lobby.settings))
+        .and_then(|()| connection.send(&net::Message::Rating(rating)))
+        .is_err()
+    {
+        return message(screen, &[tr("The other player disconnected")]);
+    }
+    while !lobby.ready() {
+        let mut replies = Vec::new();
+        for b in screen.read_input() {
+            if b == 113 {
+                let _ = connection.send(&net::Message::Quit);
+                return;
+            }
+            replies.extend(lobby.handle_key(b));
+        }
+        let Some(messages) = connection.receive() else {
+            return message(screen, &[tr("The other player disconnected")]);
+        };
+        for received in messages {
+            match received {
+                net::Message::Quit => return message(screen, &[tr("The other player left")]),
+                received => replies.extend(lobby.receive(&received)),
+            }
+        }
+        if replies.iter().any(|reply| connection.send(reply).is_err()) {
+            return message(screen, &[tr("The other player disconnected")]);
+        }
+
+        let screen_size = screen.size();
+        clear(screen).unwrap();
+        lobby.render(screen, screen_size).unwrap();
+        screen.flush().unwrap();
+        limit_fps(&mut next_frame);
+    }
+    let settings = lobby.settings;
+    let mode = settings.mode;
+    let you_were = use_lobby(&lobby);
+
+    // Both players have to see the whole board, so it's limited by the
+    // smaller of the two terminals, unless that's too small to play on.
+    let screen_size = board_size_for(screen.size());
+    let board_size = settings.board.size((
+        screen_size.0.min(client_w).max(level::MIN_NETWORK_SIZE.0),
+        screen_size.1.min(client_h).max(level::MIN_NETWORK_SIZE.1),
+    ));
+    if mode == Mode::Coop {
+        let seed = rand::random();
+        if connection
+            .send(&net::Message::Coop(board_size.0, board_size.1, seed))
+            .is_err()
+        {
+            return message(screen, &[tr("The other player disconnected")]);
+        }
+        let game = Game::new(Mode::Coop, seed, board_size);
+        return coop(screen, &mut connection, game, 0, spectators);
+    }
+    if connection
+        .send(&net::Message::Start(board_size.0, board_size.1))
+        .is_err()
+    {
+        return message(screen, &[tr("The other player disconnected")]);
+    }
+
+    let mut game = Game::new(Mode::Versus, rand::random(), board_size);
+    let mut chat = chat::Chat::default();
+
+    let mut prev_move_update = std::time::Instant::now();
+    'game: loop {
+        clear(screen).unwrap();
+
+        for b in screen.read_input() {
+            match chat_key(&mut chat, &mut connection, b) {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(_) => return message(screen, &[tr("The other player disconnected")]),
+            }
+            let direction = match b {
+                113 => {
+                    let _ = connection.send(&net::Message::Quit);
+                    return;
+                }
+                104 => MoveDirection::Left,
+                107 => MoveDirection::Up,
+                106 => MoveDirection::Down,
+                108 => MoveDirection::Right,
+                _ => continue,
+            };
+            game.handle_input(0, Input::Turn(direction));
+        }
+
+        let Some(messages) = connection.receive() else {
+            return message(screen, &[tr("The other player disconnected")]);
+        };
+        for received in messages {
+            match received {
+                net::Message::Turn(direction) => game.handle_input(1, Input::Turn(direction)),
+                net::Message::Say(line) => chat.said(snake_name(1), &line),
+                net::Message::Quit => return message(screen, &[tr("The other player left")]),
+                _ => {}
+            }
+        }
+
+        let screen_size = screen.size();
+        let too_small = !fits(screen_size, board_size);
+
+        // Holds up the other player too, they only get to see the host's game.
+        if too_small {
+            prev_move_update = std::time::Instant::now();
+        } else if prev_move_update.elapsed() > move_duration() {
+            prev_move_update = std::time::Instant::now();
+            let over = game.tick();
+
+            let snapshot = net::Snapshot::new(&game);
+            if let Some(spectators) = spectators {
+                spectators.broadcast(&snapshot);
+            }
+            if connection.send(&net::Message::State(snapshot)).is_err() {
+                return message(screen, &[tr("The other player disconnected")]);
+            }
+            if over {
+                let _ = connection.send(&net::Message::Over);
+                break 'game;
+            }
+        }
+
+        if too_small {
+            render_too_small(screen, screen_size, &game).unwrap();
+        } else {
+            render_game(screen, &game).unwrap();
+            chat.render(screen, board_screen_size(board_size).1)
+                .unwrap();
+        }
+        screen.flush().unwrap();
+
+        limit_fps(&mut next_frame);
+    }
+
+    let rating = rate(config, lobby.seats[1].rating, outcome(&game.players, 0));
+    let mut lines = vec![versus_result(&game.players, 0), &you_were];
+    lines.extend(rating.as_deref());
+    message(screen, &lines);
+}
+
+// Gives `b` to the chat first, sending the line once it's been typed.
+// Returns whether the chat took it.
+fn chat_key(
+    chat: &mut chat::Chat,
+    connection: &mut net::Connection,
+    b: u8,
+) -> Result<bool, std::io::Error> {
+    match chat.handle_key(b) {
+        None => Ok(false),
+        Some(None) => Ok(true),
+        Some(Some(line)) => {
+            chat.said(tr("You"), &line);
+            connection.send(&net::Message::Say(line)).map(|()| true)
+        }
+    }
+}
+
+// What the chat calls snake `index`, by its color.
+fn snake_name(index: usize) -> &'static str {
+    match theme::picked() {
+        Some(colors) => tr(theme::COLORS[colors[index.min(1)]].0),
+        None if index == 0 => tr("green"),
+        None => tr("blue"),
+    }
+}
+
+// Plays the next game the way `lobby` agreed on, and says which snake this
+// side is.
+fn use_lobby(lobby: &lobby::Lobby) -> String {
+    let colors = lobby.colors();
+    theme::pick(Some(colors));
+    let tick_ms = lobby.settings.speed.tick_ms();
+    TICK_MS.store(tick_ms, std::sync::atomic::Ordering::Relaxed);
+    // The host's speed can be faster than `--fps` was checked against, so
+    // draw often enough to keep up with it.
+    let fps = FPS.load(std::sync::atomic::Ordering::Relaxed);
+    if fps != 0 && (fps as u64) < min_fps(tick_ms) {
+        FPS.store(
+            min_fps(tick_ms) as u32,
+            std::sync::atomic::Ordering::Relaxed,
+        );
+    }
+    fill("You were {}", &[&tr(theme::COLORS[colors[lobby.local]].0)])
+}
+
+fn join(screen: &mut dyn term::Terminal, address: &str, config: &config::Config) {
+    let screen_size = screen.size();
+    clear(screen).unwrap();
+    ui::write_centered(
+        screen,
+        &screen_size,
+        screen_size.1 / 2,
+        &fill("Connecting to {}...", &[&address]),
+    )
+    .unwrap();
+    screen.flush().unwrap();
+
+    let address = if address.contains(':') {
+        address.to_string()
+    } else {
+        format!("{}:{}", address, net::DEFAULT_PORT)
+    };
+    let mut connection = match std::net::TcpStream::connect(&address).and_then(net::Connection::new)
+    {
+        Ok(connection) => connection,
+        Err(e) => {
+            let error = fill("Could not connect to {}: {}", &[&address, &e]);
+            return message(screen, &[&error]);
+        }
+    };
+
+    if connection
+        .send(&net::Message::Hello(
+            board_size_for(screen_size).0,
+            board_size_for(screen_size).1,
+        ))
+        .is_err()
+    {
+        return message(screen, &[tr("The host disconnected")]);
+    }
+
+    let mut board_size = None;
+    let mut snapshot: Option<net::Snapshot> = None;
+    // Until the game starts, unless joining a server.
+    let mut lobby: Option<lobby::Lobby> = None;
+    let mut you_were = tr("You were blue").to_string();
+    let mut chat = chat::Chat::default();
+
+    let mut next_frame = std::time::Instant::now();
+    'game: loop {
+        clear(screen).unwrap();
+
+        let mut replies = Vec::new();
+        for b in screen.read_input() {
+            if let (Some(lobby), None, false) = (&mut lobby, board_size, b == 113) {
+                replies.extend(lobby.handle_key(b));
+                continue;
+            }
+            if board_size.is_some() {
+                match chat_key(&mut chat, &mut connection, b) {
+                    Ok(true) => continue,
+                    Ok(false) => {}
+                    Err(_) => return message(screen, &[tr("The host disconnected")]),
+                }
+            }
+            let direction = match b {
+                113 => {
+                    let _ = connection.send(&net::Message::Quit);
+                    return;
+                }
+                104 => MoveDirection::Left,
+                107 => MoveDirection::Up,
+                106 => MoveDirection::Down,
+                108 => MoveDirection::Right,
+                _ => continue,
+            };
+            if connection.send(&net::Message::Turn(direction)).is_err() {
+                return message(screen, &[tr("The host disconnected")]);
+            }
+        }
+
+        let Some(messages) = connection.receive() else {
+            return message(screen, &[tr("The host disconnected")]);
+        };
+        for received in messages {
+            match received {
+                net::Message::Lobby(settings) if lobby.is_none() => {
+                    let rating = ratings::of(config).rating;
+                    lobby = Some(lobby::Lobby::new(1, settings, rating));
+                    replies.push(net::Message::Rating(rating));
+                }
+                net::Message::Lobby(_)
+                | net::Message::Color(_)
+                | net::Message::Ready(_)
+                | net::Message::Rating(_) => {
+                    if let Some(lobby) = &mut lobby {
+                        replies.extend(lobby.receive(&received));
+                    }
+                }
+                net::Message::Start(w, h) => {
+                    board_size = Some((w, h));
+                    if let Some(lobby) = &lobby {
+                        you_were = use_lobby(lobby);
+                    }
+                }
+                net::Message::Coop(w, h, _) | net::Message::Match(w, h, _, _)
+                    if w < level::MIN_NETWORK_SIZE.0 || h < level::MIN_NETWORK_SIZE.1 =>
+                {
+                    return message(screen, &[tr("The server sent a game this can't play")]);
+                }
+                net::Message::Coop(w, h, seed) => {
+                    if let Some(lobby) = &lobby {
+                        use_lobby(lobby);
+                    }
+                    let game = Game::new(Mode::Coop, seed, (w, h));
+                    return coop(screen, &mut connection, game, 1, None);
+                }
+                net::Message::Match(w, h, seed, you) => {
+                    let game = Game::new(Mode::Versus, seed, (w, h));
+                    if you >= game.players.len() {
+                        return message(screen, &[tr("The server sent a game this can't play")]);
+                    }
+                    return against_server(screen, &mut connection, game, you);
+                }
+                net::Message::State(state) => snapshot = Some(state),
+                net::Message::Say(line) => chat.said(snake_name(0), &line),
+                net::Message::Over => break 'game,
+                net::Message::Quit => return message(screen, &[tr("The host left")]),
+                _ => {}
+            }
+        }
+        if replies.iter().any(|reply| connection.send(reply).is_err()) {
+            return message(screen, &[tr("The host disconnected")]);
+        }
+
+        match (board_size, &snapshot, &lobby) {
+            (Some(board_size), Some(snapshot), _) => {
+                render_snapshot(screen, board_size, snapshot).unwrap();
+                chat.render(screen, board_screen_size(board_size).1)
+                    .unwrap();
+            }
+            (None, _, Some(lobby)) => lobby.render(screen, screen_size).unwrap(),
+            _ => ui::write_centered(
+                screen,
+                &screen_size,
+                screen_size.1 / 2,
+                tr("Waiting for the game to start..."),
+            )
+            .unwrap(),
+        }
+        screen.flush().unwrap();
+
+        limit_fps(&mut next_frame);
+    }
+
+    let Some(snapshot) = snapshot else {
+        return message(screen, &[tr("Draw!"), &you_were]);
+    };
+    let opponent = lobby.and_then(|lobby| lobby.seats[0].rating);
+    let rating = rate(config, opponent, outcome(&snapshot.players, 1));
+    let mut lines = vec![versus_result(&snapshot.players, 1), &you_were];
+    lines.extend(rating.as_deref());
+    message(screen, &lines);
+}
+
+// Versus on a `snake server`, steering snake `you`. What's drawn is the
+// prediction, see `predict`, and the result is what the server decided.
+fn against_server(
+    screen: &mut dyn term::Terminal,
+    connection: &mut net::Connection,
+    game: Game,
+    you: usize,
+) {
+    let mut prediction = predict::Prediction::new(game, you);
+    let mut chat = chat::Chat::default();
+
+    let mut next_frame = std::time::Instant::now();
+    'game: loop {
+        clear(screen).unwrap();
+
+        for b in screen.read_input() {
+            match chat_key(&mut chat, connection, b) {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(_) => return message(screen, &[tr("The server disconnected")]),
+            }
+            let direction = match b {
+                113 => {
+                    let _ = connection.send(&net::Message::Quit);
+                    return;
+                }
+                104 => MoveDirection::Left,
+                107 => MoveDirection::Up,
+                106 => MoveDirection::Down,
+                108 => MoveDirection::Right,
+                _ => continue,
+            };
+            let tick = prediction.press(direction);
+            if connection
+                .send(&net::Message::TurnAt(tick, direction))
+                .is_err()
+            {
+                return message(screen, &[tr("The server disconnected")]);
+            }
+        }
+
+        let Some(messages) = connection.receive() else {
+            return message(screen, &[tr("The server disconnected")]);
+        };
+        for received in messages {
+            match received {
+                net::Message::Tick(tick, turns) => {
+                    prediction.confirm(tick, &turns);
+                }
+                net::Message::Say(line) => chat.said(snake_name(1 - you), &line),
+                net::Message::Over => break 'game,
+                net::Message::Quit => return message(screen, &[tr("The other player left")]),
+                _ => {}
+            }
+        }
+
+        let screen_size = screen.size();
+        let game = prediction.predicted();
+        // The server doesn't wait, so neither does the game.
+        if fits(screen_size, game.board_size) {
+            render_game(screen, &game).unwrap();
+            chat.render(screen, board_screen_size(game.board_size).1)
+                .unwrap();
+        } else {
+            render_too_small(screen, screen_size, &game).unwrap();
+        }
+        screen.flush().unwrap();
+
+        limit_fps(&mut next_frame);
+    }
+
+    let you_were = match you {
+        0 => tr("You were green"),
+        _ => tr("You were blue"),
+    };
+    let result = versus_result(&prediction.confirmed.players, you);
+    message(screen, &[result, you_were]);
+}
+
+// Co-op over `connection`, with this side steering `local` and the other the
+// other snake. Both run `game` in lockstep, so either one waiting holds up
+// both.
+fn coop(
+    screen: &mut dyn term::Terminal,
+    connection: &mut net::Connection,
+    mut game: Game,
+    local: usize,
+    spectators: Option<&spectate::Spectators>,
+) {
+    let (disconnected, left) = match local {
+        0 => ("The other player disconnected", "The other player left"),
+        _ => ("The host disconnected", "The host left"),
+    };
+    let mut lockstep = lockstep::Lockstep::new(local, game.players.len());
+    let mut chat = chat::Chat::default();
+
+    let mut next_frame = std::time::Instant::now();
+    let mut prev_move_update = std::time::Instant::now();
+    loop {
+        clear(screen).unwrap();
+
+        for b in screen.read_input() {
+            match chat_key(&mut chat, connection, b) {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(_) => return message(screen, &[tr(disconnected)]),
+            }
+            let direction = match b {
+                113 => {
+                    let _ = connection.send(&net::Message::Quit);
+                    return;
+                }
+                104 => MoveDirection::Left,
+                107 => MoveDirection::Up,
+                106 => MoveDirection::Down,
+                108 => MoveDirection::Right,
+                _ => continue,
+            };
+            lockstep.press(direction);
+        }
+
+        let Some(messages) = connection.receive() else {
+            return message(screen, &[tr(disconnected)]);
+        };
+        for received in messages {
+            match received {
+                net::Message::Turns(tick, turns) => lockstep.receive(1 - local, tick, turns),
+                net::Message::Say(line) => chat.said(snake_name(1 - local), &line),
+                net::Message::Quit => return message(screen, &[tr(left)]),
+                _ => {}
+            }
+        }
+
+        let screen_size = screen.size();
+        let too_small = !fits(screen_size, game.board_size);
+
+        // Not sending anything while too small holds up the other side too.
+        if too_small {
+            prev_move_update = std::time::Instant::now();
+        } else if prev_move_update.elapsed() > move_duration() {
+            if let Some((tick, turns)) = lockstep.send(game.tick) {
+                if connection.send(&net::Message::Turns(tick, turns)).is_err() {
+                    return message(screen, &[tr(disconnected)]);
+                }
+            }
+            // Without the other side's turns yet this tries again next frame,
+            // so a late tick is made up for as soon as they arrive.
+            if let Some(over) = lockstep.tick(&mut game) {
+                prev_move_update = std::time::Instant::now();
+                if let Some(spectators) = spectators {
+                    spectators.broadcast(&net::Snapshot::new(&game));
+                }
+                if over {
+                    break;
+                }
+            }
+        }
+
+        if too_small {
+            render_too_small(screen, screen_size, &game).unwrap();
+        } else {
+            at_night(dark(&game), || {
+                render_level(screen, &game.level)?;
+                let (players, food) = (&game.players, &game.food);
+                let food = render_sight(screen, game.board_size, players, food, vision(&game))?;
+                render_board(screen, game.board_size, players, &food, Some(local))
+            })
+            .unwrap();
+            chat.render(screen, board_screen_size(game.board_size).1)
+                .unwrap();
+            if prev_move_update.elapsed() > std::time::Duration::from_secs(1) {
+                ui::write_centered(
+                    screen,
+                    &screen_size,
+                    screen_size.1 / 2,
+                    tr("Waiting for the other player..."),
+                )
+                .unwrap();
+            }
+        }
+        screen.flush().unwrap();
+
+        limit_fps(&mut next_frame);
+    }
+
+    let score: u32 = game.players.iter().map(|p| p.score).sum();
+    message(screen, &[&fill("You scored {} together", &[&score])]);
+}
+
+fn spectate(screen: &mut dyn term::Terminal, address: &str) {
+    let snapshots = match spectate::connect(address) {
+        Ok(snapshots) => snapshots,
+        Err(e) => {
+            let error = fill("Could not spectate {}: {}", &[&address, &e]);
+            return message(screen, &[&error]);
+        }
+    };
+    let mut snapshot: Option<net::Snapshot> = None;
+    let mut camera = camera::Camera::following(0);
+
+    let mut next_frame = std::time::Instant::now();
+    loop {
+        let screen_size = screen.size();
+        clear(screen).unwrap();
+        // All of the screen but a row for the scores, in cells.
+        let (w, h) = cell_size();
+        let view = (screen_size.0 / w, screen_size.1.saturating_sub(1) / h);
+
+        for b in screen.read_input() {
+            if b == 113 {
+                return;
+            }
+            if let Some(snapshot) = &snapshot {
+                camera.handle_key(b, &snapshot.players, snapshot.board_size, view);
+            }
+        }
+
+        loop {
+            match snapshots.try_recv() {
+                Ok(received) => snapshot = Some(received),
+                Err(std::sync::mpsc::TryRecvError::Empty) => break,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                    return message(screen, &[tr("The game has ended")]);
+                }
+            }
+        }
+
+        match &snapshot {
+            Some(snapshot) if fits(screen_size, snapshot.board_size) => {
+                render_snapshot(screen, snapshot.board_size, snapshot).unwrap()
+            }
+            // Too big to see all at once, so the camera picks a part.
+            Some(snapshot) => {
+                let viewport =
+                    camera.viewport(&snapshot.players, snapshot.board_size, view, (1, 1));
+                at_night(night(snapshot.tick), || {
+                    camera::set_viewport(Some(viewport));
+                    let food = render_sight(
+                        screen,
+                        snapshot.board_size,
+                        &snapshot.players,
+                        &snapshot.food,
+                        night_vision(snapshot.tick),
+                    );
+                    camera::set_viewport(None);
+                    render_viewport(screen, viewport, None, &snapshot.players, &food?)
+                })
+                .unwrap();
+                let following = match camera.target {
+                    camera::Target::Snake(0) => {
+                        tr("Following green, tab for the next, f to look around")
+                    }
+                    camera::Target::Snake(_) => {
+                        tr("Following blue, tab for the next, f to look around")
+                    }
+                    camera::Target::Free(_) => tr("hjkl looks around, f or tab follows a snake"),
+                };
+                let scores: Vec<String> = snapshot
+                    .players
+                    .iter()
+                    .map(|p| p.score.to_string())
+                    .collect();
+                write!(
+                    screen,
+                    "{} {}  {}",
+                    termion::cursor::Goto(1, view.1 * h + 1),
+                    scores.join(":"),
+                    following
+                )
+                .unwrap();
+            }
+            None => ui::write_centered(
+                screen,
+                &screen_size,
+                screen_size.1 / 2,
+                tr("Waiting for the game to start..."),
+            )
+            .unwrap(),
+        }
+        screen.flush().unwrap();
+
+        limit_fps(&mut next_frame);
+    }
+}
+
+// Plays until quit, starting over whenever chat crashes.
+fn twitch(
+    screen: &mut dyn term::Terminal,
+    server: &str,
+    channel: &str,
+    spectators: Option<&spectate::Spectators>,
+) {
+    let votes = match twitch::connect(server, channel) {
+        Ok(votes) => votes,
+        Err(e) => {
+            let error = fill("Could not connect to {}: {}", &[&server, &e]);
+            return message(screen, &[&error]);
+        }
+    };
+
+    let mut game = Game::new(Mode::Classic, rand::random(), board_size_for(screen.size()));
+    let mut window = twitch::Votes::default();
+    let mut last_score = None;
+
+    let mut next_frame = std::time::Instant::now();
+    let mut prev_move_update = std::time::Instant::now();
+    let mut window_start = std::time::Instant::now();
+    loop {
+        let screen_size = screen.size();
+        clear(screen).unwrap();
+
+        if screen.read_input().contains(&113) {
+            return;
+        }
+
+        loop {
+            match votes.try_recv() {
+                Ok(vote) => window.add(vote),
+                Err(std::sync::mpsc::TryRecvError::Empty) => break,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                    return message(screen, &[tr("Lost the connection to chat")]);
+                }
+            }
+        }
+
+        if window_start.elapsed() > twitch::VOTE_WINDOW {
+            window_start = std::time::Instant::now();
+            if let Some(direction) = window.close() {
+                game.handle_input(0, Input::Turn(direction));
+            }
+        }
+
+        let too_small = !fits(screen_size, game.board_size);
+
+        if too_small {
+            prev_move_update = std::time::Instant::now();
+        } else if prev_move_update.elapsed() > twitch::MOVE_DURATION {
+            prev_move_update = std::time::Instant::now();
+            let over = game.tick();
+
+            if let Some(spectators) = spectators {
+                spectators.broadcast(&net::Snapshot::new(&game));
+            }
+            if over {
+                last_score = Some(game.players[0].score);
+                game = Game::new(Mode::Classic, rand::random(), board_size_for(screen_size));
+                window = twitch::Votes::default();
+            }
+        }
+
+        if too_small {
+            render_too_small(screen, screen_size, &game).unwrap();
+            screen.flush().unwrap();
+            limit_fps(&mut next_frame);
+            continue;
+        }
+        render_game(screen, &game).unwrap();
+
+        let [up, down, left, right] = window.tally();
+        let mut status = format!(
+            "#{}  u {}  d {}  l {}  r {}",
+            channel, up, down, left, right
+        );
+        if let Some(score) = last_score {
+            status.push_str(&format!("  last game {}", score));
+        }
+        write!(
+            screen,
+            "{}{}",
+            termion::cursor::Goto(2, screen_size.1),
+            status
+        )
+        .unwrap();
+        screen.flush().unwrap();
+
+        limit_fps(&mut next_frame);
+    }
+}
+
+// Only offers a place in the high scores to a game that `counts`, and keeps
+// its replay with the score to watch from the high scores screen.
+fn game_over(
+    screen: &mut dyn term::Terminal,
+    high_scores: &mut scores::HighScores,
+    score: u32,
+    counts: bool,
+    replay: &replay::Replay,
+) -> Option<String> {
+    // Drop whatever was still being pressed when the snake died.
+    screen.read_input();
+
+    let mut name_input = if counts && high_scores.qualifies(replay.mode, score) {
+        Some(ui::TextInput::new(
+            scores::NAME_MIN_LEN,
+            scores::NAME_MAX_LEN,
+            scores::valid_name_char,
+        ))
+    } else {
+        None
+    };
+
+    loop {
+        let screen_size = screen.size();
+        let middle = screen_size.1 / 2;
+        clear(screen).unwrap();
+
+        let input = screen.read_input();
+        match &mut name_input {
+            Some(text_input) => {
+                for b in input {
+                    if let Some(name) = text_input.handle_key(b) {
+                        let hash = replay.hash();
+                        // Failing to save was already reported when creating the data directory.
+                        let saved = replay.save(&scores::replay_file(&hash)).is_ok();
+                        let entry = scores::Entry::new(
+                            name.clone(),
+                            score,
+                            replay.mode,
+                            saved.then_some(hash),
+                        );
+                        let rank = high_scores.insert(entry);
+                        let _ = high_scores.save();
+                        let entries: Vec<scores::Entry> =
+                            high_scores.of(replay.mode).into_iter().cloned().collect();
+                        score_table(screen, "High scores", &entries, Some(rank));
+                        return Some(name);
+                    }
+                }
+
+                ui::write_centered(screen, &screen_size, middle - 1, tr("New high score!"))
+                    .unwrap();
+                ui::write_centered(screen, &screen_size, middle, tr("Enter your name:")).unwrap();
+                text_input
+                    .render(
+                        screen,
+                        (ui::centered_x(&screen_size, text_input.width()), middle + 1),
+                    )
+                    .unwrap();
+            }
+            None => {
+                if !input.is_empty() {
+                    return None;
+                }
+
+                ui::write_centered(screen, &screen_size, middle - 1, tr("Press any key")).unwrap();
+            }
+        }
+
+        ui::write_centered(
+            screen,
+            &screen_size,
+            middle - 3,
+            &fill("Game over! Score: {}", &[&score]),
+        )
+        .unwrap();
+        screen.flush().unwrap();
+
+        screen.wait_for_input(IDLE_TIMEOUT);
+    }
+}
+
+// Offers to export the game that just ended, see `export`.
+fn share(screen: &mut dyn term::Terminal, replay: &replay::Replay) {
+    screen.read_input();
+    let mut menu = ui::Menu::new(&["Done", "Save as asciinema cast", "Save as GIF"]);
+
+    loop {
+        let screen_size = screen.size();
+        clear(screen).unwrap();
+
+        for b in screen.read_input() {
+            let format = match menu.handle_key(b) {
+                Some("Save as asciinema cast") => export::Format::Cast,
+                Some("Save as GIF") => export::Format::Gif,
+                Some(_) => return,
+                None if b == 113 => return,
+                None => continue,
+            };
+
+            let seconds = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs());
+            let path =
+                paths::replay_dir().join(format!("snake-{}.{}", seconds, format.extension()));
+
+            return match export::export(replay, format, &path) {
+                Ok(()) => message(screen, &[tr("Saved to"), &path.display().to_string()]),
+                Err(e) => message(screen, &[tr("Could not save the game"), &e]),
+            };
+        }
+
+        ui::write_centered(
+            screen,
+            &screen_size,
+            (screen_size.1 / 2).saturating_sub(3),
+            tr("Share this game?"),
+        )
+        .unwrap();
+        menu.render(screen, &screen_size).unwrap();
+        screen.flush().unwrap();
+
+        screen.wait_for_input(IDLE_TIMEOUT);
+    }
+}
+
+fn score_table(
+    screen: &mut dyn term::Terminal,
+    title: &str,
+    entries: &[scores::Entry],
+    highlight: Option<usize>,
+) {
+    let rows: Vec<Vec<String>> = entries
+        .iter()
+        .enumerate()
+        .map(|(rank, entry)| {
+            vec![
+                format!("{}.", rank + 1),
+                entry.name.clone(),
+                entry.score.to_string(),
+            ]
+        })
+        .collect();
+
+    while screen.read_input().is_empty() {
+        let screen_size = screen.size();
+        let top = (screen_size.1 / 2).saturating_sub(6).max(1);
+        clear(screen).unwrap();
+
+        ui::write_centered(screen, &screen_size, top, tr(title)).unwrap();
+        if rows.is_empty() {
+            ui::write_centered(screen, &screen_size, top + 2, tr("No scores yet")).unwrap();
+        } else {
+            ui::render_table(
+                screen,
+                &screen_size,
+                top + 2,
+                &["#", tr("Name"), tr("Score")],
+                &rows,
+                highlight,
+            )
+            .unwrap();
+        }
+        screen.flush().unwrap();
+
+        screen.wait_for_input(IDLE_TIMEOUT);
+    }
+}
+
+#[cfg(feature = "online")]
+fn submit_score(
+    screen: &mut dyn term::Terminal,
+    config: &config::Config,
+    name: &str,
+    score: u32,
+    replay: &replay::Replay,
+) {
+    let Some(url) = &config.leaderboard_url else {
+        return;
+    };
+
+    let screen_size = screen.size();
+    clear(screen).unwrap();
+    ui::write_centered(
+        screen,
+        &screen_size,
+        screen_size.1 / 2,
+        tr("Submitting score..."),
+    )
+    .unwrap();
+    screen.flush().unwrap();
+
+    // Whatever the server does with the replay, it has to play out the same.
+    if replay.ended_as_recorded(&replay.simulate()) != Some(true) {
+        return message(
+            screen,
+            &[
+                tr("Could not submit score"),
+                tr("The replay doesn't check out"),
+            ],
+        );
+    }
+    if let Err(e) = leaderboard::submit(url, name, score, replay) {
+        message(screen, &[tr("Could not submit score"), &e]);
+    }
+}
+
+#[cfg(not(feature = "online"))]
+fn submit_score(
+    _screen: &mut dyn term::Terminal,
+    _config: &config::Config,
+    _name: &str,
+    _score: u32,
+    _replay: &replay::Replay,
+) {
+}
+
+#[cfg(feature = "plugins")]
+fn plugins(
+    screen: &mut dyn term::Terminal,
+    spectators: Option<&spectate::Spectators>,
+    control: Option<&control::Control>,
+    effects: &mut effects::Effects,
+    config: &config::Config,
+    cheats: &cheats::Cheats,
+    high_scores: &mut scores::HighScores,
+) {
+    let plugins = plugin::discover();
+    let mut items: Vec<&str> = plugins.iter().map(|(name, _)| name.as_str()).collect();
+    items.push("Back");
+    let mut menu = ui::Menu::new(&items);
+
+    loop {
+        let screen_size = screen.size();
+        clear(screen).unwrap();
+
+        for b in screen.read_input() {
+            match menu.handle_key(b) {
+                Some("Back") => return,
+                Some(name) => {
+                    let (_, path) = plugins.iter().find(|(n, _)| n == name).unwrap();
+                    match plugin::Plugin::load(path) {
+                        Ok(plugin) => {
+                            let game =
+                                new_game(config, Mode::Classic, board_size_for(screen.size()));
+                            let (score, replay) = play(
+                                screen,
+                                spectators,
+                                control,
+                                effects,
+                                game,
+                                cheats,
+                                Some(Box::new(plugin)),
+                            );
+                            let counts = !cheats.any() && !replay.assisted;
+                            game_over(screen, high_scores, score, counts, &replay);
+                        }
+                        Err(e) => message(screen, &[tr("Could not load the plugin"), &e]),
+                    }
+                }
+                None if b == 113 => return,
+                None => {}
+            }
+        }
+
+        menu.render(screen, &screen_size).unwrap();
+        screen.flush().unwrap();
+
+        screen.wait_for_input(IDLE_TIMEOUT);
+    }
+}
+
+fn menu(
+    screen: &mut dyn term::Terminal,
+    spectators: Option<&spectate::Spectators>,
+    control: Option<&control::Control>,
+    config: &config::Config,
+) {
+    let mut high_scores = scores::HighScores::load();
+    let mut effects = effects::Effects::new(config);
+    let mut usage = usage::Usage::start(config);
+
+    let mut items = vec![
+        "Play",
+        "Practice",
+        "Twins",
+        "Play the computer",
+        "Hex grid (experimental)",
+    ];
+    #[cfg(feature = "plugins")]
+    if !plugin::discover().is_empty() {
+        items.push("Plugins");
+    }
+    items.extend(["Watch last game", "High scores", "Stats"]);
+    #[cfg(feature = "online")]
+    if config.leaderboard_url.is_some() {
+        items.push("Online scores");
+    }
+    items.push("Quit");
+    let mut menu = ui::Menu::new(&items);
+    let mut cheats = cheats::Cheats::default();
+
+    'menu: loop {
+        let screen_size = screen.size();
+        clear(screen).unwrap();
+
+        if let Some(control) = control {
+            control.refuse();
+        }
+
+        for b in screen.read_input() {
+            if let Some(cheat) = cheats.key(b) {
+                if cheat == cheats::Cheat::Nokia {
+                    match cheats.is_on(cheat) {
+                        true => theme::set(theme::ThemeName::Retro),
+                        false => theme::init(config),
+                    }
+                }
+                let text = match cheats.is_on(cheat) {
+                    true => "Cheat on: {}",
+                    false => "Cheat off: {}",
+                };
+                message(screen, &[&fill(text, &[&tr(cheat.description())])]);
+                continue;
+            }
+            let item = menu.handle_key(b);
+            if let (Some(usage), Some(item)) = (&mut usage, item) {
+                // Only the items that are games, not the ones that show
+                // something or load a plugin.
+                if matches!(
+                    item,
+                    "Play" | "Practice" | "Twins" | "Play the computer" | "Hex grid (experimental)"
+                ) {
+                    usage.game(item);
+                }
+            }
+            match item {
+                Some(item @ ("Play" | "Twins")) => match rules::load(config) {
+                    Ok(rules) => {
+                        let scripted = rules.is_some();
+                        let mode = match item {
+                            "Twins" => Mode::Twins,
+                            _ => Mode::Classic,
+                        };
+                        let game = new_game(config, mode, board_size_for(screen.size()));
+                        let (score, replay) = play(
+                            screen,
+                            spectators,
+                            control,
+                            &mut effects,
+                            game,
+                            &cheats,
+                            rules,
+                        );
+                        // Games the console changed or the autopilot
+                        // played don't count.
+                        let counts = !cheats.any() && !replay.assisted;
+                        if let Some(name) =
+                            game_over(screen, &mut high_scores, score, counts, &replay)
+                        {
+                            // The leaderboard couldn't check a score made
+                            // under different rules, and easy mode,
+                            // diagonal, wind and autopilot scores aren't
+                            // worth comparing.
+                            if !scripted
+                                && !replay.bumper
+                                && !replay.diagonal
+                                && !replay.wind
+                                && !replay.assisted
+                            {
+                                submit_score(screen, config, &name, score, &replay);
+                            }
+                        }
+                        // Exports don't know about rules scripts either.
+                        if !scripted {
+                            share(screen, &replay);
+                        }
+                    }
+                    Err(e) => message(screen, &[tr("Could not load the rules script"), &e]),
+                },
+                Some("Practice") => practice(screen, spectators, control, &mut effects, config),
+                Some("Hex grid (experimental)") => honeycomb::play(screen),
+                Some("Play the computer") => {
+                    if let Some(opponent) = choose_opponent(screen, config) {
+                        rival(screen, spectators, opponent, config);
+                    }
+                }
+                #[cfg(feature = "plugins")]
+                Some("Plugins") => plugins(
+                    screen,
+                    spectators,
+                    control,
+                    &mut effects,
+                    config,
+                    &cheats,
+                    &mut high_scores,
+                ),
+                Some("Watch last game") => {
+                    match (
+                        replay::Replay::load(&paths::last_replay_file()),
+                        rules::load(config),
+                    ) {
+                        (Ok(replay), Ok(rules)) => watch(screen, &replay, rules),
+                        (Err(e), _) => message(screen, &[&e.to_string()]),
+                        (_, Err(e)) => {
+                            message(screen, &[tr("Could not load the rules script"), &e])
+                        }
+                    }
+                }
+                Some("High scores") => browser::browse(screen, config, &high_scores, false),
+                Some("Stats") => heatmap::show(screen, config),
+                #[cfg(feature = "online")]
+                Some("Online scores") => browser::browse(screen, config, &high_scores, true),
+                Some(_) => break 'menu,
+                None if b == 113 => break 'menu,
+                None => {}
+            }
+        }
+
+        menu.render(screen, &screen_size).unwrap();
+        screen.flush().unwrap();
+
+        screen.wait_for_input(IDLE_TIMEOUT);
+    }
+
+    if let Some(usage) = usage {
+        usage.finish(config);
+    }
+}
+
+// `--telnet-port` and `--ssh-port`, in either order.
+fn daemon_ports(flags: &[&str]) -> Option<Command> {
+    let (mut telnet_port, mut ssh_port) = (daemon::DEFAULT_TELNET_PORT, None);
+    for pair in flags.chunks(2) {
+        match pair {
+            ["--telnet-port", port] => telnet_port = port.parse().ok()?,
+            ["--ssh-port", port] => ssh_port = Some(port.parse().ok()?),
+            _ => return None,
+        }
+    }
+    Some(Command::Daemon(telnet_port, ssh_port))
+}
+
+enum Command {
+    Menu,
+    Replay(replay::Replay),
+    Host(u16, Mode),
+    Server(u16),
+    Join(String),
+    Spectate(String),
+    Twitch(String, String),
+    Engine,
+    Bench(u64),
+    // The bots, and how many games each pair plays.
+    Tourney(Vec<String>, u32),
+    Selfplay(selfplay::Options),
+    Edit(std::path::PathBuf),
+    Play(level::Level),
+    Export(replay::Replay, std::path::PathBuf, export::Format),
+    Import(std::path::PathBuf, std::path::PathBuf),
+    Stats(stats::Format, Option<std::path::PathBuf>),
+    Verify(replay::Replay),
+    #[cfg(feature = "ssh")]
+    ServeSsh(u16),
+    // The telnet port, and the SSH one if there's SSH too.
+    Daemon(u16, Option<u16>),
+    // What to tell the daemon.
+    Sessions(String),
+}
+
+// `snake verify`: plays `replay` back and says whether it ends the way it was
+// recorded, and if not, how the first checkpoint that's different differs.
+fn verify_replay(replay: &replay::Replay) -> bool {
+    let ending = |game: &Game| {
+        let players: Vec<String> = game
+            .players
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                format!(
+                    "player {} scored {}, length {}",
+                    i + 1,
+                    p.score,
+                    p.segments.len()
+                )
+            })
+            .collect();
+        format!("{} ticks, {}", game.tick, players.join(", "))
+    };
+
+    let (recorded, played) = match replay.verify() {
+        replay::Verification::Verified(game) => {
+            println!("Plays back as recorded: {}", ending(&game));
+            return true;
+        }
+        replay::Verification::NoChecksum(game) => {
+            println!("Played back: {}", ending(&game));
+            println!(
+                "Replays before version 7 don't say how they ended, this one is version {}",
+                replay.version
+            );
+            return false;
+        }
+        replay::Verification::Diverged { recorded, played } => (recorded, played),
+    };
+
+    let Some(recorded) = recorded else {
+        println!(
+            "Does not end as recorded, the checksum is different after {} ticks",
+            played.tick
+        );
+        return false;
+    };
+    println!("Does not play back as recorded, by tick {}:", recorded.tick);
+    let mut differences = Vec::new();
+    if recorded.tick != played.tick {
+        differences.push(format!("  the game ended on tick {}", played.tick));
+    }
+    for (i, (recorded, played)) in recorded.scores.iter().zip(&played.scores).enumerate() {
+        if recorded != played {
+            differences.push(format!(
+                "  player {} score: recorded {}, played back {}",
+                i + 1,
+                recorded,
+                played
+            ));
+        }
+    }
+    for (i, (recorded, played)) in recorded.lengths.iter().zip(&played.lengths).enumerate() {
+        if recorded != played {
+            differences.push(format!(
+                "  player {} length: recorded {}, played back {}",
+                i + 1,
+                recorded,
+                played
+            ));
+        }
+    }
+    if differences.is_empty() {
+        differences.push("  the snakes or food are somewhere else".to_string());
+    }
+    for difference in differences {
+        println!("{}", difference);
+    }
+    false
+}
+
+// Converts a level from another snake game, saved where `snake edit` can open
+// it and fix whatever doesn't fit.
+fn import_level(path: &std::path::Path, output: &std::path::Path) -> Result<(), String> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let level = import::import(&contents).map_err(|e| format!("{}: {}", path.display(), e))?;
+    level
+        .save(output)
+        .map_err(|e| format!("{}: {}", output.display(), e))?;
+
+    match level.validate() {
+        Ok(()) => println!(
+            "Imported {}, play it with snake play {}",
+            output.display(),
+            output.display()
+        ),
+        Err(e) => println!(
+            "Imported {}, but it can't be played yet: {}",
+            output.display(),
+            e
+        ),
+    }
+    Ok(())
+}
+
+fn usage() -> ! {
+    eprintln!("Usage:");
+    eprintln!(
+        "  snake [--spectate <port>] [--control <socket>] [--no-bell] [--fps <fps>|uncapped]"
+    );
+    eprintln!("        [--tick-ms <ms>] [--dev]");
+    eprintln!("  snake replay <file>");
+    eprintln!("  snake host [--coop] [--port <port>] [--spectate <port>]");
+    eprintln!("  snake server [--port <port>]");
+    eprintln!("  snake join <host>[:<port>]");
+    eprintln!("  snake spectate <host>[:<port>]");
+    eprintln!("  snake twitch <channel> [--server <host>:<port>] [--spectate <port>]");
+    eprintln!("  snake engine [--spectate <port>]");
+    eprintln!("  snake edit <level>");
+    eprintln!("  snake play <level>");
+    eprintln!("  snake import <nsnake or nibbles level> <level>");
+    eprintln!("  snake bench [<thousands of ticks>]");
+    eprintln!("  snake tourney --bots <program>... [--games <n>]");
+    eprintln!("  snake selfplay <file>.jsonl|<file> [--games <n>] [--bot easy|medium|hard]");
+    eprintln!("        [--size <w>x<h>]");
+    eprintln!("  snake export <replay> <file>.cast|<file>.gif");
+    eprintln!("  snake verify <replay>");
+    eprintln!("  snake stats export [--format json|csv] [<file>]");
+    #[cfg(feature = "ssh")]
+    eprintln!("  snake serve-ssh [--port <port>]");
+    eprintln!("  snake daemon [--telnet-port <port>] [--ssh-port <port>]");
+    eprintln!("  snake sessions [kill <session>]");
+    std::process::exit(2);
+}
+
+fn main() {
+    crash::install();
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+
+    let spectate_port: Option<u16> = match args.iter().position(|a| a == "--spectate") {
+        Some(i) => {
+            let port = args.get(i + 1).and_then(|p| p.parse().ok());
+            args.drain(i..(i + 2).min(args.len()));
+            Some(port.unwrap_or_else(|| usage()))
+        }
+        None => None,
+    };
+
+    let control_path: Option<std::path::PathBuf> = match args.iter().position(|a| a == "--control")
+    {
+        Some(i) => {
+            let path = args.get(i + 1).map(std::path::PathBuf::from);
+            args.drain(i..(i + 2).min(args.len()));
+            Some(path.unwrap_or_else(|| usage()))
+        }
+        None => None,
+    };
+
+    if let Some(i) = args.iter().position(|a| a == "--fps") {
+        let fps = match args.get(i + 1).map(|fps| fps.as_str()) {
+            Some("uncapped") => 0,
+            fps => match fps.and_then(|fps| fps.parse::<u32>().ok()) {
+                Some(fps @ 1..=MAX_FPS) => fps,
+                _ => {
+                    eprintln!("--fps takes 1 to {} or \"uncapped\"", MAX_FPS);
+                    usage()
+                }
+            },
+        };
+        FPS.store(fps, std::sync::atomic::Ordering::Relaxed);
+        args.drain(i..i + 2);
+    }
+
+    if let Some(i) = args.iter().position(|a| a == "--tick-ms") {
+        let range = FASTEST_MOVE.as_millis() as u64..=SLOWEST_MOVE.as_millis() as u64;
+        match args.get(i + 1).and_then(|ms| ms.parse::<u64>().ok()) {
+            Some(ms) if range.contains(&ms) => {
+                TICK_MS.store(ms, std::sync::atomic::Ordering::Relaxed)
+            }
+            _ => {
+                eprintln!("--tick-ms takes {} to {}", range.start(), range.end());
+                usage()
+            }
+        }
+        args.drain(i..i + 2);
+    }
+    // Drawing less often than the snakes move would skip moves.
+    let fps = FPS.load(std::sync::atomic::Ordering::Relaxed) as u64;
+    let tick_ms = TICK_MS.load(std::sync::atomic::Ordering::Relaxed);
+    if fps != 0 && fps < min_fps(tick_ms) {
+        eprintln!(
+            "--fps has to be at least {} for a tick every {}ms",
+            min_fps(tick_ms),
+            tick_ms
+        );
+        usage();
+    }
+
+    if let Some(i) = args.iter().position(|a| a == "--dev") {
+        args.remove(i);
+        console::ENABLED.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    let no_bell = match args.iter().position(|a| a == "--no-bell") {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    };
+
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+
+    let command = match args.as_slice() {
+        [] => Command::Menu,
+        ["replay", path] => match replay::Replay::load(std::path::Path::new(path)) {
+            Ok(replay) => Command::Replay(replay),
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                std::process::exit(1);
+            }
+        },
+        ["host"] => Command::Host(net::DEFAULT_PORT, Mode::Versus),
+        ["host", "--coop"] => Command::Host(net::DEFAULT_PORT, Mode::Coop),
+        ["host", "--port", port] | ["host", "--coop", "--port", port] => match port.parse() {
+            Ok(port) if args.contains(&"--coop") => Command::Host(port, Mode::Coop),
+            Ok(port) => Command::Host(port, Mode::Versus),
+            Err(_) => usage(),
+        },
+        ["server"] => Command::Server(net::DEFAULT_PORT),
+        ["server", "--port", port] => match port.parse() {
+            Ok(port) => Command::Server(port),
+            Err(_) => usage(),
+        },
+        ["join", address] => Command::Join(address.to_string()),
+        ["spectate", address] => Command::Spectate(address.to_string()),
+        ["twitch", channel] => {
+            Command::Twitch(twitch::DEFAULT_SERVER.to_string(), channel.to_string())
+        }
+        ["twitch", channel, "--server", server] => {
+            Command::Twitch(server.to_string(), channel.to_string())
+        }
+        ["engine"] => Command::Engine,
+        ["bench"] | ["bench", _] => match bench::parse(args.get(1).copied()) {
+            Some(thousands) => Command::Bench(thousands),
+            None => usage(),
+        },
+        ["tourney", rest @ ..] => match tourney::parse(rest) {
+            Some((bots, games)) => Command::Tourney(bots, games),
+            None => usage(),
+        },
+        ["selfplay", rest @ ..] => match selfplay::parse(rest) {
+            Some(options) => Command::Selfplay(options),
+            None => usage(),
+        },
+        ["edit", path] => Command::Edit(path.into()),
+        ["play", path] => match level::Level::load(std::path::Path::new(path)) {
+            Ok(level) => Command::Play(level),
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                std::process::exit(1);
+            }
+        },
+        ["import", path, output] => Command::Import(path.into(), output.into()),
+        ["stats", "export", rest @ ..] => {
+            let (format, rest) = match rest {
+                ["--format", name, rest @ ..] => match stats::Format::parse(name) {
+                    Some(format) => (format, rest),
+                    None => usage(),
+                },
+                _ => (stats::Format::Json, rest),
+            };
+            match rest {
+                [] => Command::Stats(format, None),
+                [path] => Command::Stats(format, Some(path.into())),
+                _ => usage(),
+            }
+        }
+        ["verify", path] => match replay::Replay::load(std::path::Path::new(path)) {
+            Ok(replay) => Command::Verify(replay),
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                std::process::exit(1);
+            }
+        },
+        ["export", path, output] => {
+            let output = std::path::PathBuf::from(output);
+            let Some(format) = export::Format::from_path(&output) else {
+                usage()
+            };
+            match replay::Replay::load(std::path::Path::new(path)) {
+                Ok(replay) => Command::Export(replay, output, format),
+                Err(e) => {
+                    eprintln!("{}: {}", path, e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        #[cfg(feature = "ssh")]
+        ["serve-ssh"] => Command::ServeSsh(ssh::DEFAULT_PORT),
+        #[cfg(feature = "ssh")]
+        ["serve-ssh", "--port", port] => match port.parse() {
+            Ok(port) => Command::ServeSsh(port),
+            Err(_) => usage(),
+        },
+        ["daemon", flags @ ..] => daemon_ports(flags).unwrap_or_else(|| usage()),
+        ["sessions"] => Command::Sessions("list".to_string()),
+        ["sessions", "kill", id] => Command::Sessions(format!("kill {}", id)),
+        _ => usage(),
+    };
+
+    let spectators = spectate_port.map(|port| match spectate::Spectators::listen(port) {
+        Ok(spectators) => spectators,
+        Err(e) => {
+            eprintln!("Could not listen for spectators on port {}: {}", port, e);
+            std::process::exit(1);
+        }
+    });
+
+    // Only the local menu has games to drive.
+    let control = match (&command, control_path) {
+        (Command::Menu, Some(path)) => match control::Control::listen(&path) {
+            Ok(control) => Some(control),
+            Err(e) => {
+                eprintln!("Could not listen on {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        },
+        (_, Some(_)) => usage(),
+        (_, None) => None,
+    };
+
+    if let Command::Export(replay, output, format) = &command {
+        if let Err(e) = export::export(replay, *format, output) {
+            eprintln!("{}: {}", output.display(), e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Command::Import(path, output) = &command {
+        match import_level(path, output) {
+            Ok(()) => return,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Command::Stats(format, output) = &command {
+        if let Err(e) = stats::export(*format, output.as_deref()) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Command::Verify(replay) = &command {
+        if !verify_replay(replay) {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Command::Bench(thousands) = command {
+        bench::run(thousands);
+        return;
+    }
+
+    if let Command::Tourney(bots, games) = &command {
+        if let Err(e) = tourney::run(bots, *games) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Command::Selfplay(options) = &command {
+        if let Err(e) = selfplay::run(options) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Command::Sessions(command) = &command {
+        if let Err(e) = daemon::admin(command) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Command::Server(port) = command {
+        if let Err(e) = server::serve(port, move_duration()) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Bots talk over stdin and stdout, so the terminal is left alone.
+    if let Command::Engine = command {
+        if let Err(e) = engine::run(spectators.as_ref()) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let mut config = config::Config::load().unwrap_or_else(|e| {
+        eprintln!("Ignoring invalid config: {}", e);
+        config::Config::default()
+    });
+    if no_bell && config.feedback == snake::reactions::Feedback::Bell {
+        config.feedback = snake::reactions::Feedback::Flash;
+    }
+    let (cell_width, cell_height) = config.cell_size.columns_rows();
+    CELL_WIDTH.store(cell_width, std::sync::atomic::Ordering::Relaxed);
+    CELL_HEIGHT.store(cell_height, std::sync::atomic::Ordering::Relaxed);
+    FOOD_HINT.store(config.food_hint, std::sync::atomic::Ordering::Relaxed);
+    REDUCED_MOTION.store(config.reduced_motion, std::sync::atomic::Ordering::Relaxed);
+    camera::set_steady(config.reduced_motion);
+    DAY_NIGHT.store(config.day_night, std::sync::atomic::Ordering::Relaxed);
+    SPLIT_SCREEN.store(config.split_screen, std::sync::atomic::Ordering::Relaxed);
+    // Emoji are two columns wide and would be left out of narrower cells, so
+    // those get letters too.
+    EMOJI_FOOD.store(
+        config.emoji_food && cell_width >= 2 && term::supports_emoji(),
+        std::sync::atomic::Ordering::Relaxed,
+    );
+    if let Some(curve) = &config.speed_curve {
+        let _ = SPEED_CURVE.set(curve.clone());
+    }
+    theme::init(&config);
+    if let Err(e) = l10n::init(&config) {
+        eprintln!("Ignoring invalid translation: {}", e);
+    }
+
+    if let Err(e) = paths::create_dirs() {
+        eprintln!(
+            "Could not create data directories, scores won't be saved: {}",
+            e
+        );
+    }
+
+    #[cfg(feature = "ssh")]
+    if let Command::ServeSsh(port) = command {
+        if let Err(e) = ssh::serve(port, config, Default::default()) {
+            eprintln!("Could not serve SSH on port {}: {}", port, e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Command::Daemon(telnet_port, ssh_port) = command {
+        if let Err(e) = daemon::run(config, telnet_port, ssh_port) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let mut screen = term::LocalTerminal::new().unwrap();
+
+    match command {
+        Command::Menu => menu(&mut screen, spectators.as_ref(), control.as_ref(), &config),
+        Command::Replay(replay) => match rules::load(&config) {
+            Ok(rules) => watch(&mut screen, &replay, rules),
+            Err(e) => message(&mut screen, &[tr("Could not load the rules script"), &e]),
+        },
+        Command::Host(port, mode) => host(&mut screen, port, mode, spectators.as_ref(), &config),
+        Command::Join(address) => join(&mut screen, &address, &config),
+        Command::Spectate(address) => spectate(&mut screen, &address),
+        Command::Twitch(server, channel) => {
+            twitch(&mut screen, &server, &channel, spectators.as_ref())
+        }
+        Command::Edit(path) => {
+            editor::edit(&mut screen, &path, &mut effects::Effects::new(&config))
+        }
+        Command::Play(level) => {
+            editor::play(&mut screen, &level, &mut effects::Effects::new(&config))
+        }
+        Command::Engine
+        | Command::Bench(_)
+        | Command::Tourney(..)
+        | Command::Selfplay(_)
+        | Command::Export(..)
+        | Command::Import(..)
+        | Command::Stats(..)
+        | Command::Verify(_)
+        | Command::Server(_)
+        | Command::Daemon(..)
+        | Command::Sessions(_) => unreachable!(),
+        #[cfg(feature = "ssh")]
+        Command::ServeSsh(_) => unreachable!(),
+    }
+
+    clear(&mut screen).unwrap();
     screen.flush().unwrap();
 }
@@ -0,0 +1,84 @@
+// A small picture of the whole board, for when the terminal has shrunk below
+// it and the game is waiting for it to grow back: every character stands for
+// a block of cells, showing the most important thing in it.
+
+use crate::game::{Coordinate, Food, Player};
+use crate::level::Level;
+use std::io::Write;
+
+// What a block has in it, from least to most worth showing.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Block {
+    Empty,
+    Wall,
+    Food,
+    Snake(usize),
+    Head(usize),
+}
+
+// Draws the map in the top left corner, in at most `space` columns and rows,
+// or nothing when there isn't room for one.
+pub fn render(
+    screen: &mut dyn Write,
+    space: (u16, u16),
+    level: &Level,
+    players: &[Player],
+    food: &[Food],
+) -> Result<(), std::io::Error> {
+    let (width, height) = level.size;
+    if space.0 < 2 || space.1 < 2 {
+        return Ok(());
+    }
+    // How many cells across and down a block is, leaving a column and a row
+    // for the border.
+    let scale = (
+        width.div_ceil(space.0 - 1).max(1),
+        height.div_ceil(space.1 - 1).max(1),
+    );
+    let size = (width.div_ceil(scale.0), height.div_ceil(scale.1));
+
+    let mut blocks = vec![Block::Empty; size.0 as usize * size.1 as usize];
+    let mut mark = |Coordinate(x, y): Coordinate, block: Block| {
+        if !Coordinate(x, y).on_board(level.size) {
+            return;
+        }
+        let index = ((y - 1) / scale.1) as usize * size.0 as usize + ((x - 1) / scale.0) as usize;
+        blocks[index] = blocks[index].max(block);
+    };
+    for wall in level.walls.iter().copied().chain(level.mask_edge()) {
+        mark(wall, Block::Wall);
+    }
+    for cell in food.iter().flat_map(|f| f.cells()) {
+        mark(cell, Block::Food);
+    }
+    for (index, player) in players.iter().enumerate() {
+        for segment in player.segments.iter().skip(1) {
+            mark(*segment, Block::Snake(index));
+        }
+        if let Some(head) = player.segments.front() {
+            mark(*head, Block::Head(index));
+        }
+    }
+
+    let theme = crate::theme::current();
+    for (row, line) in (1..).zip(blocks.chunks(size.0 as usize)) {
+        write!(screen, "{}", termion::cursor::Goto(1, row))?;
+        for block in line {
+            let (color, glyph) = match *block {
+                Block::Empty => (crate::theme::shade(termion::color::Rgb(60, 60, 60)), '·'),
+                Block::Wall => (theme.wall, '▒'),
+                Block::Food => (theme.food[0].color, '*'),
+                Block::Snake(index) => (crate::theme::snake(index).1, '█'),
+                Block::Head(index) => (crate::theme::snake(index).0, '█'),
+            };
+            write!(screen, "{}{}", termion::color::Fg(color), glyph)?;
+        }
+        write!(screen, "{}│", termion::color::Fg(termion::color::Reset))?;
+    }
+    write!(
+        screen,
+        "{}{}┘",
+        termion::cursor::Goto(1, size.1 + 1),
+        "─".repeat(size.0 as usize)
+    )
+}
@@ -0,0 +1,337 @@
+// Two player games over TCP. In versus the host runs the only real `Game` and
+// sends the whole board to the client after every move; the client just
+// renders what it receives and forwards its key presses. In co-op both sides
+// run the game and only send their turns, see `lockstep`. Against `snake
+// server` neither player hosts: the server plays the game and sends the turns
+// it played every tick, and clients show what they predict, see `predict`.
+// Messages are a kind byte followed by their fields, encoded as in `wire`:
+//
+//   kind  from    message  fields
+//   0     client  hello    <width> <height>     terminal size, sent on connect
+//   1     host    lobby    <mode speed board>   the settings as text, see
+//                                               `lobby`
+//   2     both    color    <n>                  the color picked, in the lobby
+//   3     both    ready    <0|1>
+//   4     host    start    <width> <height>     board size both sides play on
+//   5     client  turn     <direction>
+//   6     host    state    <snapshot>
+//   7     host    over                          sent after the final state
+//   8     host    coop     <width> <height> <seed>
+//                                               a co-op game instead of start
+//   9     both    turns    <tick> <count> <direction ...>
+//                                               in co-op, every tick's turns
+//   10    server  match    <width> <height> <seed> <you>
+//                                               a game against the server's
+//                                               other client, as snake <you>
+//   11    client  turn-at  <tick> <direction>
+//   12    server  tick     <tick> <count> <snake direction ...>
+//                                               the turns it played, in order
+//   13    both    say      <text>               a line of chat, relayed by
+//                                               servers
+//   14    both    quit
+//   15    both    rating   <n>                  the player's, see `ratings`,
+//                                               sent on entering the lobby
+
+use crate::game::{Coordinate, Food, FoodKind, Game, MoveDirection, Player};
+use crate::wire::{self, Decoder, Encoder};
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_PORT: u16 = 7777;
+
+// Everything a client needs to draw a frame. Spectators get the same thing,
+// see `spectate`.
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub tick: u32,
+    pub board_size: (u16, u16),
+    pub players: Vec<Player>,
+    pub food: Vec<Food>,
+}
+
+impl Snapshot {
+    pub fn new(game: &Game) -> Self {
+        Snapshot {
+            tick: game.tick,
+            board_size: game.board_size,
+            players: game.players.clone(),
+            food: game.food.clone(),
+        }
+    }
+
+    // <tick> <width> <height> <food count> <x y kind ...> <player count>
+    // then <alive> <score> <direction> <segments as a path> for every player.
+    pub fn encode(&self, out: &mut Encoder) {
+        out.number(self.tick.into());
+        out.number(self.board_size.0.into());
+        out.number(self.board_size.1.into());
+
+        out.number(self.food.len() as u64);
+        for food in &self.food {
+            out.coordinate(food.location);
+            out.byte(match food.kind {
+                FoodKind::Apple => 0,
+                FoodKind::Cherry => 1,
+                FoodKind::Mouse => 2,
+                FoodKind::Feast => 3,
+            });
+        }
+
+        out.number(self.players.len() as u64);
+        for player in &self.players {
+            out.byte(player.alive as u8);
+            out.number(player.score.into());
+            out.direction(player.move_direction);
+            let segments: Vec<Coordinate> = player.segments.iter().copied().collect();
+            out.path(&segments);
+        }
+    }
+
+    pub fn decode(input: &mut Decoder) -> Option<Self> {
+        let tick = input.small()?;
+        let board_size = (input.small()?, input.small()?);
+
+        let mut food = Vec::new();
+        for _ in 0..input.number()? {
+            let location = input.coordinate()?;
+            let kind = match input.byte()? {
+                0 => FoodKind::Apple,
+                1 => FoodKind::Cherry,
+                2 => FoodKind::Mouse,
+                3 => FoodKind::Feast,
+                _ => return None,
+            };
+            food.push(Food {
+                kind,
+                ..Food::new(location)
+            });
+        }
+
+        let mut players = Vec::new();
+        for _ in 0..input.number()? {
+            let alive = input.byte()? == 1;
+            let score = input.small()?;
+            let move_direction = input.direction()?;
+            let segments = input.path()?;
+            if segments.is_empty() {
+                return None;
+            }
+
+            let mut player = Player::from_segments(move_direction, segments.into());
+            player.score = score;
+            player.alive = alive;
+            players.push(player);
+        }
+
+        Some(Snapshot {
+            tick,
+            board_size,
+            players,
+            food,
+        })
+    }
+}
+
+pub enum Message {
+    Hello(u16, u16),
+    Lobby(crate::lobby::Settings),
+    Color(usize),
+    Ready(bool),
+    Start(u16, u16),
+    Turn(MoveDirection),
+    State(Snapshot),
+    Over,
+    Coop(u16, u16, u64),
+    Turns(u32, Vec<MoveDirection>),
+    Match(u16, u16, u64, usize),
+    TurnAt(u32, MoveDirection),
+    Tick(u32, Vec<(usize, MoveDirection)>),
+    Say(String),
+    Quit,
+    Rating(u32),
+}
+
+impl Message {
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Encoder::default();
+        match self {
+            Message::Hello(w, h) => {
+                out.byte(0);
+                out.number((*w).into());
+                out.number((*h).into());
+            }
+            Message::Lobby(settings) => {
+                out.byte(1);
+                out.text(&settings.encode());
+            }
+            Message::Color(color) => {
+                out.byte(2);
+                out.number(*color as u64);
+            }
+            Message::Ready(ready) => {
+                out.byte(3);
+                out.byte(*ready as u8);
+            }
+            Message::Start(w, h) => {
+                out.byte(4);
+                out.number((*w).into());
+                out.number((*h).into());
+            }
+            Message::Turn(direction) => {
+                out.byte(5);
+                out.direction(*direction);
+            }
+            Message::State(snapshot) => {
+                out.byte(6);
+                snapshot.encode(&mut out);
+            }
+            Message::Over => out.byte(7),
+            Message::Coop(w, h, seed) => {
+                out.byte(8);
+                out.number((*w).into());
+                out.number((*h).into());
+                out.number(*seed);
+            }
+            Message::Turns(tick, turns) => {
+                out.byte(9);
+                out.number((*tick).into());
+                out.number(turns.len() as u64);
+                for direction in turns {
+                    out.direction(*direction);
+                }
+            }
+            Message::Match(w, h, seed, you) => {
+                out.byte(10);
+                out.number((*w).into());
+                out.number((*h).into());
+                out.number(*seed);
+                out.number(*you as u64);
+            }
+            Message::TurnAt(tick, direction) => {
+                out.byte(11);
+                out.number((*tick).into());
+                out.direction(*direction);
+            }
+            Message::Tick(tick, turns) => {
+                out.byte(12);
+                out.number((*tick).into());
+                out.number(turns.len() as u64);
+                for (snake, direction) in turns {
+                    out.number(*snake as u64);
+                    out.direction(*direction);
+                }
+            }
+            Message::Say(line) => {
+                out.byte(13);
+                out.text(line);
+            }
+            Message::Quit => out.byte(14),
+            Message::Rating(rating) => {
+                out.byte(15);
+                out.number((*rating).into());
+            }
+        }
+        out.bytes
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut input = Decoder::new(bytes);
+        let input = &mut input;
+        let message = match input.byte()? {
+            0 => Message::Hello(input.small()?, input.small()?),
+            1 => Message::Lobby(crate::lobby::Settings::decode(&input.text()?)?),
+            2 => Message::Color(input.small()?),
+            3 => Message::Ready(input.byte()? == 1),
+            4 => Message::Start(input.small()?, input.small()?),
+            5 => Message::Turn(input.direction()?),
+            6 => Message::State(Snapshot::decode(input)?),
+            7 => Message::Over,
+            8 => Message::Coop(input.small()?, input.small()?, input.number()?),
+            9 => {
+                let tick = input.small()?;
+                let turns = (0..input.number()?)
+                    .map(|_| input.direction())
+                    .collect::<Option<_>>()?;
+                Message::Turns(tick, turns)
+            }
+            10 => Message::Match(
+                input.small()?,
+                input.small()?,
+                input.number()?,
+                input.small()?,
+            ),
+            11 => Message::TurnAt(input.small()?, input.direction()?),
+            12 => {
+                let tick = input.small()?;
+                let turns = (0..input.number()?)
+                    .map(|_| Some((input.small()?, input.direction()?)))
+                    .collect::<Option<_>>()?;
+                Message::Tick(tick, turns)
+            }
+            13 => Message::Say(input.text()?),
+            14 => Message::Quit,
+            15 => Message::Rating(input.small()?),
+            _ => return None,
+        };
+        // Leftovers mean it wasn't the message it looked like.
+        input.is_empty().then_some(message)
+    }
+}
+
+// Reading happens on a separate thread so the game loops can poll for
+// messages without ever blocking on the socket.
+pub struct Connection {
+    stream: std::net::TcpStream,
+    messages: std::sync::mpsc::Receiver<Message>,
+}
+
+impl Connection {
+    pub fn new(stream: std::net::TcpStream) -> Result<Self, std::io::Error> {
+        stream.set_nodelay(true)?;
+        let mut reader = std::io::BufReader::new(stream.try_clone()?);
+        let (sender, messages) = std::sync::mpsc::channel();
+
+        std::thread::spawn(move || {
+            if !wire::read_header(&mut reader) {
+                return;
+            }
+            // A message we don't understand means the other side isn't
+            // speaking our protocol, so treat it like a disconnect.
+            while let Some(message) = wire::read_frame(&mut reader)
+                .as_deref()
+                .and_then(Message::decode)
+            {
+                if sender.send(message).is_err() {
+                    return;
+                }
+            }
+        });
+
+        let mut stream = stream;
+        wire::write_header(&mut stream)?;
+        Ok(Connection { stream, messages })
+    }
+
+    pub fn send(&mut self, message: &Message) -> Result<(), std::io::Error> {
+        wire::write_frame(&mut self.stream, &message.encode())
+    }
+
+    // Everything that arrived since the last call, or None once the other
+    // side has gone away.
+    pub fn receive(&self) -> Option<Vec<Message>> {
+        let mut messages = Vec::new();
+
+        loop {
+            match self.messages.try_recv() {
+                Ok(message) => messages.push(message),
+                Err(std::sync::mpsc::TryRecvError::Empty) => return Some(messages),
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                    return if messages.is_empty() {
+                        None
+                    } else {
+                        Some(messages)
+                    };
+                }
+            }
+        }
+    }
+}
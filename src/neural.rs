@@ -0,0 +1,165 @@
+// A computer player steered by a small neural net, only built with the
+// `neural` feature and only offered when `neural_net` is set in the config.
+// The net is a JSON file of fully connected layers:
+//
+//   {"view": 11,
+//    "layers": [{"weights": [[...], ...], "biases": [...]}, ...]}
+//
+// with a row of weights for every output of a layer, and as many in a row as
+// the layer has inputs. Every layer but the last goes through a ReLU. The
+// input is what's around the snake's head, view cells across, as the planes
+// `selfplay` writes: its head, the rest of it, food, and anything else that
+// would kill it, which is walls, other snakes and off the board. The last
+// layer has an output for every way to go, in `selfplay`'s order, and the
+// snake goes the highest one it can.
+
+use crate::ai::Controller;
+use crate::game::{Coordinate, Game, MoveDirection};
+use serde::Deserialize;
+
+const CHANNELS: usize = 4;
+// In the order of the outputs.
+const DIRECTIONS: [MoveDirection; 4] = [
+    MoveDirection::Up,
+    MoveDirection::Down,
+    MoveDirection::Left,
+    MoveDirection::Right,
+];
+
+#[derive(Deserialize)]
+struct Layer {
+    weights: Vec<Vec<f32>>,
+    biases: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Net {
+    view: u16,
+    layers: Vec<Layer>,
+}
+
+impl Net {
+    pub fn load(path: &std::path::Path) -> Result<Self, String> {
+        let read = || -> Result<Net, String> {
+            let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+            let net: Net = serde_json::from_str(&contents).map_err(|e| e.to_string())?;
+            net.validate()?;
+            Ok(net)
+        };
+        read().map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.view.is_multiple_of(2) {
+            return Err("the view has to be an odd number of cells".to_string());
+        }
+        let mut inputs = CHANNELS * self.view as usize * self.view as usize;
+        for (index, layer) in self.layers.iter().enumerate() {
+            if layer.biases.len() != layer.weights.len() {
+                return Err(format!(
+                    "layer {} has {} outputs but {} biases",
+                    index,
+                    layer.weights.len(),
+                    layer.biases.len()
+                ));
+            }
+            if let Some(row) = layer.weights.iter().find(|row| row.len() != inputs) {
+                return Err(format!(
+                    "layer {} has {} inputs but a row of {} weights",
+                    index,
+                    inputs,
+                    row.len()
+                ));
+            }
+            inputs = layer.weights.len();
+        }
+        match (self.layers.is_empty(), inputs) {
+            (false, 4) => Ok(()),
+            _ => Err("the last layer has to have an output for every direction".to_string()),
+        }
+    }
+
+    // The planes around `player`'s head, one after the other.
+    fn observe(&self, game: &Game, player: usize) -> Vec<f32> {
+        let view = self.view as i32;
+        let Coordinate(head_x, head_y) = game.players[player].head();
+        let mut input = vec![0.0; CHANNELS * (view * view) as usize];
+
+        for dy in 0..view {
+            for dx in 0..view {
+                let x = i32::from(head_x) + dx - view / 2;
+                let y = i32::from(head_y) + dy - view / 2;
+                let cell = (dy * view + dx) as usize;
+                let channel = match (u16::try_from(x), u16::try_from(y)) {
+                    (Ok(x), Ok(y)) if Coordinate(x, y).on_board(game.board_size) => {
+                        channel(game, player, Coordinate(x, y))
+                    }
+                    _ => Some(3),
+                };
+                if let Some(channel) = channel {
+                    input[channel * (view * view) as usize + cell] = 1.0;
+                }
+            }
+        }
+        input
+    }
+
+    fn run(&self, mut values: Vec<f32>) -> Vec<f32> {
+        for (index, layer) in self.layers.iter().enumerate() {
+            values = layer
+                .weights
+                .iter()
+                .zip(&layer.biases)
+                .map(|(row, bias)| {
+                    let sum = row.iter().zip(&values).map(|(w, v)| w * v).sum::<f32>() + bias;
+                    match index + 1 == self.layers.len() {
+                        true => sum,
+                        false => sum.max(0.0),
+                    }
+                })
+                .collect();
+        }
+        values
+    }
+}
+
+// Which plane `coordinate` is set in for `player`, if any.
+fn channel(game: &Game, player: usize, coordinate: Coordinate) -> Option<usize> {
+    let own = &game.players[player];
+    if own.head() == coordinate {
+        return Some(0);
+    }
+    if own.segments.contains(&coordinate) {
+        return Some(1);
+    }
+    if game.level.is_wall(&coordinate)
+        || game
+            .players
+            .iter()
+            .any(|other| other.alive && other.segments.contains(&coordinate))
+    {
+        return Some(3);
+    }
+    game.food
+        .iter()
+        .any(|food| food.covers(coordinate))
+        .then_some(2)
+}
+
+// Steers with a net that can be shared with other games.
+pub struct Driver(pub std::rc::Rc<Net>);
+
+impl Controller for Driver {
+    fn steer(&mut self, game: &Game, player: usize) -> Option<MoveDirection> {
+        let current = game.players[player].move_direction;
+        let outputs = self.0.run(self.0.observe(game, player));
+
+        let (_, best) = outputs
+            .iter()
+            .zip(DIRECTIONS)
+            .filter(|(_, direction)| *direction != current.opposite())
+            .max_by(|(a, _), (b, _)| a.total_cmp(b))?;
+        (best != current).then_some(best)
+    }
+}
@@ -0,0 +1,133 @@
+// Where snake keeps its files: the XDG base directories on Linux and other
+// unixes, ~/Library/Application Support on macOS and %APPDATA% on Windows.
+
+use std::path::PathBuf;
+
+const APP_NAME: &str = "snake";
+
+pub fn config_dir() -> PathBuf {
+    base_config_dir().join(APP_NAME)
+}
+
+pub fn data_dir() -> PathBuf {
+    base_data_dir().join(APP_NAME)
+}
+
+pub fn config_file() -> PathBuf {
+    config_dir().join("config.toml")
+}
+
+#[cfg_attr(not(feature = "ssh"), allow(dead_code))]
+pub fn ssh_host_key_file() -> PathBuf {
+    config_dir().join("ssh_host_key")
+}
+
+#[cfg_attr(not(feature = "plugins"), allow(dead_code))]
+pub fn plugin_dir() -> PathBuf {
+    config_dir().join("plugins")
+}
+
+pub fn scores_file() -> PathBuf {
+    data_dir().join("scores")
+}
+
+pub fn replay_dir() -> PathBuf {
+    data_dir().join("replays")
+}
+
+pub fn last_replay_file() -> PathBuf {
+    replay_dir().join("last.replay")
+}
+
+pub fn locale_file(language: &str) -> PathBuf {
+    config_dir()
+        .join("locales")
+        .join(format!("{}.toml", language))
+}
+
+pub fn usage_file() -> PathBuf {
+    data_dir().join("usage.json")
+}
+
+pub fn deaths_file() -> PathBuf {
+    data_dir().join("deaths")
+}
+
+pub fn ratings_file() -> PathBuf {
+    data_dir().join("ratings.json")
+}
+
+pub fn runs_dir() -> PathBuf {
+    data_dir().join("runs")
+}
+
+pub fn dump_dir() -> PathBuf {
+    data_dir().join("dumps")
+}
+
+pub fn crash_dir() -> PathBuf {
+    data_dir().join("crashes")
+}
+
+// Where `snake sessions` finds the daemon.
+pub fn daemon_socket() -> PathBuf {
+    data_dir().join("daemon.sock")
+}
+
+pub fn create_dirs() -> Result<(), std::io::Error> {
+    std::fs::create_dir_all(config_dir())?;
+    std::fs::create_dir_all(replay_dir())
+}
+
+fn home_dir() -> PathBuf {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+#[cfg(target_os = "windows")]
+fn base_config_dir() -> PathBuf {
+    app_data_dir()
+}
+
+#[cfg(target_os = "windows")]
+fn base_data_dir() -> PathBuf {
+    app_data_dir()
+}
+
+#[cfg(target_os = "windows")]
+fn app_data_dir() -> PathBuf {
+    std::env::var_os("APPDATA")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home_dir().join("AppData").join("Roaming"))
+}
+
+#[cfg(target_os = "macos")]
+fn base_config_dir() -> PathBuf {
+    home_dir().join("Library").join("Application Support")
+}
+
+#[cfg(target_os = "macos")]
+fn base_data_dir() -> PathBuf {
+    home_dir().join("Library").join("Application Support")
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn base_config_dir() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn base_data_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+// The spec says relative paths in these variables are invalid and should be
+// ignored, so they fall back to the default just like an unset variable.
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn xdg_dir(var: &str, default: &str) -> PathBuf {
+    match std::env::var_os(var).map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => dir,
+        _ => home_dir().join(default),
+    }
+}
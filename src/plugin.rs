@@ -0,0 +1,193 @@
+// Game modes as wasm modules, picked from the `.wasm` files in the plugins
+// directory. A plugin exports whichever of these it needs, all taking and
+// returning i32s:
+//
+//   food_points(player) -> points     how much eating food is worth, instead
+//                                     of what its kind is worth
+//   spawn_food(width, height) -> xy   where eaten food comes back, as
+//                                     x * 65536 + y, or -1 for a random cell
+//   is_over(tick) -> over             non-zero to end the game after this tick
+//
+// and may import these from the "snake" module to look at the game:
+//
+//   tick() score(player) length(player) head_x(player) head_y(player)
+//
+// Players are numbered from 0. Plugins get nothing else from the outside and
+// run with limited memory and fuel, so a broken one can't hang the game.
+
+use crate::game::{Coordinate, Event, Game, Player};
+use wasmi::{Caller, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc};
+
+const MAX_MEMORY: usize = 16 << 20;
+const FUEL_PER_CALL: u64 = 10_000_000;
+
+// What the imported functions see: a copy of the game from just before the
+// current call.
+struct View {
+    tick: u32,
+    players: Vec<Player>,
+    limits: StoreLimits,
+}
+
+pub struct Plugin {
+    store: Store<View>,
+    food_points: Option<TypedFunc<i32, i32>>,
+    spawn_food: Option<TypedFunc<(i32, i32), i32>>,
+    is_over: Option<TypedFunc<i32, i32>>,
+}
+
+// The plugins directory's `.wasm` files by name, without the extension.
+pub fn discover() -> Vec<(String, std::path::PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(crate::paths::plugin_dir()) else {
+        return Vec::new();
+    };
+
+    let mut plugins: Vec<(String, std::path::PathBuf)> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|e| e == "wasm"))
+        .filter_map(|path| Some((path.file_stem()?.to_str()?.to_string(), path)))
+        .collect();
+    plugins.sort();
+    plugins
+}
+
+impl Plugin {
+    pub fn load(path: &std::path::Path) -> Result<Self, String> {
+        let error = |e: &dyn std::fmt::Display| format!("{}: {}", path.display(), e);
+        let wasm = std::fs::read(path).map_err(|e| error(&e))?;
+
+        let mut config = wasmi::Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, wasm).map_err(|e| error(&e))?;
+
+        let mut store = Store::new(
+            &engine,
+            View {
+                tick: 0,
+                players: Vec::new(),
+                limits: StoreLimitsBuilder::new().memory_size(MAX_MEMORY).build(),
+            },
+        );
+        store.limiter(|view| &mut view.limits);
+        store.set_fuel(FUEL_PER_CALL).map_err(|e| error(&e))?;
+
+        let mut linker = <Linker<View>>::new(&engine);
+        let player = |caller: &Caller<'_, View>, index: i32| -> Option<Player> {
+            let index = usize::try_from(index).ok()?;
+            caller.data().players.get(index).cloned()
+        };
+        linker
+            .func_wrap("snake", "tick", |caller: Caller<'_, View>| {
+                caller.data().tick as i32
+            })
+            .and_then(|l| {
+                l.func_wrap("snake", "score", move |caller: Caller<'_, View>, index| {
+                    player(&caller, index).map_or(-1, |p| p.score as i32)
+                })
+            })
+            .and_then(|l| {
+                l.func_wrap("snake", "length", move |caller: Caller<'_, View>, index| {
+                    player(&caller, index).map_or(-1, |p| p.segments.len() as i32)
+                })
+            })
+            .and_then(|l| {
+                l.func_wrap("snake", "head_x", move |caller: Caller<'_, View>, index| {
+                    player(&caller, index).map_or(-1, |p| p.head().0 as i32)
+                })
+            })
+            .and_then(|l| {
+                l.func_wrap("snake", "head_y", move |caller: Caller<'_, View>, index| {
+                    player(&caller, index).map_or(-1, |p| p.head().1 as i32)
+                })
+            })
+            .map_err(|e| error(&e))?;
+
+        let instance = linker
+            .instantiate_and_start(&mut store, &module)
+            .map_err(|e| error(&e))?;
+
+        Ok(Plugin {
+            food_points: export(&instance, &store, "food_points").map_err(|e| error(&e))?,
+            spawn_food: export(&instance, &store, "spawn_food").map_err(|e| error(&e))?,
+            is_over: export(&instance, &store, "is_over").map_err(|e| error(&e))?,
+            store,
+        })
+    }
+
+    fn call<Params: wasmi::WasmParams, Results: wasmi::WasmResults>(
+        &mut self,
+        func: TypedFunc<Params, Results>,
+        game: &Game,
+        params: Params,
+    ) -> Result<Results, String> {
+        let view = self.store.data_mut();
+        view.tick = game.tick;
+        view.players.clone_from(&game.players);
+
+        self.store
+            .set_fuel(FUEL_PER_CALL)
+            .map_err(|e| e.to_string())?;
+        func.call(&mut self.store, params)
+            .map_err(|e| e.to_string())
+    }
+}
+
+// None if the plugin doesn't export `name`, an error if it does with the wrong
+// signature.
+fn export<Params: wasmi::WasmParams, Results: wasmi::WasmResults>(
+    instance: &wasmi::Instance,
+    store: &Store<View>,
+    name: &str,
+) -> Result<Option<TypedFunc<Params, Results>>, String> {
+    instance
+        .get_func(store, name)
+        .map(|func| func.typed(store))
+        .transpose()
+        .map_err(|e| format!("{}: {}", name, e))
+}
+
+impl crate::rules::Rules for Plugin {
+    fn after_tick(&mut self, game: &mut Game) -> Result<bool, String> {
+        for event in game.events.clone() {
+            let Event::FoodEaten {
+                player,
+                food,
+                points: eaten,
+            } = event
+            else {
+                continue;
+            };
+
+            if let Some(food_points) = self.food_points {
+                let points = self.call(food_points, game, player as i32)?;
+                let score = &mut game.players[player].score;
+                *score = (*score - eaten).saturating_add(points.max(0) as u32);
+            }
+
+            if let Some(spawn_food) = self.spawn_food {
+                let (width, height) = game.board_size;
+                let xy = self.call(spawn_food, game, (width as i32, height as i32))?;
+                if xy >= 0 {
+                    let location = Coordinate((xy >> 16) as u16, xy as u16);
+                    if game
+                        .players
+                        .iter()
+                        .any(|p| p.check_collisions(&location, &game.board_size))
+                    {
+                        return Err(format!(
+                            "spawn_food returned {}, {}, which isn't a free cell",
+                            location.0, location.1
+                        ));
+                    }
+                    game.food[food].location = location;
+                }
+            }
+        }
+
+        match self.is_over {
+            Some(is_over) => Ok(self.call(is_over, game, game.tick as i32)? != 0),
+            None => Ok(false),
+        }
+    }
+}
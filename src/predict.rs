@@ -0,0 +1,91 @@
+// Versus against a server that decides what happens, which is a round trip
+// away. Steering a snake that only turns once the server has heard about it
+// feels like steering through mud, so the client shows where things will
+// probably be instead: the game as the server last played it, `lead` ticks
+// further on with this side's turns that are still on their way, and the
+// other snake going straight. When the server's ticks come in they're played
+// on the confirmed game, and the guess is made again from there.
+//
+// Turns are sent for the tick the client is showing, so they count where
+// they were seen to. One that reaches the server after that tick has been
+// played counts on the next one, and the client leads by that much more from
+// then on.
+
+use crate::game::{Game, Input, MoveDirection};
+
+// How many ticks ahead the client can get, so a bad connection means a laggy
+// game rather than one that's all guessing.
+pub const MAX_LEAD: u32 = 10;
+
+pub struct Prediction {
+    // The snake steered from here.
+    local: usize,
+    // The game as the server played it, up to the last tick it sent.
+    pub confirmed: Game,
+    // Turns sent but not played by the server yet, with the tick each was
+    // sent for.
+    pending: std::collections::VecDeque<(u32, MoveDirection)>,
+    lead: u32,
+}
+
+impl Prediction {
+    pub fn new(game: Game, local: usize) -> Self {
+        Prediction {
+            local,
+            confirmed: game,
+            pending: std::collections::VecDeque::new(),
+            lead: 2,
+        }
+    }
+
+    pub fn lead(&self) -> u32 {
+        self.lead
+    }
+
+    // A turn pressed now, which is sent for the tick this returns.
+    pub fn press(&mut self, direction: MoveDirection) -> u32 {
+        let tick = self.confirmed.tick + self.lead;
+        self.pending.push_back((tick, direction));
+        tick
+    }
+
+    // The server played `turns` on tick `tick`, in that order. Returns
+    // whether that ended the game.
+    pub fn confirm(&mut self, tick: u32, turns: &[(usize, MoveDirection)]) -> bool {
+        for &(snake, direction) in turns {
+            // Someone else's server could send anything.
+            if snake >= self.confirmed.players.len() {
+                continue;
+            }
+            // The server plays a snake's turns in the order they were sent.
+            if snake == self.local {
+                if let Some((sent_for, _)) = self.pending.pop_front() {
+                    let late = tick.saturating_sub(sent_for);
+                    self.lead = (self.lead + late).min(MAX_LEAD);
+                }
+            }
+            self.confirmed.handle_input(snake, Input::Turn(direction));
+        }
+        self.confirmed.tick()
+    }
+
+    // The game as it probably is by the time turns pressed now get there.
+    pub fn predicted(&self) -> Game {
+        let mut game = self.confirmed.clone();
+        let mut pending = self.pending.iter().peekable();
+        for _ in 0..self.lead {
+            while let Some((_, direction)) = pending.next_if(|(tick, _)| *tick <= game.tick) {
+                game.handle_input(self.local, Input::Turn(*direction));
+            }
+            if game.tick() {
+                return game;
+            }
+        }
+        // Turns for the tick that's next show which way the snake is going.
+        let tick = game.tick;
+        for (_, direction) in pending.filter(|(sent_for, _)| *sent_for <= tick) {
+            game.handle_input(self.local, Input::Turn(*direction));
+        }
+        game
+    }
+}
@@ -0,0 +1,152 @@
+// Where games get their random numbers from. Games are seeded so replays play
+// out the same, but anything can be plugged in when one is made, see
+// `Game::with_rng`: numbers straight from the operating system, or a script of
+// them to set up exactly what a test needs.
+//
+// Everything random in a game comes from the one it's made with, or from a
+// stream of it, see `Game::stream`, so a seed is the same game everywhere.
+// That holds across operating systems and architectures as long as nothing
+// here goes through `usize` or floats, whose sizes and rounding differ.
+
+use rand::{Rng as _, SeedableRng, TryRngCore};
+
+pub trait Rng: Send {
+    // A number from 0 up to but not including `n`, which is more than 0.
+    fn below(&mut self, n: u32) -> u32;
+
+    // Another source, for numbers that shouldn't follow from these.
+    fn split(&self) -> Box<dyn Rng>;
+
+    // Numbered sources of their own, none of them the same as this or each
+    // other, for anything random that isn't the game's rules.
+    fn stream(&self, n: u64) -> Box<dyn Rng>;
+
+    fn clone_box(&self) -> Box<dyn Rng>;
+
+    // How to pick up where this is, as a seed and how far into its stream,
+    // for saving games. None for anything that can't be started again.
+    fn state(&self) -> Option<(u64, u64)> {
+        None
+    }
+}
+
+impl Clone for Box<dyn Rng> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+// Different every time, for anything that doesn't have to be played back.
+#[derive(Copy, Clone, Default)]
+pub struct Entropy;
+
+impl Rng for Entropy {
+    fn below(&mut self, n: u32) -> u32 {
+        rand::rngs::OsRng.unwrap_err().random_range(0..n)
+    }
+
+    fn split(&self) -> Box<dyn Rng> {
+        Box::new(Entropy)
+    }
+
+    fn stream(&self, _n: u64) -> Box<dyn Rng> {
+        Box::new(Entropy)
+    }
+
+    fn clone_box(&self) -> Box<dyn Rng> {
+        Box::new(*self)
+    }
+}
+
+// A ChaCha stream from a seed, the same numbers on every computer. The
+// 12-round one is what `rand`'s StdRng is, which games used before, so old
+// replays still play back the same.
+#[derive(Clone)]
+pub struct Seeded {
+    seed: u64,
+    rng: rand_chacha::ChaCha12Rng,
+}
+
+impl Seeded {
+    pub fn new(seed: u64) -> Self {
+        Seeded {
+            seed,
+            rng: rand_chacha::ChaCha12Rng::seed_from_u64(seed),
+        }
+    }
+
+    // The stream from `seed`, `position` words in, as `state` gave it.
+    pub fn resume(seed: u64, position: u64) -> Self {
+        let mut seeded = Seeded::new(seed);
+        seeded.rng.set_word_pos(position.into());
+        seeded
+    }
+}
+
+impl Rng for Seeded {
+    fn below(&mut self, n: u32) -> u32 {
+        self.rng.random_range(0..n)
+    }
+
+    // Seeded with every bit of the seed flipped.
+    fn split(&self) -> Box<dyn Rng> {
+        Box::new(Seeded::new(!self.seed))
+    }
+
+    // ChaCha streams of the same seed, after the one games play from.
+    fn stream(&self, n: u64) -> Box<dyn Rng> {
+        let mut seeded = Seeded::new(self.seed);
+        seeded.rng.set_stream(n.wrapping_add(1));
+        Box::new(seeded)
+    }
+
+    fn clone_box(&self) -> Box<dyn Rng> {
+        Box::new(self.clone())
+    }
+
+    // Streams are 2^68 words long, which no game gets near the end of. Only
+    // the first is saved, which is the one games play from.
+    fn state(&self) -> Option<(u64, u64)> {
+        (self.rng.get_stream() == 0).then(|| (self.seed, self.rng.get_word_pos() as u64))
+    }
+}
+
+// Gives out `numbers` in order, over and over, each one wrapped to fit below
+// what was asked for. With no numbers it's always 0.
+#[derive(Clone)]
+pub struct Scripted {
+    numbers: Vec<u32>,
+    next: usize,
+}
+
+impl Scripted {
+    pub fn new(numbers: &[u32]) -> Self {
+        Scripted {
+            numbers: numbers.to_vec(),
+            next: 0,
+        }
+    }
+}
+
+impl Rng for Scripted {
+    fn below(&mut self, n: u32) -> u32 {
+        let Some(number) = self.numbers.get(self.next % self.numbers.len().max(1)) else {
+            return 0;
+        };
+        self.next += 1;
+        number % n
+    }
+
+    // The same script from the start.
+    fn split(&self) -> Box<dyn Rng> {
+        Box::new(Scripted::new(&self.numbers))
+    }
+
+    fn stream(&self, _n: u64) -> Box<dyn Rng> {
+        self.split()
+    }
+
+    fn clone_box(&self) -> Box<dyn Rng> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,100 @@
+// Elo ratings from versus games against the computer and over the network,
+// one for every profile (see `profile` in the config), in ratings.json in
+// the data directory. Everyone starts at START, and a game moves a rating by
+// up to K, more for beating someone rated higher. The computer is rated by
+// its difficulty, and network players tell each other theirs in the lobby.
+// Games on a `snake server` don't count, there's nobody to be rated against.
+
+use serde::{Deserialize, Serialize};
+
+pub const START: u32 = 1200;
+const K: f64 = 32.0;
+const DEFAULT_PROFILE: &str = "default";
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+impl Outcome {
+    // How the same game went for the other side.
+    pub fn other(self) -> Self {
+        match self {
+            Outcome::Win => Outcome::Loss,
+            Outcome::Loss => Outcome::Win,
+            Outcome::Draw => Outcome::Draw,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rating {
+    pub rating: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Rating {
+            rating: START,
+            wins: 0,
+            losses: 0,
+            draws: 0,
+        }
+    }
+}
+
+pub fn profile(config: &crate::config::Config) -> &str {
+    config.profile.as_deref().unwrap_or(DEFAULT_PROFILE)
+}
+
+// Every profile's, none when there's no file yet.
+pub fn all() -> std::collections::BTreeMap<String, Rating> {
+    // Ratings that can't be read start over rather than stopping the game.
+    std::fs::read_to_string(crate::paths::ratings_file())
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub fn of(config: &crate::config::Config) -> Rating {
+    all().get(profile(config)).copied().unwrap_or_default()
+}
+
+// How much a game against someone rated `opponent` moves `rating`.
+pub fn change(rating: u32, opponent: u32, outcome: Outcome) -> i32 {
+    let expected = 1.0 / (1.0 + 10f64.powf((f64::from(opponent) - f64::from(rating)) / 400.0));
+    let score = match outcome {
+        Outcome::Win => 1.0,
+        Outcome::Loss => 0.0,
+        Outcome::Draw => 0.5,
+    };
+    (K * (score - expected)).round() as i32
+}
+
+// Counts a game against someone rated `opponent`, returning the profile's
+// rating after it and how much it changed.
+pub fn record(config: &crate::config::Config, opponent: u32, outcome: Outcome) -> (u32, i32) {
+    let mut ratings = all();
+    let rating = ratings.entry(profile(config).to_string()).or_default();
+    let change = change(rating.rating, opponent, outcome);
+
+    rating.rating = rating.rating.saturating_add_signed(change);
+    match outcome {
+        Outcome::Win => rating.wins += 1,
+        Outcome::Loss => rating.losses += 1,
+        Outcome::Draw => rating.draws += 1,
+    }
+    let after = rating.rating;
+
+    // Failing to save was already reported when creating the data directory.
+    if let Ok(contents) = serde_json::to_string_pretty(&ratings) {
+        let _ = std::fs::write(crate::paths::ratings_file(), contents + "\n");
+    }
+    (after, change)
+}
@@ -0,0 +1,128 @@
+// What a game calls for after every tick besides being drawn: the bell or a
+// flash when a snake eats or dies, and slow motion after a near miss. Only
+// worked out here; `effects` in the binary rings, flashes and waits, so this
+// can be tested without a terminal.
+
+use crate::game::{Coordinate, Event, Game};
+use serde::Deserialize;
+
+// Slow motion makes ticks this many times longer, for this long.
+pub const SLOW_MOTION_SCALE: u32 = 3;
+const SLOW_MOTION_DURATION: std::time::Duration = std::time::Duration::from_millis(600);
+
+#[derive(Copy, Clone, PartialEq, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Feedback {
+    // The terminal bell.
+    #[default]
+    Bell,
+    // The whole screen inverted for a moment, for terminals without sound or
+    // people without a liking for beeps.
+    Flash,
+    Off,
+}
+
+pub struct Reactions {
+    feedback: Feedback,
+    reduced_motion: bool,
+    slow_motion: bool,
+    slow_until: Option<std::time::Instant>,
+    // Where each snake's head was after the last tick if it was about to
+    // crash then.
+    danger: Vec<Option<Coordinate>>,
+    // The usual time between moves, and what it is now.
+    usual: std::time::Duration,
+    move_duration: std::time::Duration,
+}
+
+impl Reactions {
+    pub fn new(
+        feedback: Feedback,
+        reduced_motion: bool,
+        slow_motion: bool,
+        usual: std::time::Duration,
+    ) -> Self {
+        Reactions {
+            feedback,
+            reduced_motion,
+            slow_motion,
+            slow_until: None,
+            danger: Vec::new(),
+            usual,
+            move_duration: usual,
+        }
+    }
+
+    pub fn set_move_duration(&mut self, move_duration: std::time::Duration) {
+        self.move_duration = move_duration;
+    }
+
+    // The tick that just happened, and the bell or flash it calls for.
+    pub fn handle(&mut self, game: &Game) -> Option<Feedback> {
+        // Slowing down is the kind of thing reduced motion is for.
+        if self.slow_motion && !self.reduced_motion {
+            self.near_miss(game);
+        }
+
+        let noticeable = game.events.iter().any(|e| {
+            matches!(
+                e,
+                Event::FoodEaten { .. }
+                    | Event::FormationFoodEaten { .. }
+                    | Event::FrenzyFoodEaten { .. }
+                    | Event::Died { .. }
+                    | Event::LostLife { .. }
+            )
+        });
+        match self.feedback {
+            _ if !noticeable => None,
+            Feedback::Flash if self.reduced_motion => None,
+            Feedback::Off => None,
+            feedback => Some(feedback),
+        }
+    }
+
+    // Starts slow motion when a snake that was about to crash last tick has
+    // moved on without crashing, if the game is going faster than usual.
+    fn near_miss(&mut self, game: &Game) {
+        let crashed = |index: usize| {
+            game.events.iter().any(|e| {
+                matches!(e, Event::Died { player } | Event::LostLife { player } | Event::Bumped { player } if *player == index)
+            })
+        };
+        let near_miss = game.players.iter().enumerate().any(|(index, p)| {
+            self.danger
+                .get(index)
+                .copied()
+                .flatten()
+                .is_some_and(|head| p.alive && p.head() != head && !crashed(index))
+        });
+        if near_miss && self.move_duration < self.usual {
+            self.slow_until = Some(std::time::Instant::now() + SLOW_MOTION_DURATION);
+        }
+
+        self.danger = (0..game.players.len())
+            .map(|index| {
+                let player = &game.players[index];
+                (player.alive && game.crash_ahead(index)).then(|| player.head())
+            })
+            .collect();
+    }
+
+    // How many times longer than usual the next tick should take.
+    pub fn time_scale(&mut self) -> u32 {
+        match self.slow_until {
+            Some(until) if until > std::time::Instant::now() => SLOW_MOTION_SCALE,
+            _ => {
+                self.slow_until = None;
+                1
+            }
+        }
+    }
+
+    // Forgets the game that's over.
+    pub fn finish(&mut self) {
+        self.slow_until = None;
+        self.danger.clear();
+    }
+}
@@ -0,0 +1,645 @@
+//! Replay files store everything needed to re-run a game: the header pins down
+//! the starting state, the stream records which input arrived on which tick.
+//!
+//! All integers are little endian.
+//!
+//! Header (24 bytes, 22 before version 18, 21 before version 4):
+//!
+//! | offset | size | field                                   |
+//! |--------|------|-----------------------------------------|
+//! | 0      | 4    | magic, `SNKR`                           |
+//! | 4      | 2    | format version                          |
+//! | 6      | 8    | RNG seed                                |
+//! | 14     | 1    | game mode id                            |
+//! | 15     | 2    | board width                             |
+//! | 17     | 2    | board height                            |
+//! | 19     | 1    | lives, 0 is the same as 1               |
+//! | 20     | 1    | flags: 1 bumper, 2 diagonal, 4 wind     |
+//! | 21     | 1    | grace ticks before a crash              |
+//! | 22     | 2    | milliseconds between moves at the start |
+//!
+//! Followed by 5 byte records until the end of the file:
+//!
+//! | offset | size | field                                   |
+//! |--------|------|-----------------------------------------|
+//! | 0      | 4    | tick the record applies to              |
+//! | 4      | 1    | record code                             |
+//!
+//! Record codes: `0`-`3` turn up/down/left/right, `4` elongate, `5` and `6`
+//! faster and slower, `7` swap snakes, `8`-`11` turn up left/up right/down
+//! left/down right, `255` end of the game. Inputs are applied in file order
+//! before the snake moves on their tick, speed changes right after the move
+//! before, for the wait until the move on their tick. The end record is always
+//! last and its tick is the number of moves the game lasted. From version 7
+//! it's followed by an 8 byte checksum, FNV-1a over everything before it and
+//! the state the game ended in: the tick, then every player's score, lives,
+//! whether it's alive, its length as 4 bytes and its segments head first, then
+//! every food's location. Playing the replay back has to end in the same
+//! state.
+//!
+//! From version 12 the checksum is followed by checkpoints of how the game
+//! went, one every 25 ticks and one for the tick it ended on, for telling
+//! where a replay that doesn't end the same stopped playing back the same: a
+//! 4 byte count, then for every checkpoint
+//!
+//! | offset | size | field                                   |
+//! |--------|------|-----------------------------------------|
+//! | 0      | 4    | tick                                    |
+//! | 4      | 8    | FNV-1a of the state, as for the checksum|
+//! | 12     | 1    | number of players                       |
+//! | 13     | 8    | per player, its score and its length    |
+//!
+//! Replays are saved gzip compressed, since the records of a long game are
+//! mostly the same few bytes over and over. Readers look for the gzip magic
+//! bytes, `1f 8b`, and decompress first, so files saved before replays were
+//! compressed load as they always did.
+//!
+//! Readers reject files with a newer format version than they know about
+//! instead of guessing, so old builds fail with a clear message. Newer builds
+//! keep decoding every older version.
+//!
+//! Version 2 is laid out the same as version 1, but food is placed on a random
+//! free cell instead of retrying random cells until one is free. Version 1
+//! games are played back the old way. Version 3 uses what used to be two
+//! reserved header bytes, which older versions always wrote as zero, for
+//! lives and bumper mode. Version 4 adds a byte to the header for how long
+//! snakes wait in front of a crash, older versions never wait. Version 5 has
+//! the same layout, but snakes grow by keeping their tail for a move instead
+//! of getting a cell stuck on behind it, which earlier versions are played back
+//! with. Version 6 adds the speed records, before which games kept to one
+//! speed. Version 7 adds the checksum, version 8 twins mode and the swap
+//! record. Version 9 has the same layout, but food comes in kinds worth 1, 2
+//! or 3 points, where it was all worth 1 before. Version 10 adds formations
+//! of food that turn up now and then, and version 11 feasts, both without
+//! changing the layout. Version 12 adds the checkpoints. Version 13 makes the
+//! bumper byte flags, where it was only ever 1 or 0 before, for diagonal
+//! moves and the diagonal turn records. Version 14 has the same layout, but
+//! the letters of a word turn up now and then, version 15 frenzies of food
+//! worth double, and version 16 the other random events. Version 17 adds the
+//! wind flag. Version 18 adds how long moves took at the start, older
+//! versions play back at whatever speed the viewer plays at.
+
+use crate::game::{FoodPlacement, Game, Growth, Input, Mode, MoveDirection};
+use crate::level::Level;
+use std::io::{Read, Write};
+
+const MAGIC: &[u8; 4] = b"SNKR";
+const GZIP_MAGIC: &[u8; 2] = &[0x1f, 0x8b];
+pub const FORMAT_VERSION: u16 = 18;
+const CHECKPOINT_TICKS: u32 = 25;
+
+const CODE_ELONGATE: u8 = 4;
+const CODE_FASTER: u8 = 5;
+const CODE_SLOWER: u8 = 6;
+const CODE_SWAP: u8 = 7;
+const CODE_DIAGONALS: [(u8, MoveDirection); 4] = [
+    (8, MoveDirection::UpLeft),
+    (9, MoveDirection::UpRight),
+    (10, MoveDirection::DownLeft),
+    (11, MoveDirection::DownRight),
+];
+const FLAG_BUMPER: u8 = 1;
+const FLAG_DIAGONAL: u8 = 2;
+const FLAG_WIND: u8 = 4;
+const CODE_END: u8 = 255;
+
+pub enum ReplayError {
+    Io(std::io::Error),
+    NotAReplay,
+    TooNew(u16),
+    UnknownMode(u8),
+    UnknownRecord(u8),
+    Truncated,
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "could not read replay: {}", e),
+            ReplayError::NotAReplay => write!(f, "not a snake replay file"),
+            ReplayError::TooNew(version) => write!(
+                f,
+                "replay uses format version {}, but this build only supports up to version {}",
+                version, FORMAT_VERSION
+            ),
+            ReplayError::UnknownMode(id) => write!(f, "replay uses unknown game mode {}", id),
+            ReplayError::UnknownRecord(code) => {
+                write!(f, "replay contains unknown record type {}", code)
+            }
+            ReplayError::Truncated => write!(f, "replay is truncated"),
+        }
+    }
+}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(e: std::io::Error) -> Self {
+        ReplayError::Io(e)
+    }
+}
+
+// The player changing how long the game waits between moves. Doesn't change
+// how the game plays out, only how fast.
+#[derive(Copy, Clone, PartialEq)]
+pub enum SpeedChange {
+    Faster,
+    Slower,
+}
+
+// How a game was going on a tick, see the format above.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Checkpoint {
+    pub tick: u32,
+    pub hash: u64,
+    pub scores: Vec<u32>,
+    pub lengths: Vec<u32>,
+}
+
+impl Checkpoint {
+    pub fn of(game: &Game) -> Self {
+        Checkpoint {
+            tick: game.tick,
+            hash: fnv(&state(game)),
+            scores: game.players.iter().map(|p| p.score).collect(),
+            lengths: game
+                .players
+                .iter()
+                .map(|p| p.segments.len() as u32)
+                .collect(),
+        }
+    }
+}
+
+// What playing a replay back says about it.
+pub enum Verification {
+    // It ended the way it was recorded to.
+    Verified(Game),
+    // Replays before version 7 don't say how they ended.
+    NoChecksum(Game),
+    // It played out differently, first seen by the tick of `played`. Without
+    // checkpoints there's only the checksum to go by, and no `recorded`.
+    Diverged {
+        recorded: Option<Checkpoint>,
+        played: Checkpoint,
+    },
+}
+
+pub struct Replay {
+    // Written back as it was read, since it decides how the game plays out.
+    pub version: u16,
+    pub mode: Mode,
+    pub seed: u64,
+    pub board_size: (u16, u16),
+    pub lives: u8,
+    pub bumper: bool,
+    // Always off before version 13.
+    pub diagonal: bool,
+    // Always off before version 17.
+    pub wind: bool,
+    pub grace: u8,
+    // Milliseconds between moves before any speed change, unknown before
+    // version 18.
+    pub tick_ms: Option<u16>,
+    pub inputs: Vec<(u32, Input)>,
+    pub speed_changes: Vec<(u32, SpeedChange)>,
+    pub length: u32,
+    // Only older replays don't have one, see `seal`.
+    pub checksum: Option<u64>,
+    // In tick order, the last one where the game ended. Empty before version
+    // 12.
+    pub checkpoints: Vec<Checkpoint>,
+    // Whether the game ever let something else steer. Not saved, the inputs
+    // are the same either way.
+    pub assisted: bool,
+}
+
+impl Replay {
+    pub fn new(game: &Game) -> Self {
+        Replay {
+            version: FORMAT_VERSION,
+            mode: game.mode,
+            seed: game.seed,
+            board_size: game.board_size,
+            lives: game.players[0].lives.min(u8::MAX as u32) as u8,
+            bumper: game.bumper,
+            diagonal: game.diagonal,
+            wind: game.wind.is_some(),
+            grace: game.grace.min(u8::MAX as u32) as u8,
+            tick_ms: None,
+            inputs: Vec::new(),
+            speed_changes: Vec::new(),
+            length: 0,
+            checksum: None,
+            checkpoints: Vec::new(),
+            assisted: false,
+        }
+    }
+
+    pub fn record(&mut self, tick: u32, input: Input) {
+        self.inputs.push((tick, input));
+    }
+
+    pub fn record_speed_change(&mut self, tick: u32, change: SpeedChange) {
+        self.speed_changes.push((tick, change));
+    }
+
+    pub fn game(&self) -> Game {
+        let food_placement = match self.version {
+            1 => FoodPlacement::Retry,
+            _ => FoodPlacement::FreeCells,
+        };
+        let mut game = Game::with_food_placement(
+            self.mode,
+            self.seed,
+            Level::empty(self.board_size),
+            food_placement,
+        );
+        game.set_lives(self.lives as u32);
+        game.bumper = self.bumper;
+        game.diagonal = self.diagonal;
+        game.set_wind(self.wind);
+        game.grace = self.grace as u32;
+        if self.version < 5 {
+            game.growth = Growth::BehindTail;
+        }
+        game.set_food_variants(self.version >= 9);
+        game.formations = self.version >= 10;
+        game.feasts = self.version >= 11;
+        game.letter_rounds = self.version >= 14;
+        game.random_events = match self.version {
+            ..15 => Vec::new(),
+            15 => vec![0],
+            _ => (0..crate::events::ALL.len()).collect(),
+        };
+        game
+    }
+
+    // All inputs that have to be applied before the move on `tick`.
+    pub fn inputs_at(&self, tick: u32) -> impl Iterator<Item = Input> + '_ {
+        self.inputs
+            .iter()
+            .filter(move |(t, _)| *t == tick)
+            .map(|(_, input)| *input)
+    }
+
+    // All speed changes made once the game got to `tick`.
+    pub fn speed_changes_at(&self, tick: u32) -> impl Iterator<Item = SpeedChange> + '_ {
+        self.speed_changes
+            .iter()
+            .filter(move |(t, _)| *t == tick)
+            .map(|(_, change)| *change)
+    }
+
+    // Called after every tick, keeps a checkpoint of the ones that get one.
+    // A game that was rewound takes them again from where it went back to.
+    pub fn checkpoint(&mut self, game: &Game) {
+        if !game.tick.is_multiple_of(CHECKPOINT_TICKS) {
+            return;
+        }
+        self.checkpoints.retain(|c| c.tick < game.tick);
+        self.checkpoints.push(Checkpoint::of(game));
+    }
+
+    // Ends the replay with `game`, the state the game ended in, and a checksum
+    // of it so an edited replay can be told from a real one by playing it back.
+    pub fn seal(&mut self, game: &Game) {
+        self.length = game.tick;
+        self.checksum = Some(self.checksum_of(game));
+        self.checkpoints.retain(|c| c.tick < game.tick);
+        self.checkpoints.push(Checkpoint::of(game));
+    }
+
+    pub fn checksum_of(&self, game: &Game) -> u64 {
+        let mut data = Vec::new();
+        self.write_body(&mut data).unwrap();
+        data.extend(state(game));
+        fnv(&data)
+    }
+
+    // Whether `game`, the replay played back, ended the way it did when it
+    // was recorded. Replays without a checksum can't tell.
+    pub fn ended_as_recorded(&self, game: &Game) -> Option<bool> {
+        self.checksum
+            .map(|checksum| checksum == self.checksum_of(game))
+    }
+
+    // Plays the replay back, stopping at the first checkpoint it doesn't
+    // match, for `snake verify`.
+    pub fn verify(&self) -> Verification {
+        let mut game = self.game();
+        let mut checkpoints = self.checkpoints.iter().peekable();
+        let mut over = false;
+        loop {
+            while let Some(recorded) = checkpoints.next_if(|c| c.tick <= game.tick || over) {
+                let played = Checkpoint::of(&game);
+                if *recorded != played {
+                    return Verification::Diverged {
+                        recorded: Some(recorded.clone()),
+                        played,
+                    };
+                }
+            }
+            if over || game.tick >= self.length {
+                break;
+            }
+            for input in self.inputs_at(game.tick) {
+                game.handle_input(0, input);
+            }
+            over = game.tick();
+        }
+
+        match self.ended_as_recorded(&game) {
+            Some(true) => Verification::Verified(game),
+            None => Verification::NoChecksum(game),
+            Some(false) => Verification::Diverged {
+                recorded: None,
+                played: Checkpoint::of(&game),
+            },
+        }
+    }
+
+    // Plays the whole replay back, without any rules.
+    pub fn simulate(&self) -> Game {
+        let mut game = self.game();
+        while game.tick < self.length {
+            for input in self.inputs_at(game.tick) {
+                game.handle_input(0, input);
+            }
+            if game.tick() {
+                break;
+            }
+        }
+        game
+    }
+
+    pub fn write_to(&self, w: &mut impl Write) -> Result<(), std::io::Error> {
+        self.write_body(w)?;
+        if self.version >= 7 {
+            w.write_all(&self.checksum.unwrap_or(0).to_le_bytes())?;
+        }
+        if self.version >= 12 {
+            w.write_all(&(self.checkpoints.len() as u32).to_le_bytes())?;
+            for checkpoint in &self.checkpoints {
+                w.write_all(&checkpoint.tick.to_le_bytes())?;
+                w.write_all(&checkpoint.hash.to_le_bytes())?;
+                w.write_all(&[checkpoint.scores.len() as u8])?;
+                for (score, length) in checkpoint.scores.iter().zip(&checkpoint.lengths) {
+                    w.write_all(&score.to_le_bytes())?;
+                    w.write_all(&length.to_le_bytes())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Everything but the checksum.
+    fn write_body(&self, w: &mut impl Write) -> Result<(), std::io::Error> {
+        w.write_all(MAGIC)?;
+        w.write_all(&self.version.to_le_bytes())?;
+        w.write_all(&self.seed.to_le_bytes())?;
+        w.write_all(&[self.mode.id()])?;
+        w.write_all(&self.board_size.0.to_le_bytes())?;
+        w.write_all(&self.board_size.1.to_le_bytes())?;
+        let flags = (self.bumper as u8 * FLAG_BUMPER)
+            | (self.diagonal as u8 * FLAG_DIAGONAL)
+            | (self.wind as u8 * FLAG_WIND);
+        w.write_all(&[self.lives, flags])?;
+        if self.version >= 4 {
+            w.write_all(&[self.grace])?;
+        }
+        if self.version >= 18 {
+            w.write_all(&self.tick_ms.unwrap_or(0).to_le_bytes())?;
+        }
+
+        let mut records: Vec<(u32, u8)> = self
+            .inputs
+            .iter()
+            .map(|(tick, input)| (*tick, input_code(input)))
+            .chain(self.speed_changes.iter().map(|(tick, change)| {
+                let code = match change {
+                    SpeedChange::Faster => CODE_FASTER,
+                    SpeedChange::Slower => CODE_SLOWER,
+                };
+                (*tick, code)
+            }))
+            .collect();
+        records.sort_by_key(|(tick, _)| *tick);
+        for (tick, code) in records {
+            w.write_all(&tick.to_le_bytes())?;
+            w.write_all(&[code])?;
+        }
+        w.write_all(&self.length.to_le_bytes())?;
+        w.write_all(&[CODE_END])
+    }
+
+    pub fn read_from(r: &mut impl Read) -> Result<Self, ReplayError> {
+        let mut data = Vec::new();
+        r.read_to_end(&mut data)?;
+        if data.starts_with(GZIP_MAGIC) {
+            let mut decompressed = Vec::new();
+            flate2::read::GzDecoder::new(&data[..])
+                .read_to_end(&mut decompressed)
+                .map_err(|_| ReplayError::NotAReplay)?;
+            data = decompressed;
+        }
+
+        if data.len() < 6 || &data[0..4] != MAGIC {
+            return Err(ReplayError::NotAReplay);
+        }
+
+        match u16::from_le_bytes([data[4], data[5]]) {
+            version @ (1..=18) => decode_v1(&data, version),
+            version if version > FORMAT_VERSION => Err(ReplayError::TooNew(version)),
+            _ => Err(ReplayError::NotAReplay),
+        }
+    }
+
+    // FNV-1a over the encoded replay, enough to tell replays apart.
+    pub fn hash(&self) -> String {
+        let mut data = Vec::new();
+        self.write_to(&mut data).unwrap();
+
+        format!("{:016x}", fnv(&data))
+    }
+
+    pub fn save(&self, path: &std::path::Path) -> Result<(), std::io::Error> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let mut gzip = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        self.write_to(&mut gzip)?;
+        gzip.finish()?.flush()
+    }
+
+    pub fn load(path: &std::path::Path) -> Result<Self, ReplayError> {
+        Replay::read_from(&mut std::fs::File::open(path)?)
+    }
+}
+
+// Later versions only changed how the game plays, gave the reserved bytes a
+// meaning and added to the end of the header, not the layout.
+fn decode_v1(data: &[u8], version: u16) -> Result<Replay, ReplayError> {
+    let header_size = match version {
+        18.. => 24,
+        4.. => 22,
+        _ => 21,
+    };
+    let header = data.get(0..header_size).ok_or(ReplayError::Truncated)?;
+    let u16_at = |i: usize| u16::from_le_bytes([header[i], header[i + 1]]);
+
+    let seed = u64::from_le_bytes(header[6..14].try_into().unwrap());
+    let mode = Mode::from_id(header[14]).ok_or(ReplayError::UnknownMode(header[14]))?;
+    let board_size = (u16_at(15), u16_at(17));
+    let (lives, bumper, diagonal) = match version {
+        13.. => (
+            header[19].max(1),
+            header[20] & FLAG_BUMPER != 0,
+            header[20] & FLAG_DIAGONAL != 0,
+        ),
+        _ => (header[19].max(1), header[20] == 1, false),
+    };
+    let wind = version >= 17 && header[20] & FLAG_WIND != 0;
+    let grace = header.get(21).copied().unwrap_or(0);
+    let tick_ms = match version {
+        18.. => Some(u16_at(22)).filter(|&ms| ms != 0),
+        _ => None,
+    };
+
+    let mut inputs = Vec::new();
+    let mut speed_changes = Vec::new();
+    for (index, record) in data[header_size..].chunks(5).enumerate() {
+        if record.len() < 5 {
+            return Err(ReplayError::Truncated);
+        }
+
+        let tick = u32::from_le_bytes(record[0..4].try_into().unwrap());
+        let input = match record[4] {
+            0 => Input::Turn(MoveDirection::Up),
+            1 => Input::Turn(MoveDirection::Down),
+            2 => Input::Turn(MoveDirection::Left),
+            3 => Input::Turn(MoveDirection::Right),
+            CODE_ELONGATE => Input::Elongate,
+            CODE_SWAP if version >= 8 => Input::Swap,
+            code @ 8..=11 if version >= 13 => {
+                let (_, direction) = CODE_DIAGONALS[usize::from(code - 8)];
+                Input::Turn(direction)
+            }
+            code @ (CODE_FASTER | CODE_SLOWER) if version >= 6 => {
+                let change = match code {
+                    CODE_FASTER => SpeedChange::Faster,
+                    _ => SpeedChange::Slower,
+                };
+                speed_changes.push((tick, change));
+                continue;
+            }
+            CODE_END => {
+                let end = header_size + (index + 1) * 5;
+                let checksum = match version {
+                    7.. => {
+                        let bytes = data.get(end..end + 8).ok_or(ReplayError::Truncated)?;
+                        Some(u64::from_le_bytes(bytes.try_into().unwrap()))
+                    }
+                    _ => None,
+                };
+                let checkpoints = match version {
+                    12.. => decode_checkpoints(data.get(end + 8..).unwrap_or_default())?,
+                    _ => Vec::new(),
+                };
+                return Ok(Replay {
+                    version,
+                    mode,
+                    seed,
+                    board_size,
+                    lives,
+                    bumper,
+                    diagonal,
+                    wind,
+                    grace,
+                    tick_ms,
+                    inputs,
+                    speed_changes,
+                    length: tick,
+                    checksum,
+                    checkpoints,
+                    assisted: false,
+                });
+            }
+            code => return Err(ReplayError::UnknownRecord(code)),
+        };
+        inputs.push((tick, input));
+    }
+
+    Err(ReplayError::Truncated)
+}
+
+fn decode_checkpoints(data: &[u8]) -> Result<Vec<Checkpoint>, ReplayError> {
+    let mut bytes = data.iter().copied();
+    let mut take = |n: usize| -> Result<Vec<u8>, ReplayError> {
+        let taken: Vec<u8> = bytes.by_ref().take(n).collect();
+        match taken.len() == n {
+            true => Ok(taken),
+            false => Err(ReplayError::Truncated),
+        }
+    };
+    let u32_of = |b: Vec<u8>| u32::from_le_bytes(b.try_into().unwrap());
+
+    let count = u32_of(take(4)?);
+    let mut checkpoints = Vec::new();
+    for _ in 0..count {
+        let tick = u32_of(take(4)?);
+        let hash = u64::from_le_bytes(take(8)?.try_into().unwrap());
+        let (mut scores, mut lengths) = (Vec::new(), Vec::new());
+        for _ in 0..take(1)?[0] {
+            scores.push(u32_of(take(4)?));
+            lengths.push(u32_of(take(4)?));
+        }
+        checkpoints.push(Checkpoint {
+            tick,
+            hash,
+            scores,
+            lengths,
+        });
+    }
+    Ok(checkpoints)
+}
+
+// What the checksum and checkpoints go by: the tick, then every player's
+// score, lives, whether it's alive, its length and its segments, then every
+// food's location.
+fn state(game: &Game) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&game.tick.to_le_bytes());
+    for player in &game.players {
+        data.extend_from_slice(&player.score.to_le_bytes());
+        data.extend_from_slice(&player.lives.to_le_bytes());
+        data.push(player.alive as u8);
+        data.extend_from_slice(&(player.segments.len() as u32).to_le_bytes());
+        for segment in &player.segments {
+            data.extend_from_slice(&segment.0.to_le_bytes());
+            data.extend_from_slice(&segment.1.to_le_bytes());
+        }
+    }
+    for food in &game.food {
+        data.extend_from_slice(&food.location.0.to_le_bytes());
+        data.extend_from_slice(&food.location.1.to_le_bytes());
+    }
+    data
+}
+
+fn fnv(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn input_code(input: &Input) -> u8 {
+    match input {
+        Input::Turn(MoveDirection::Up) => 0,
+        Input::Turn(MoveDirection::Down) => 1,
+        Input::Turn(MoveDirection::Left) => 2,
+        Input::Turn(MoveDirection::Right) => 3,
+        Input::Turn(direction) => CODE_DIAGONALS
+            .iter()
+            .find(|(_, d)| d == direction)
+            .map_or(CODE_END, |(code, _)| *code),
+        Input::Elongate => CODE_ELONGATE,
+        Input::Swap => CODE_SWAP,
+    }
+}
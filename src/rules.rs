@@ -0,0 +1,28 @@
+// Custom rules layered over the usual ones, by scripts or plugins. They only
+// see the game between ticks, through the events the last tick produced.
+
+use crate::game::Game;
+
+pub trait Rules {
+    // Called after every tick, including the one that ended the game. Returns
+    // true to end the game early.
+    fn after_tick(&mut self, game: &mut Game) -> Result<bool, String>;
+}
+
+// The rules set up in the config, if any. Loaded again for every game, so
+// changes to a script show up without restarting snake.
+pub fn load(config: &crate::config::Config) -> Result<Option<Box<dyn Rules>>, String> {
+    let Some(path) = &config.script else {
+        return Ok(None);
+    };
+
+    #[cfg(feature = "lua")]
+    return crate::script::Script::load(path)
+        .map(|script| Some(Box::new(script) as Box<dyn Rules>));
+
+    #[cfg(not(feature = "lua"))]
+    Err(format!(
+        "{}: this snake was built without the lua feature",
+        path.display()
+    ))
+}
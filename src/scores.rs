@@ -0,0 +1,174 @@
+// The best scores of every mode, a line each in the scores file:
+//
+//   <score> <name> <mode> <unix time> <replay hash>
+//
+// with - for a time or replay that isn't known. Lines from before there were
+// modes are only a score and a name, and count as classic. The replay of an
+// entry is the one in the replays directory named for its hash, see
+// `replay_file`.
+
+use crate::game::Mode;
+use serde::{Deserialize, Serialize};
+
+const MAX_ENTRIES: usize = 10;
+pub const NAME_MIN_LEN: usize = 3;
+pub const NAME_MAX_LEN: usize = 10;
+
+fn classic() -> Mode {
+    Mode::Classic
+}
+
+// Online leaderboards send the same, with whatever they know of the rest.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub name: String,
+    pub score: u32,
+    #[serde(default = "classic")]
+    pub mode: Mode,
+    // When it was set, in seconds since 1970.
+    #[serde(default)]
+    pub date: Option<u64>,
+    #[serde(default)]
+    pub replay_hash: Option<String>,
+}
+
+impl Entry {
+    // A score set now.
+    pub fn new(name: String, score: u32, mode: Mode, replay_hash: Option<String>) -> Self {
+        let date = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()
+            .map(|d| d.as_secs());
+        Entry {
+            name,
+            score,
+            mode,
+            date,
+            replay_hash,
+        }
+    }
+}
+
+pub struct HighScores {
+    // Best first, up to `MAX_ENTRIES` of every mode.
+    entries: Vec<Entry>,
+    path: std::path::PathBuf,
+}
+
+impl HighScores {
+    pub fn load() -> Self {
+        let path = crate::paths::scores_file();
+        let mut entries: Vec<Entry> = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents.lines().filter_map(parse_line).collect(),
+            Err(_) => Vec::new(),
+        };
+
+        // The sort is stable, so equal scores keep the order they were set in.
+        entries.sort_by_key(|e| std::cmp::Reverse(e.score));
+        let mut high_scores = HighScores { entries, path };
+        high_scores.truncate();
+        high_scores
+    }
+
+    pub fn save(&self) -> Result<(), std::io::Error> {
+        let mut contents = String::new();
+        for entry in &self.entries {
+            let date = entry.date.map_or("-".to_string(), |d| d.to_string());
+            contents.push_str(&format!(
+                "{} {} {} {} {}\n",
+                entry.score,
+                entry.name,
+                entry.mode.name(),
+                date,
+                entry.replay_hash.as_deref().unwrap_or("-")
+            ));
+        }
+
+        std::fs::write(&self.path, contents)
+    }
+
+    // Every mode's, best first.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn of(&self, mode: Mode) -> Vec<&Entry> {
+        self.entries.iter().filter(|e| e.mode == mode).collect()
+    }
+
+    pub fn qualifies(&self, mode: Mode, score: u32) -> bool {
+        if score == 0 {
+            return false;
+        }
+
+        match self.of(mode).get(MAX_ENTRIES - 1) {
+            Some(last) => score > last.score,
+            None => true,
+        }
+    }
+
+    // Returns the rank (zero based) the new entry ended up at among its
+    // mode's.
+    pub fn insert(&mut self, entry: Entry) -> usize {
+        let (mode, score) = (entry.mode, entry.score);
+        let rank = self
+            .of(mode)
+            .iter()
+            .position(|e| e.score < score)
+            .unwrap_or(self.of(mode).len());
+        let index = self
+            .entries
+            .iter()
+            .position(|e| e.score < score)
+            .unwrap_or(self.entries.len());
+
+        self.entries.insert(index, entry);
+        self.truncate();
+
+        rank
+    }
+
+    fn truncate(&mut self) {
+        let mut kept = std::collections::HashMap::new();
+        self.entries.retain(|e| {
+            let count = kept.entry(e.mode.id()).or_insert(0);
+            *count += 1;
+            *count <= MAX_ENTRIES
+        });
+    }
+}
+
+// Where the replay of an entry with `hash` is kept.
+pub fn replay_file(hash: &str) -> std::path::PathBuf {
+    crate::paths::replay_dir().join(format!("{}.replay", hash))
+}
+
+pub fn valid_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
+
+fn parse_line(line: &str) -> Option<Entry> {
+    let mut fields = line.split_whitespace();
+    let score = fields.next()?.parse().ok()?;
+    let name = fields.next()?;
+
+    if name.len() < NAME_MIN_LEN || name.len() > NAME_MAX_LEN || !name.chars().all(valid_name_char)
+    {
+        return None;
+    }
+
+    let mode = match fields.next() {
+        Some(mode) => Mode::from_name(mode)?,
+        None => Mode::Classic,
+    };
+    let date = fields.next().and_then(|d| d.parse().ok());
+    let replay_hash = fields.next().filter(|h| *h != "-").map(str::to_string);
+
+    Some(Entry {
+        name: name.to_string(),
+        score,
+        mode,
+        date,
+        replay_hash,
+    })
+}
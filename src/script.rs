@@ -0,0 +1,173 @@
+// Custom rules written in Lua, loaded from the `script` set in the config.
+// A script defines whichever of these hooks it needs as globals:
+//
+//   on_tick(tick)                 after every tick, return true to end the game
+//   on_food_eaten(player, x, y)   `player` ate the food at x, y
+//   on_death(player)              `player` crashed
+//   spawn_food(width, height)     where eaten food comes back, as x, y, or
+//                                 nothing to let the game pick a random cell
+//
+// and can look at and change the game through the `game` table while a hook
+// runs: game.tick(), game.board() (width, height), game.head(player) (x, y),
+// game.length(player), game.grow(player), game.score(player) and
+// game.set_score(player, score). Players are numbered from 1, as usual in Lua.
+// For example, to make every food worth three points:
+//
+//   function on_food_eaten(player)
+//     game.set_score(player, game.score(player) + 2)
+//   end
+
+use crate::game::{Coordinate, Event, Game};
+use mlua::{FromLuaMulti, Function, IntoLuaMulti, Lua};
+use std::cell::RefCell;
+
+pub struct Script {
+    lua: Lua,
+}
+
+impl Script {
+    pub fn load(path: &std::path::Path) -> Result<Self, String> {
+        let source =
+            std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        let lua = Lua::new();
+        lua.load(&source)
+            .set_name(format!("@{}", path.display()))
+            .exec()
+            .map_err(|e| e.to_string())?;
+
+        Ok(Script { lua })
+    }
+}
+
+impl crate::rules::Rules for Script {
+    fn after_tick(&mut self, game: &mut Game) -> Result<bool, String> {
+        let lua = &self.lua;
+        let events = game.events.clone();
+        let game = RefCell::new(game);
+
+        // Hooks call back into `game`, so it mustn't stay borrowed while one
+        // runs.
+        let player = |index: usize| -> mlua::Result<usize> {
+            match index.checked_sub(1) {
+                Some(player) if player < game.borrow().players.len() => Ok(player),
+                _ => Err(mlua::Error::runtime(format!(
+                    "there is no player {}",
+                    index
+                ))),
+            }
+        };
+
+        lua.scope(|scope| {
+            let api = lua.create_table()?;
+            api.set(
+                "tick",
+                scope.create_function(|_, ()| Ok(game.borrow().tick))?,
+            )?;
+            api.set(
+                "board",
+                scope.create_function(|_, ()| Ok(game.borrow().board_size))?,
+            )?;
+            api.set(
+                "head",
+                scope.create_function(|_, index| {
+                    let Coordinate(x, y) = game.borrow().players[player(index)?].head();
+                    Ok((x, y))
+                })?,
+            )?;
+            api.set(
+                "length",
+                scope.create_function(|_, index| {
+                    Ok(game.borrow().players[player(index)?].segments.len())
+                })?,
+            )?;
+            api.set(
+                "grow",
+                scope.create_function(|_, index| {
+                    let player = player(index)?;
+                    game.borrow_mut().grow(player);
+                    Ok(())
+                })?,
+            )?;
+            api.set(
+                "score",
+                scope
+                    .create_function(|_, index| Ok(game.borrow().players[player(index)?].score))?,
+            )?;
+            api.set(
+                "set_score",
+                scope.create_function(|_, (index, score): (usize, u32)| {
+                    let player = player(index)?;
+                    game.borrow_mut().players[player].score = score;
+                    Ok(())
+                })?,
+            )?;
+            lua.globals().set("game", api)?;
+
+            for event in events {
+                match event {
+                    Event::FoodEaten { player, food, .. } => {
+                        let Coordinate(x, y) = game.borrow().players[player].head();
+                        call::<()>(lua, "on_food_eaten", (player + 1, x, y))?;
+
+                        let (width, height) = game.borrow().board_size;
+                        if let Some((Some(x), Some(y))) = call(lua, "spawn_food", (width, height))?
+                        {
+                            let location = Coordinate(x, y);
+                            let mut game = game.borrow_mut();
+                            if game
+                                .players
+                                .iter()
+                                .any(|p| p.check_collisions(&location, &game.board_size))
+                            {
+                                return Err(mlua::Error::runtime(format!(
+                                    "spawn_food returned {}, {}, which isn't a free cell",
+                                    x, y
+                                )));
+                            }
+                            game.food[food].location = location;
+                        }
+                    }
+                    // Gone for good, so there's nowhere to spawn it.
+                    Event::FormationFoodEaten { player, .. }
+                    | Event::FrenzyFoodEaten { player, .. } => {
+                        let Coordinate(x, y) = game.borrow().players[player].head();
+                        call::<()>(lua, "on_food_eaten", (player + 1, x, y))?;
+                    }
+                    Event::Died { player } => {
+                        call::<()>(lua, "on_death", player + 1)?;
+                    }
+                    Event::BoardFull
+                    | Event::LostLife { .. }
+                    | Event::Bumped { .. }
+                    | Event::DoorOpened { .. }
+                    | Event::LetterEaten { .. }
+                    | Event::WordSpelled { .. }
+                    | Event::LettersLost
+                    | Event::RandomEventStarted { .. }
+                    | Event::RandomEventEnded { .. }
+                    | Event::FormationEaten { .. }
+                    | Event::FormationExpired => {}
+                }
+            }
+
+            let tick = game.borrow().tick;
+            let over: Option<Option<bool>> = call(lua, "on_tick", tick)?;
+            Ok(over.flatten().unwrap_or(false))
+        })
+        // Without the traceback, which doesn't fit on the screen.
+        .map_err(|e| e.to_string().lines().next().unwrap_or_default().to_string())
+    }
+}
+
+// Calls the hook if the script defines it.
+fn call<R: FromLuaMulti>(
+    lua: &Lua,
+    hook: &str,
+    args: impl IntoLuaMulti,
+) -> mlua::Result<Option<R>> {
+    match lua.globals().get::<Option<Function>>(hook)? {
+        Some(function) => function.call(args).map(Some),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,269 @@
+// `snake selfplay <file> [--games <n>] [--bot easy|medium|hard] [--size <w>x<h>]`
+// plays classic games without a terminal, with the computer steering, and
+// writes down what the snake saw and did every tick, so agents can be trained
+// by the real rules. What it saw is a board of CHANNELS planes, height by
+// width, 1 where there's
+//
+//   0  its head
+//   1  the rest of it
+//   2  food
+//   3  a wall
+//
+// and 0 elsewhere, before the tick. The action is the way it went during the
+// tick, 0 up, 1 down, 2 left and 3 right, and the reward the points it scored
+// doing so, or -1 for dying. Game n is seeded n, so the same options write
+// the same file.
+//
+// A .jsonl file gets a line a tick:
+//
+//   {"game": 0, "tick": 3, "board": [[[0, 1, ...], ...], ...], "action": 3,
+//    "reward": 0.0, "done": false}
+//
+// Anything else is the start of the names of NPY files, which numpy loads:
+//
+//   <file>.observations.npy  uint8, ticks x CHANNELS x height x width
+//   <file>.actions.npy       uint8, ticks
+//   <file>.rewards.npy       float32, ticks
+//   <file>.done.npy          uint8, ticks, 1 on the last tick of every game
+
+use crate::ai::Difficulty;
+use crate::game::{Coordinate, Game, Input, Mode, MoveDirection};
+use serde::Serialize;
+use std::io::Write;
+
+const CHANNELS: usize = 4;
+const DEFAULT_GAMES: u32 = 100;
+const DEFAULT_SIZE: (u16, u16) = (20, 20);
+// Computer players can go round in circles forever.
+const MAX_TICKS: u32 = 5000;
+
+pub struct Options {
+    pub path: std::path::PathBuf,
+    pub games: u32,
+    pub bot: Difficulty,
+    pub size: (u16, u16),
+}
+
+// From everything after `selfplay`.
+pub fn parse(args: &[&str]) -> Option<Options> {
+    let mut path = None;
+    let mut options = Options {
+        path: std::path::PathBuf::new(),
+        games: DEFAULT_GAMES,
+        bot: Difficulty::Medium,
+        size: DEFAULT_SIZE,
+    };
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--games" => options.games = args.next()?.parse().ok().filter(|n| *n > 0)?,
+            "--bot" => {
+                let name = args.next()?;
+                options.bot = Difficulty::ALL
+                    .into_iter()
+                    .find(|d| d.name().eq_ignore_ascii_case(name))?;
+            }
+            "--size" => {
+                let (w, h) = args.next()?.split_once('x')?;
+                options.size = (w.parse().ok()?, h.parse().ok()?);
+            }
+            arg if path.is_none() && !arg.starts_with("--") => path = Some(arg.into()),
+            _ => return None,
+        }
+    }
+
+    options.path = path?;
+    Some(options)
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    game: u32,
+    tick: u32,
+    board: Vec<Vec<&'a [u8]>>,
+    action: u8,
+    reward: f32,
+    done: bool,
+}
+
+// Every tick so far, for the NPY files.
+#[derive(Default)]
+struct Ticks {
+    observations: Vec<u8>,
+    actions: Vec<u8>,
+    rewards: Vec<u8>,
+    done: Vec<u8>,
+}
+
+// Self-play games only ever go straight.
+fn action(direction: MoveDirection) -> u8 {
+    MoveDirection::STRAIGHT
+        .iter()
+        .position(|d| *d == direction)
+        .expect("self-play snakes don't go diagonally") as u8
+}
+
+// The planes, one after the other, rows of each top to bottom.
+fn observe(game: &Game) -> Vec<u8> {
+    let (width, height) = (game.board_size.0 as usize, game.board_size.1 as usize);
+    let mut board = vec![0; CHANNELS * width * height];
+    let mut set = |channel: usize, Coordinate(x, y): Coordinate| {
+        if Coordinate(x, y).on_board(game.board_size) {
+            board[(channel * height + y as usize - 1) * width + x as usize - 1] = 1;
+        }
+    };
+
+    let player = &game.players[0];
+    for (index, segment) in player.segments.iter().enumerate() {
+        set(if index == 0 { 0 } else { 1 }, *segment);
+    }
+    for food in &game.food {
+        for cell in food.cells() {
+            set(2, cell);
+        }
+    }
+    for wall in &game.level.walls {
+        set(3, *wall);
+    }
+    board
+}
+
+// A numpy array file, version 1.0, of `data` with elements of `descr`.
+fn write_npy(
+    path: &std::path::Path,
+    descr: &str,
+    shape: &[usize],
+    data: &[u8],
+) -> Result<(), String> {
+    let shape = match shape {
+        [n] => format!("({},)", n),
+        _ => format!(
+            "({})",
+            shape
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+        descr, shape
+    );
+    // The magic, version and length take 10 bytes, and the data starts on a
+    // multiple of 64 after a newline.
+    while (10 + header.len() + 1) % 64 != 0 {
+        header.push(' ');
+    }
+    header.push('\n');
+
+    let mut contents = b"\x93NUMPY\x01\x00".to_vec();
+    contents.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    contents.extend_from_slice(header.as_bytes());
+    contents.extend_from_slice(data);
+    std::fs::write(path, contents).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+pub fn run(options: &Options) -> Result<(), String> {
+    let (min_width, min_height) = crate::level::MIN_SIZE;
+    if options.size.0 < min_width || options.size.1 < min_height {
+        return Err(format!(
+            "the board has to be at least {}x{}",
+            min_width, min_height
+        ));
+    }
+
+    let path = &options.path;
+    let error = |e: std::io::Error| format!("{}: {}", path.display(), e);
+    let jsonl = path.extension().is_some_and(|e| e == "jsonl");
+    let mut lines = match jsonl {
+        true => Some(std::io::BufWriter::new(
+            std::fs::File::create(path).map_err(error)?,
+        )),
+        false => None,
+    };
+    let mut ticks = Ticks::default();
+    let mut count = 0;
+
+    for n in 0..options.games {
+        let mut game = Game::new(Mode::Classic, n.into(), options.size);
+        let mut bot = options.bot.controller(&game, 0);
+
+        loop {
+            let observation = observe(&game);
+            let (tick, score) = (game.tick, game.players[0].score);
+            if let Some(direction) = bot.steer(&game, 0) {
+                game.handle_input(0, Input::Turn(direction));
+            }
+            let over = game.tick() || game.tick >= MAX_TICKS;
+
+            let player = &game.players[0];
+            let action = action(player.move_direction);
+            let reward = match player.alive {
+                true => (player.score - score) as f32,
+                false => -1.0,
+            };
+            count += 1;
+
+            match &mut lines {
+                Some(lines) => {
+                    let (width, height) = (options.size.0 as usize, options.size.1 as usize);
+                    let board = observation
+                        .chunks(width * height)
+                        .map(|plane| plane.chunks(width).collect())
+                        .collect();
+                    let line = Line {
+                        game: n,
+                        tick,
+                        board,
+                        action,
+                        reward,
+                        done: over,
+                    };
+                    serde_json::to_writer(&mut *lines, &line).map_err(|e| e.to_string())?;
+                    lines.write_all(b"\n").map_err(error)?;
+                }
+                None => {
+                    ticks.observations.extend(observation);
+                    ticks.actions.push(action);
+                    ticks.rewards.extend(reward.to_le_bytes());
+                    ticks.done.push(over as u8);
+                }
+            }
+
+            if over {
+                break;
+            }
+        }
+    }
+
+    match lines {
+        Some(mut lines) => lines.flush().map_err(error)?,
+        None => {
+            let file = |name: &str| {
+                let mut file = path.as_os_str().to_owned();
+                file.push(format!(".{}.npy", name));
+                std::path::PathBuf::from(file)
+            };
+            let (width, height) = (options.size.0 as usize, options.size.1 as usize);
+            write_npy(
+                &file("observations"),
+                "|u1",
+                &[count, CHANNELS, height, width],
+                &ticks.observations,
+            )?;
+            write_npy(&file("actions"), "|u1", &[count], &ticks.actions)?;
+            write_npy(&file("rewards"), "<f4", &[count], &ticks.rewards)?;
+            write_npy(&file("done"), "|u1", &[count], &ticks.done)?;
+        }
+    }
+
+    println!(
+        "{} ticks of {} games written to {}",
+        count,
+        options.games,
+        path.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,124 @@
+// `snake server` plays versus games for clients that `snake join` it, with
+// no terminal of its own. Clients are paired up in the order they connect,
+// and every pair gets a thread running their game, so the server is the only
+// one deciding what happened in it and neither player hosts. See `predict`
+// for how clients keep up with it.
+
+use crate::game::{Game, Input, Mode, MoveDirection};
+use crate::level::MIN_NETWORK_SIZE;
+use crate::net::{Connection, Message};
+
+// How long a client has to say hello after connecting.
+const HELLO_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+pub fn serve(port: u16, move_duration: std::time::Duration) -> Result<(), String> {
+    let listener = std::net::TcpListener::bind(("0.0.0.0", port))
+        .map_err(|e| format!("Could not listen on port {}: {}", port, e))?;
+    println!("Serving versus games on port {}", port);
+
+    let (sender, clients) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let sender = sender.clone();
+            std::thread::spawn(move || {
+                if let Some(client) = greet(stream) {
+                    let _ = sender.send(client);
+                }
+            });
+        }
+    });
+
+    let mut waiting = None;
+    for client in clients {
+        match waiting.take() {
+            None => waiting = Some(client),
+            Some(first) => {
+                std::thread::spawn(move || play([first, client], move_duration));
+            }
+        }
+    }
+    Ok(())
+}
+
+// A client that's said hello, with the size of its terminal.
+fn greet(stream: std::net::TcpStream) -> Option<(Connection, (u16, u16))> {
+    let connection = Connection::new(stream).ok()?;
+    let started = std::time::Instant::now();
+    while started.elapsed() < HELLO_TIMEOUT {
+        if let Some(Message::Hello(w, h)) = connection.receive()?.into_iter().next() {
+            return Some((connection, (w, h)));
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    None
+}
+
+fn play(clients: [(Connection, (u16, u16)); 2], move_duration: std::time::Duration) {
+    let [(first, first_size), (second, second_size)] = clients;
+    let mut connections = [first, second];
+    // Both players have to see the whole board, unless that's too small to
+    // play on.
+    let board_size = (
+        first_size.0.min(second_size.0).max(MIN_NETWORK_SIZE.0),
+        first_size.1.min(second_size.1).max(MIN_NETWORK_SIZE.1),
+    );
+    let seed = rand::random();
+    for (you, connection) in connections.iter_mut().enumerate() {
+        let _ = connection.send(&Message::Match(board_size.0, board_size.1, seed, you));
+    }
+
+    let mut game = Game::new(Mode::Versus, seed, board_size);
+    // Every snake's turns by the tick they were sent for.
+    let mut turns: [std::collections::BTreeMap<u32, Vec<MoveDirection>>; 2] = Default::default();
+    let mut next_move = std::time::Instant::now();
+    loop {
+        next_move += move_duration;
+        std::thread::sleep(next_move.saturating_duration_since(std::time::Instant::now()));
+
+        for snake in 0..connections.len() {
+            let Some(messages) = connections[snake].receive() else {
+                let _ = connections[1 - snake].send(&Message::Quit);
+                return;
+            };
+            for received in messages {
+                match received {
+                    Message::TurnAt(tick, direction) => {
+                        turns[snake].entry(tick).or_default().push(direction)
+                    }
+                    Message::Say(line) => {
+                        let _ = connections[1 - snake].send(&Message::Say(line));
+                    }
+                    Message::Quit => {
+                        let _ = connections[1 - snake].send(&Message::Quit);
+                        return;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        // Turns that arrived too late for their tick count on this one.
+        let mut played = Vec::new();
+        for (snake, turns) in turns.iter_mut().enumerate() {
+            while let Some(entry) = turns.first_entry().filter(|e| *e.key() <= game.tick) {
+                for direction in entry.remove() {
+                    game.handle_input(snake, Input::Turn(direction));
+                    played.push((snake, direction));
+                }
+            }
+        }
+        let tick = game.tick;
+        let over = game.tick();
+
+        // A client that's gone shows up as one on the next receive.
+        for connection in &mut connections {
+            let _ = connection.send(&Message::Tick(tick, played.clone()));
+            if over {
+                let _ = connection.send(&Message::Over);
+            }
+        }
+        if over {
+            return;
+        }
+    }
+}
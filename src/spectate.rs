@@ -0,0 +1,121 @@
+// Live spectating over WebSocket. A game started with `--spectate <port>`
+// accepts any number of WebSocket connections on that port and sends each of
+// them a text message with the JSON encoded `net::Snapshot` after every move:
+//
+//   {"tick":12,"board_size":[80,24],
+//    "players":[{"move_direction":"right","segments":[[9,1],[8,1]],"score":1,"alive":true}],
+//    "food":[{"location":[30,7],"kind":"apple"}]}
+//
+// web/spectate.html watches one in a browser. `snake spectate <host>[:<port>]`
+// watches in the terminal, and connects to /binary instead, which sends the
+// same snapshots as binary messages encoded like `net` does, several times
+// smaller than the JSON.
+
+use crate::net::Snapshot;
+
+pub const DEFAULT_PORT: u16 = 7778;
+
+// Frames a slow spectator can fall behind by before it starts missing some.
+const BACKLOG: usize = 8;
+
+// Every frame in both encodings, made once for however many spectators want
+// each.
+type Frame = std::sync::Arc<(String, Vec<u8>)>;
+
+pub struct Spectators {
+    clients: std::sync::Arc<std::sync::Mutex<Vec<std::sync::mpsc::SyncSender<Frame>>>>,
+}
+
+const BINARY_PATH: &str = "/binary";
+
+impl Spectators {
+    pub fn listen(port: u16) -> Result<Self, std::io::Error> {
+        let listener = std::net::TcpListener::bind(("0.0.0.0", port))?;
+        let clients = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let accepted = std::sync::Arc::clone(&clients);
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (sender, frames) = std::sync::mpsc::sync_channel(BACKLOG);
+                accepted.lock().unwrap().push(sender);
+                std::thread::spawn(move || serve(stream, frames));
+            }
+        });
+
+        Ok(Spectators { clients })
+    }
+
+    // Never blocks: every spectator has its own thread doing the writing.
+    pub fn broadcast(&self, snapshot: &Snapshot) {
+        let mut binary = crate::wire::Encoder::default();
+        binary.byte(crate::wire::VERSION);
+        snapshot.encode(&mut binary);
+        let frame: Frame =
+            std::sync::Arc::new((serde_json::to_string(snapshot).unwrap(), binary.bytes));
+
+        self.clients.lock().unwrap().retain(|client| {
+            match client.try_send(std::sync::Arc::clone(&frame)) {
+                Ok(()) | Err(std::sync::mpsc::TrySendError::Full(_)) => true,
+                Err(std::sync::mpsc::TrySendError::Disconnected(_)) => false,
+            }
+        });
+    }
+}
+
+fn serve(stream: std::net::TcpStream, frames: std::sync::mpsc::Receiver<Frame>) {
+    // The request line is all that's needed, and it comes first.
+    let mut request = [0; 64];
+    let Ok(n) = stream.peek(&mut request) else {
+        return;
+    };
+    let binary = request[..n].starts_with(format!("GET {} ", BINARY_PATH).as_bytes());
+    let Ok(mut socket) = tungstenite::accept(stream) else {
+        return;
+    };
+
+    for frame in frames {
+        let message = match binary {
+            true => tungstenite::Message::binary(frame.1.clone()),
+            false => tungstenite::Message::text(frame.0.as_str()),
+        };
+        if socket.send(message).is_err() {
+            return;
+        }
+    }
+}
+
+// Connects to a spectated game and hands every snapshot it sends to the
+// returned channel, which closes once the game goes away.
+pub fn connect(address: &str) -> Result<std::sync::mpsc::Receiver<Snapshot>, String> {
+    let address = if address.contains(':') {
+        address.to_string()
+    } else {
+        format!("{}:{}", address, DEFAULT_PORT)
+    };
+
+    let stream = std::net::TcpStream::connect(&address).map_err(|e| e.to_string())?;
+    let url = format!("ws://{}{}", address, BINARY_PATH);
+    let (mut socket, _) = tungstenite::client(url, stream).map_err(|e| e.to_string())?;
+
+    let (sender, snapshots) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        while let Ok(message) = socket.read() {
+            let tungstenite::Message::Binary(bytes) = message else {
+                continue;
+            };
+            // Every frame starts with the version it's encoded in.
+            let mut input = crate::wire::Decoder::new(&bytes);
+            if input.byte() != Some(crate::wire::VERSION) {
+                return;
+            }
+            let Some(snapshot) = Snapshot::decode(&mut input) else {
+                return;
+            };
+            if sender.send(snapshot).is_err() {
+                return;
+            }
+        }
+    });
+
+    Ok(snapshots)
+}
@@ -0,0 +1,255 @@
+// `snake serve-ssh` lets people play with nothing but an SSH client. Every
+// session gets a thread of its own running the same menu as a local terminal,
+// so sessions don't share anything but the high score file. Anyone can log in
+// with any name: there is nothing here worth protecting. `snake daemon` runs
+// this next to its other frontends, with the sessions listed alongside theirs.
+
+use crate::term::Terminal;
+use russh::keys::ssh_key::private::Ed25519Keypair;
+use russh::keys::ssh_key::LineEnding;
+use russh::keys::{PrivateKey, PublicKey};
+use russh::server::{Auth, ChannelOpenHandle, Handle, Handler, Msg, Server, Session};
+use russh::{Channel, ChannelId, MethodKind, MethodSet};
+use std::sync::{Arc, Mutex};
+
+pub const DEFAULT_PORT: u16 = 2222;
+
+struct SshTerminal {
+    handle: Handle,
+    channel: ChannelId,
+    runtime: tokio::runtime::Handle,
+    input: std::sync::mpsc::Receiver<Vec<u8>>,
+    size: Arc<Mutex<(u16, u16)>>,
+    buffer: Vec<u8>,
+    pending: Vec<u8>,
+}
+
+impl std::io::Write for SshTerminal {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, std::io::Error> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    // Fails once the client has gone away, which ends the session's thread.
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let data = std::mem::take(&mut self.buffer);
+        self.runtime
+            .block_on(self.handle.data(self.channel, data))
+            .map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))
+    }
+}
+
+impl Terminal for SshTerminal {
+    fn read_input(&mut self) -> Vec<u8> {
+        let mut input = std::mem::take(&mut self.pending);
+        input.extend(self.input.try_iter().flatten());
+        input
+    }
+
+    fn wait_for_input(&mut self, timeout: std::time::Duration) {
+        if !self.pending.is_empty() {
+            return;
+        }
+        if let Ok(input) = self.input.recv_timeout(timeout) {
+            self.pending = input;
+        }
+    }
+
+    fn size(&self) -> (u16, u16) {
+        *self.size.lock().unwrap()
+    }
+}
+
+struct Client {
+    config: Arc<crate::config::Config>,
+    sessions: Arc<crate::daemon::Sessions>,
+    peer: String,
+    input: Option<std::sync::mpsc::Sender<Vec<u8>>>,
+    size: Arc<Mutex<(u16, u16)>>,
+}
+
+impl Handler for Client {
+    type Error = russh::Error;
+
+    async fn auth_none(&mut self, _user: &str) -> Result<Auth, Self::Error> {
+        Ok(Auth::Accept)
+    }
+
+    async fn auth_password(&mut self, _user: &str, _password: &str) -> Result<Auth, Self::Error> {
+        Ok(Auth::Accept)
+    }
+
+    async fn auth_publickey(&mut self, _user: &str, _key: &PublicKey) -> Result<Auth, Self::Error> {
+        Ok(Auth::Accept)
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        _channel: Channel<Msg>,
+        reply: ChannelOpenHandle,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        reply.accept().await;
+        Ok(())
+    }
+
+    async fn pty_request(
+        &mut self,
+        channel: ChannelId,
+        _term: &str,
+        col_width: u32,
+        row_height: u32,
+        _pix_width: u32,
+        _pix_height: u32,
+        _modes: &[(russh::Pty, u32)],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        *self.size.lock().unwrap() = (col_width as u16, row_height as u16);
+        session.channel_success(channel)
+    }
+
+    async fn window_change_request(
+        &mut self,
+        _channel: ChannelId,
+        col_width: u32,
+        row_height: u32,
+        _pix_width: u32,
+        _pix_height: u32,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        *self.size.lock().unwrap() = (col_width as u16, row_height as u16);
+        Ok(())
+    }
+
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if self.input.is_some() {
+            return session.channel_failure(channel);
+        }
+
+        let (sender, input) = std::sync::mpsc::channel();
+        self.input = Some(sender);
+        session.channel_success(channel)?;
+
+        let mut terminal = SshTerminal {
+            handle: session.handle(),
+            channel,
+            runtime: tokio::runtime::Handle::current(),
+            input,
+            size: Arc::clone(&self.size),
+            buffer: Vec::new(),
+            pending: Vec::new(),
+        };
+        let config = Arc::clone(&self.config);
+        let (handle, runtime) = (session.handle(), tokio::runtime::Handle::current());
+        let kill = Box::new(move || {
+            let handle = handle.clone();
+            runtime.spawn(async move {
+                let _ = handle.close(channel).await;
+            });
+        });
+        let registered = self.sessions.register("ssh", self.peer.clone(), kill);
+
+        std::thread::spawn(move || {
+            let _registered = registered;
+            crate::theme::init(&config);
+            crate::menu(&mut terminal, None, None, &config);
+
+            let _ = crate::clear(&mut terminal);
+            let _ = std::io::Write::flush(&mut terminal);
+            let handle = terminal.handle.clone();
+            terminal.runtime.block_on(async {
+                let _ = handle.exit_status_request(channel, 0).await;
+                let _ = handle.close(channel).await;
+            });
+        });
+
+        Ok(())
+    }
+
+    async fn data(
+        &mut self,
+        _channel: ChannelId,
+        data: &[u8],
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if let Some(input) = &self.input {
+            let _ = input.send(data.to_vec());
+        }
+        Ok(())
+    }
+}
+
+struct SnakeServer {
+    config: Arc<crate::config::Config>,
+    sessions: Arc<crate::daemon::Sessions>,
+}
+
+impl Server for SnakeServer {
+    type Handler = Client;
+
+    fn new_client(&mut self, peer: Option<std::net::SocketAddr>) -> Client {
+        Client {
+            config: Arc::clone(&self.config),
+            sessions: Arc::clone(&self.sessions),
+            peer: peer.map_or_else(|| "-".to_string(), |peer| peer.to_string()),
+            input: None,
+            size: Arc::new(Mutex::new((80, 24))),
+        }
+    }
+}
+
+pub fn serve(
+    port: u16,
+    config: crate::config::Config,
+    sessions: Arc<crate::daemon::Sessions>,
+) -> Result<(), String> {
+    let ssh_config = russh::server::Config {
+        methods: MethodSet::from(
+            &[
+                MethodKind::None,
+                MethodKind::Password,
+                MethodKind::PublicKey,
+            ][..],
+        ),
+        auth_rejection_time: std::time::Duration::from_secs(1),
+        inactivity_timeout: Some(std::time::Duration::from_secs(3600)),
+        keys: vec![host_key()?],
+        nodelay: true,
+        ..Default::default()
+    };
+
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    let mut server = SnakeServer {
+        config: Arc::new(config),
+        sessions,
+    };
+
+    println!("Listening for SSH connections on port {}", port);
+    runtime
+        .block_on(server.run_on_address(Arc::new(ssh_config), ("0.0.0.0", port)))
+        .map_err(|e| e.to_string())
+}
+
+// Generated on first start and kept, so clients don't complain about a
+// changed host key every time the server restarts.
+fn host_key() -> Result<PrivateKey, String> {
+    let path = crate::paths::ssh_host_key_file();
+    if path.exists() {
+        return PrivateKey::read_openssh_file(&path)
+            .map_err(|e| format!("{}: {}", path.display(), e));
+    }
+
+    let key = PrivateKey::from(Ed25519Keypair::from_seed(&rand::random()));
+    key.write_openssh_file(&path, LineEnding::LF)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    Ok(key)
+}
@@ -0,0 +1,126 @@
+// `snake stats export`, for keeping track of games somewhere else: the high
+// scores, every profile's versus rating and the usage totals, when
+// `usage_stats` has been keeping them. As JSON it looks like:
+//
+//   {"version": 1,
+//    "scores": [{"rank": 1, "name": "jsw", "score": 120, "mode": "classic"}],
+//    "ratings": {"default": {"rating": 1216, "wins": 1, "losses": 0,
+//                            "draws": 0}},
+//    "usage": {"sessions": 12, "session_seconds": 4080,
+//              "games": {"play": 30, "twins": 2}}}
+//
+// and as CSV there's a line for every number, scores first:
+//
+//   section,name,value
+//   score,jsw,120
+//   rating,default,1216
+//   sessions,,12
+//   session_seconds,,4080
+//   games,play,30
+//
+// Fields are only ever added, and `version` goes up when one changes meaning.
+
+use serde::Serialize;
+
+const VERSION: u32 = 1;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Format {
+    Json,
+    Csv,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Score<'a> {
+    rank: usize,
+    name: &'a str,
+    score: u32,
+    mode: &'static str,
+}
+
+#[derive(Serialize)]
+struct Stats<'a> {
+    version: u32,
+    scores: Vec<Score<'a>>,
+    ratings: std::collections::BTreeMap<String, crate::ratings::Rating>,
+    usage: &'a crate::usage::Totals,
+}
+
+// Writes everything to `output`, or to stdout without one.
+pub fn export(format: Format, output: Option<&std::path::Path>) -> Result<(), String> {
+    let high_scores = crate::scores::HighScores::load();
+    let totals = crate::usage::totals();
+    let stats = Stats {
+        version: VERSION,
+        scores: (1..)
+            .zip(high_scores.entries())
+            .map(|(rank, entry)| Score {
+                rank,
+                name: &entry.name,
+                score: entry.score,
+                mode: entry.mode.name(),
+            })
+            .collect(),
+        ratings: crate::ratings::all(),
+        usage: &totals,
+    };
+
+    let contents = match format {
+        Format::Json => serde_json::to_string_pretty(&stats).map_err(|e| e.to_string())? + "\n",
+        Format::Csv => csv(&stats),
+    };
+    match output {
+        Some(path) => {
+            std::fs::write(path, contents).map_err(|e| format!("{}: {}", path.display(), e))
+        }
+        None => {
+            print!("{}", contents);
+            Ok(())
+        }
+    }
+}
+
+// Names are letters, digits, - and _, and game kinds menu items, so only
+// profiles, which are whatever the config says, might need quoting.
+fn csv(stats: &Stats) -> String {
+    let mut lines = vec!["section,name,value".to_string()];
+    lines.extend(
+        stats
+            .scores
+            .iter()
+            .map(|score| format!("score,{},{}", score.name, score.score)),
+    );
+    lines.extend(
+        stats
+            .ratings
+            .iter()
+            .map(|(profile, rating)| format!("rating,{},{}", quoted(profile), rating.rating)),
+    );
+    lines.push(format!("sessions,,{}", stats.usage.sessions));
+    lines.push(format!("session_seconds,,{}", stats.usage.session_seconds));
+    lines.extend(
+        stats
+            .usage
+            .games
+            .iter()
+            .map(|(kind, count)| format!("games,{},{}", kind, count)),
+    );
+    lines.join("\n") + "\n"
+}
+
+fn quoted(field: &str) -> String {
+    match field.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
@@ -0,0 +1,209 @@
+// The telnet frontend of `snake daemon`, for playing with `telnet host 2323`.
+// Telnet clients start out echoing and sending whole lines, so the server
+// asks them to leave echoing to it and send keys as they're typed, and to
+// say how big the window is. Everything else they offer is ignored: a client
+// that's told no to options it didn't ask about is free to keep asking.
+
+use crate::term::Terminal;
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+
+const IAC: u8 = 255;
+const WILL: u8 = 251;
+const DO: u8 = 253;
+const SB: u8 = 250;
+const ECHO: u8 = 1;
+const SUPPRESS_GO_AHEAD: u8 = 3;
+const NAWS: u8 = 31;
+
+#[derive(Default)]
+enum State {
+    #[default]
+    Data,
+    // After a carriage return, which is followed by a line feed or a NUL.
+    Return,
+    Command,
+    // Negotiating the option that comes next.
+    Option,
+    Subnegotiation(Vec<u8>),
+    SubnegotiationCommand(Vec<u8>),
+}
+
+// Takes the telnet commands out of what the client sends, leaving the keys.
+#[derive(Default)]
+struct Parser {
+    state: State,
+}
+
+impl Parser {
+    // The keys in `data`, and the window size if the client said.
+    fn parse(&mut self, data: &[u8]) -> (Vec<u8>, Option<(u16, u16)>) {
+        let mut keys = Vec::new();
+        let mut size = None;
+        for &b in data {
+            self.state = match std::mem::take(&mut self.state) {
+                State::Data | State::Return if b == IAC => State::Command,
+                State::Return if b == b'\n' || b == 0 => State::Data,
+                State::Data | State::Return if b == b'\r' => {
+                    keys.push(13);
+                    State::Return
+                }
+                State::Data | State::Return => {
+                    keys.push(b);
+                    State::Data
+                }
+                State::Command => match b {
+                    IAC => {
+                        keys.push(IAC);
+                        State::Data
+                    }
+                    SB => State::Subnegotiation(Vec::new()),
+                    WILL..=IAC => State::Option,
+                    _ => State::Data,
+                },
+                State::Option => State::Data,
+                State::Subnegotiation(bytes) if b == IAC => State::SubnegotiationCommand(bytes),
+                State::Subnegotiation(mut bytes) => {
+                    bytes.push(b);
+                    State::Subnegotiation(bytes)
+                }
+                State::SubnegotiationCommand(mut bytes) if b == IAC => {
+                    bytes.push(IAC);
+                    State::Subnegotiation(bytes)
+                }
+                State::SubnegotiationCommand(bytes) => {
+                    if let [NAWS, w1, w2, h1, h2] = bytes[..] {
+                        let (w, h) = (u16::from_be_bytes([w1, w2]), u16::from_be_bytes([h1, h2]));
+                        // Clients that don't know say zero.
+                        if w > 0 && h > 0 {
+                            size = Some((w, h));
+                        }
+                    }
+                    // Anything but IAC SE is a broken client, which there's
+                    // no making sense of either way.
+                    State::Data
+                }
+            };
+        }
+        (keys, size)
+    }
+}
+
+struct TelnetTerminal {
+    stream: std::net::TcpStream,
+    input: std::sync::mpsc::Receiver<Vec<u8>>,
+    size: Arc<Mutex<(u16, u16)>>,
+    buffer: Vec<u8>,
+    pending: Vec<u8>,
+}
+
+impl TelnetTerminal {
+    fn new(stream: std::net::TcpStream) -> Result<Self, std::io::Error> {
+        stream.set_nodelay(true)?;
+        (&stream).write_all(&[IAC, WILL, ECHO, IAC, WILL, SUPPRESS_GO_AHEAD, IAC, DO, NAWS])?;
+
+        let size = Arc::new(Mutex::new((80, 24)));
+        let (sender, input) = std::sync::mpsc::channel();
+        let mut reader = stream.try_clone()?;
+        let window = Arc::clone(&size);
+        std::thread::spawn(move || {
+            let mut parser = Parser::default();
+            let mut buffer = [0; 1024];
+            while let Ok(n @ 1..) = reader.read(&mut buffer) {
+                let (keys, size) = parser.parse(&buffer[..n]);
+                if let Some(size) = size {
+                    *window.lock().unwrap() = size;
+                }
+                if !keys.is_empty() && sender.send(keys).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Ok(TelnetTerminal {
+            stream,
+            input,
+            size,
+            buffer: Vec::new(),
+            pending: Vec::new(),
+        })
+    }
+}
+
+impl Write for TelnetTerminal {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, std::io::Error> {
+        // A byte that would be taken for a command has to be sent twice.
+        for &b in buf {
+            self.buffer.push(b);
+            if b == IAC {
+                self.buffer.push(IAC);
+            }
+        }
+        Ok(buf.len())
+    }
+
+    // Fails once the client has gone away, which ends the session's thread.
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        let data = std::mem::take(&mut self.buffer);
+        self.stream.write_all(&data)
+    }
+}
+
+impl Terminal for TelnetTerminal {
+    fn read_input(&mut self) -> Vec<u8> {
+        let mut input = std::mem::take(&mut self.pending);
+        input.extend(self.input.try_iter().flatten());
+        input
+    }
+
+    fn wait_for_input(&mut self, timeout: std::time::Duration) {
+        if !self.pending.is_empty() {
+            return;
+        }
+        if let Ok(input) = self.input.recv_timeout(timeout) {
+            self.pending = input;
+        }
+    }
+
+    fn size(&self) -> (u16, u16) {
+        *self.size.lock().unwrap()
+    }
+}
+
+pub fn serve(
+    port: u16,
+    config: crate::config::Config,
+    sessions: Arc<crate::daemon::Sessions>,
+) -> Result<(), String> {
+    let listener = std::net::TcpListener::bind(("0.0.0.0", port)).map_err(|e| e.to_string())?;
+    let config = Arc::new(config);
+
+    println!("Listening for telnet connections on port {}", port);
+    for stream in listener.incoming().flatten() {
+        let peer = stream
+            .peer_addr()
+            .map_or_else(|_| "-".to_string(), |peer| peer.to_string());
+        let Ok(kill) = stream.try_clone() else {
+            continue;
+        };
+        let Ok(mut terminal) = TelnetTerminal::new(stream) else {
+            continue;
+        };
+        let kill = Box::new(move || {
+            let _ = kill.shutdown(std::net::Shutdown::Both);
+        });
+        let registered = sessions.register("telnet", peer, kill);
+        let config = Arc::clone(&config);
+
+        std::thread::spawn(move || {
+            let _registered = registered;
+            crate::theme::init(&config);
+            crate::menu(&mut terminal, None, None, &config);
+
+            let _ = crate::clear(&mut terminal);
+            let _ = terminal.flush();
+            let _ = terminal.stream.shutdown(std::net::Shutdown::Both);
+        });
+    }
+    Ok(())
+}
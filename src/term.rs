@@ -0,0 +1,106 @@
+use std::io::{Read, Write};
+use termion::raw::IntoRawMode;
+
+// What every screen draws to and reads keys from. Usually the terminal snake
+// was started in, but `serve-ssh` gives every session its own.
+pub trait Terminal: Write {
+    // Everything typed since the last call. Never blocks.
+    fn read_input(&mut self) -> Vec<u8>;
+
+    // Blocks until there is something to read or `timeout` has passed, for
+    // screens with nothing to do until a key is pressed. Leaves what was typed
+    // for `read_input`.
+    fn wait_for_input(&mut self, timeout: std::time::Duration);
+
+    fn size(&self) -> (u16, u16);
+}
+
+// Whether the terminal can most likely draw emoji: it has to be set up for
+// UTF-8, and not be the Linux console or a dumb terminal, which only have the
+// glyphs their own fonts do. There's no asking a terminal, so this guesses
+// from the environment.
+pub fn supports_emoji() -> bool {
+    let utf8 = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .is_some_and(|locale| {
+            let locale = locale.to_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        });
+    let term = std::env::var("TERM").unwrap_or_default();
+    utf8 && !matches!(term.as_str(), "" | "linux" | "dumb")
+}
+
+// Big enough for a whole frame of a large terminal, which then goes out in one
+// write when flushed instead of tearing as it's drawn.
+const BUFFER_SIZE: usize = 256 * 1024;
+
+pub struct LocalTerminal {
+    screen: std::io::BufWriter<termion::raw::RawTerminal<std::io::Stdout>>,
+    input: std::sync::mpsc::Receiver<Vec<u8>>,
+    // Read while waiting, but not taken by `read_input` yet.
+    pending: Vec<u8>,
+}
+
+impl LocalTerminal {
+    pub fn new() -> Result<Self, std::io::Error> {
+        Ok(LocalTerminal {
+            screen: std::io::BufWriter::with_capacity(
+                BUFFER_SIZE,
+                std::io::stdout().into_raw_mode()?,
+            ),
+            input: read_stdin(),
+            pending: Vec::new(),
+        })
+    }
+}
+
+impl Write for LocalTerminal {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, std::io::Error> {
+        self.screen.write(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        self.screen.flush()
+    }
+}
+
+impl Terminal for LocalTerminal {
+    fn read_input(&mut self) -> Vec<u8> {
+        let mut input = std::mem::take(&mut self.pending);
+        input.extend(self.input.try_iter().flatten());
+        input
+    }
+
+    fn wait_for_input(&mut self, timeout: std::time::Duration) {
+        if !self.pending.is_empty() {
+            return;
+        }
+        if let Ok(input) = self.input.recv_timeout(timeout) {
+            self.pending = input;
+        }
+    }
+
+    fn size(&self) -> (u16, u16) {
+        termion::terminal_size().unwrap()
+    }
+}
+
+// Reads stdin on a thread of its own, so it can be waited on with a timeout.
+fn read_stdin() -> std::sync::mpsc::Receiver<Vec<u8>> {
+    let (sender, input) = std::sync::mpsc::channel();
+
+    std::thread::spawn(move || {
+        let mut stdin = std::io::stdin();
+        let mut buf = [0; 64];
+
+        while let Ok(n @ 1..) = stdin.read(&mut buf) {
+            if sender.send(buf[..n].to_vec()).is_err() {
+                return;
+            }
+        }
+    });
+
+    input
+}
@@ -0,0 +1,328 @@
+// The colors and food glyphs the board is drawn in. `theme` in the config
+// picks one, and when it isn't set the date does: winter from December to
+// twelfth night, halloween the week before it, and classic the rest of the
+// year. The nokia cheat swaps in the retro theme, which can't be picked any
+// other way. At night, see `set_night`, every theme is drawn darker. Games
+// play the same whatever they look like.
+
+use serde::Deserialize;
+use termion::color::Rgb;
+
+#[derive(Copy, Clone, PartialEq, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeName {
+    Classic,
+    Winter,
+    Halloween,
+    #[serde(skip_deserializing)]
+    Retro,
+}
+
+pub struct FoodStyle {
+    pub emoji: char,
+    // For terminals without emoji, with `color` behind it.
+    pub letter: char,
+    pub color: Rgb,
+}
+
+pub struct Theme {
+    // Head and body of the first snake, then of the second.
+    pub snakes: [(Rgb, Rgb); 2],
+    pub wall: Rgb,
+    // Apples, cherries, mice and feasts, in that order.
+    pub food: [FoodStyle; 4],
+}
+
+const CLASSIC: Theme = Theme {
+    snakes: [
+        (Rgb(0, 255, 0), Rgb(255, 255, 255)),
+        (Rgb(0, 128, 255), Rgb(128, 200, 255)),
+    ],
+    wall: Rgb(128, 128, 128),
+    food: [
+        FoodStyle {
+            emoji: '🍎',
+            letter: '\'',
+            color: Rgb(255, 0, 0),
+        },
+        FoodStyle {
+            emoji: '🍒',
+            letter: '"',
+            color: Rgb(255, 0, 160),
+        },
+        FoodStyle {
+            emoji: '🐭',
+            letter: 'm',
+            color: Rgb(160, 160, 160),
+        },
+        FoodStyle {
+            emoji: '🍗',
+            letter: '&',
+            color: Rgb(220, 130, 40),
+        },
+    ],
+};
+
+const WINTER: Theme = Theme {
+    snakes: [
+        (Rgb(120, 200, 255), Rgb(230, 245, 255)),
+        (Rgb(255, 40, 40), Rgb(255, 200, 200)),
+    ],
+    wall: Rgb(190, 215, 235),
+    food: [
+        FoodStyle {
+            emoji: '🍪',
+            letter: 'o',
+            color: Rgb(200, 140, 60),
+        },
+        FoodStyle {
+            emoji: '🍬',
+            letter: '*',
+            color: Rgb(255, 80, 120),
+        },
+        FoodStyle {
+            emoji: '🎁',
+            letter: '#',
+            color: Rgb(0, 170, 80),
+        },
+        FoodStyle {
+            emoji: '🎂',
+            letter: '&',
+            color: Rgb(255, 230, 240),
+        },
+    ],
+};
+
+const HALLOWEEN: Theme = Theme {
+    snakes: [
+        (Rgb(255, 140, 0), Rgb(140, 70, 0)),
+        (Rgb(170, 80, 255), Rgb(90, 40, 150)),
+    ],
+    wall: Rgb(60, 60, 60),
+    food: [
+        FoodStyle {
+            emoji: '🍬',
+            letter: '*',
+            color: Rgb(255, 200, 0),
+        },
+        FoodStyle {
+            emoji: '🎃',
+            letter: '@',
+            color: Rgb(255, 110, 0),
+        },
+        FoodStyle {
+            emoji: '🦇',
+            letter: 'w',
+            color: Rgb(120, 0, 160),
+        },
+        FoodStyle {
+            emoji: '🍫',
+            letter: '&',
+            color: Rgb(120, 60, 20),
+        },
+    ],
+};
+
+// Shades of green like an old phone's screen, and food without any color of
+// its own.
+const RETRO: Theme = Theme {
+    snakes: [
+        (Rgb(170, 220, 60), Rgb(120, 170, 40)),
+        (Rgb(200, 240, 140), Rgb(150, 200, 90)),
+    ],
+    wall: Rgb(90, 120, 50),
+    food: [
+        FoodStyle {
+            emoji: '🍏',
+            letter: 'o',
+            color: Rgb(170, 220, 60),
+        },
+        FoodStyle {
+            emoji: '🥝',
+            letter: '*',
+            color: Rgb(170, 220, 60),
+        },
+        FoodStyle {
+            emoji: '🐛',
+            letter: 'm',
+            color: Rgb(170, 220, 60),
+        },
+        FoodStyle {
+            emoji: '🥬',
+            letter: '&',
+            color: Rgb(170, 220, 60),
+        },
+    ],
+};
+
+// How much of its brightness a color keeps at night, in percent.
+const NIGHT_LIGHT: u16 = 45;
+
+const fn dim(c: u8) -> u8 {
+    (c as u16 * NIGHT_LIGHT / 100) as u8
+}
+
+const fn night_rgb(Rgb(r, g, b): Rgb) -> Rgb {
+    Rgb(dim(r), dim(g), dim(b))
+}
+
+const fn night_food(food: &FoodStyle) -> FoodStyle {
+    FoodStyle {
+        emoji: food.emoji,
+        letter: food.letter,
+        color: night_rgb(food.color),
+    }
+}
+
+const fn night(theme: &Theme) -> Theme {
+    let [(head, body), (other_head, other_body)] = theme.snakes;
+    Theme {
+        snakes: [
+            (night_rgb(head), night_rgb(body)),
+            (night_rgb(other_head), night_rgb(other_body)),
+        ],
+        wall: night_rgb(theme.wall),
+        food: [
+            night_food(&theme.food[0]),
+            night_food(&theme.food[1]),
+            night_food(&theme.food[2]),
+            night_food(&theme.food[3]),
+        ],
+    }
+}
+
+const CLASSIC_NIGHT: Theme = night(&CLASSIC);
+const WINTER_NIGHT: Theme = night(&WINTER);
+const HALLOWEEN_NIGHT: Theme = night(&HALLOWEEN);
+const RETRO_NIGHT: Theme = night(&RETRO);
+
+// What players can pick for their snake in the lobby before a game over the
+// network, by name, with its head and then its body.
+pub const COLORS: [(&str, Rgb, Rgb); 6] = [
+    ("green", Rgb(0, 255, 0), Rgb(255, 255, 255)),
+    ("blue", Rgb(0, 128, 255), Rgb(128, 200, 255)),
+    ("red", Rgb(255, 40, 40), Rgb(255, 200, 200)),
+    ("orange", Rgb(255, 140, 0), Rgb(140, 70, 0)),
+    ("purple", Rgb(170, 80, 255), Rgb(90, 40, 150)),
+    ("yellow", Rgb(255, 220, 0), Rgb(255, 240, 160)),
+];
+
+// A month and a day of it.
+type Date = (u32, u32);
+
+// When each seasonal theme is on, from the first date to the last, both
+// included. A season can run over into the next year.
+const SEASONS: [(ThemeName, Date, Date); 2] = [
+    (ThemeName::Winter, (12, 1), (1, 6)),
+    (ThemeName::Halloween, (10, 24), (10, 31)),
+];
+
+// The theme to draw with, set from the config before anything is drawn.
+// Classic until then, which is what exports get. Every thread has its own, so
+// sessions of `snake daemon` don't get each other's cheats and colors.
+thread_local! {
+    static CURRENT: std::cell::Cell<Option<ThemeName>> = const { std::cell::Cell::new(None) };
+    // The colors picked for both snakes, indexes into `COLORS`, which win
+    // over the theme's until they're cleared.
+    static PICKED: std::cell::Cell<Option<[usize; 2]>> = const { std::cell::Cell::new(None) };
+    static NIGHT: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+pub fn init(config: &crate::config::Config) {
+    set(config.theme.unwrap_or_else(|| season(today())));
+}
+
+pub fn set(name: ThemeName) {
+    CURRENT.set(Some(name));
+}
+
+pub fn pick(colors: Option<[usize; 2]>) {
+    PICKED.set(colors);
+}
+
+pub fn picked() -> Option<[usize; 2]> {
+    PICKED.get()
+}
+
+// Draws everything darker until it's day again.
+pub fn set_night(night: bool) {
+    NIGHT.set(night);
+}
+
+// `color` the way it looks now, for colors that aren't the theme's own.
+pub fn shade(color: Rgb) -> Rgb {
+    match NIGHT.get() {
+        true => night_rgb(color),
+        false => color,
+    }
+}
+
+// Head and body of the first snake, or of the second for anything else.
+pub fn snake(index: usize) -> (Rgb, Rgb) {
+    let index = index.min(1);
+    match picked() {
+        Some(picked) => {
+            let (_, head, body) = COLORS[picked[index] % COLORS.len()];
+            match NIGHT.get() {
+                true => (night_rgb(head), night_rgb(body)),
+                false => (head, body),
+            }
+        }
+        None => current().snakes[index],
+    }
+}
+
+pub fn current() -> &'static Theme {
+    match (CURRENT.get(), NIGHT.get()) {
+        (Some(ThemeName::Winter), false) => &WINTER,
+        (Some(ThemeName::Winter), true) => &WINTER_NIGHT,
+        (Some(ThemeName::Halloween), false) => &HALLOWEEN,
+        (Some(ThemeName::Halloween), true) => &HALLOWEEN_NIGHT,
+        (Some(ThemeName::Retro), false) => &RETRO,
+        (Some(ThemeName::Retro), true) => &RETRO_NIGHT,
+        (Some(ThemeName::Classic) | None, false) => &CLASSIC,
+        (Some(ThemeName::Classic) | None, true) => &CLASSIC_NIGHT,
+    }
+}
+
+fn season(today: Date) -> ThemeName {
+    SEASONS
+        .iter()
+        .find(|(_, first, last)| {
+            if first <= last {
+                *first <= today && today <= *last
+            } else {
+                today >= *first || today <= *last
+            }
+        })
+        .map_or(ThemeName::Classic, |(name, _, _)| *name)
+}
+
+// The month and day in UTC, which is close enough for a season.
+fn today() -> Date {
+    let days = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() / 86_400);
+    let (_, month, day) = civil_from_days(days);
+    (month, day)
+}
+
+// The year, month and day `days` after 1970, the way Howard Hinnant's
+// civil_from_days does it, with years starting in March so leap days come
+// last.
+pub fn civil_from_days(days: u64) -> (u64, u32, u32) {
+    let era = (days + 719_468) / 146_097;
+    let day_of_era = (days + 719_468) % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    (year, month as u32, day as u32)
+}
@@ -0,0 +1,211 @@
+// `snake tourney --bots <program>... [--games <n>]` plays every bot against
+// every other one, n versus games a pair without a terminal, and prints the
+// standings. Bots are programs that speak the bot protocol on stdin and
+// stdout: every tick they're sent the game as a line of JSON, the same as
+// `snake engine` answers with plus which snake is theirs, and answer with a
+// line saying where to turn:
+//
+//   > {"you": 0, "tick": 3, "board_size": [40, 20], "players": [...], "food": [...]}
+//   < {"direction": "up"}
+//
+// or {} to keep going. A bot that takes longer than TIMEOUT to answer keeps
+// going too. Every game starts the bots again, the pairs swap sides every
+// game, and game n of every pair is seeded n, so a rerun plays out the same
+// for bots that do. A game still going after MAX_TICKS is a draw.
+
+use crate::game::{Game, Input, Mode, MoveDirection};
+use crate::ratings::Outcome;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+
+const DEFAULT_GAMES: u32 = 10;
+const BOARD_SIZE: (u16, u16) = (40, 20);
+const MAX_TICKS: u32 = 10_000;
+const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
+#[derive(Serialize)]
+struct Turn<'a> {
+    you: usize,
+    #[serde(flatten)]
+    snapshot: &'a crate::net::Snapshot,
+}
+
+#[derive(Deserialize)]
+struct Answer {
+    direction: Option<MoveDirection>,
+}
+
+struct Bot {
+    child: std::process::Child,
+    stdin: std::process::ChildStdin,
+    answers: std::sync::mpsc::Receiver<String>,
+}
+
+impl Bot {
+    fn start(program: &str) -> Result<Self, String> {
+        // Without a / it would be looked for on PATH rather than here.
+        let program = match std::path::Path::new(program).is_file() && !program.contains('/') {
+            true => format!("./{}", program),
+            false => program.to_string(),
+        };
+        let mut child = std::process::Command::new(&program)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("{}: {}", program, e))?;
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = std::io::BufReader::new(child.stdout.take().unwrap());
+        let (sender, answers) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for line in stdout.lines() {
+                let Ok(line) = line else { return };
+                if sender.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Ok(Bot {
+            child,
+            stdin,
+            answers,
+        })
+    }
+
+    fn tell(&mut self, snapshot: &crate::net::Snapshot, you: usize) {
+        // Answers that came too late for the last tick aren't for this one.
+        while self.answers.try_recv().is_ok() {}
+
+        let line = serde_json::to_string(&Turn { you, snapshot }).unwrap();
+        // A bot that's gone just doesn't answer.
+        let _ = writeln!(self.stdin, "{}", line).and_then(|()| self.stdin.flush());
+    }
+
+    fn answer(&mut self, deadline: std::time::Instant) -> Option<MoveDirection> {
+        let timeout = deadline.saturating_duration_since(std::time::Instant::now());
+        let line = self.answers.recv_timeout(timeout).ok()?;
+        serde_json::from_str::<Answer>(&line).ok()?.direction
+    }
+}
+
+impl Drop for Bot {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[derive(Default)]
+struct Standing {
+    won: u32,
+    lost: u32,
+    drawn: u32,
+}
+
+impl Standing {
+    fn add(&mut self, outcome: Outcome) {
+        match outcome {
+            Outcome::Win => self.won += 1,
+            Outcome::Loss => self.lost += 1,
+            Outcome::Draw => self.drawn += 1,
+        }
+    }
+
+    // A point a win and half a draw.
+    fn points(&self) -> f64 {
+        f64::from(self.won) + f64::from(self.drawn) / 2.0
+    }
+}
+
+// The bots and how many games a pair plays, from everything after `tourney`.
+pub fn parse(args: &[&str]) -> Option<(Vec<String>, u32)> {
+    let mut bots = Vec::new();
+    let mut games = DEFAULT_GAMES;
+    let mut args = args.iter();
+    let mut in_bots = false;
+
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--bots" => in_bots = true,
+            "--games" => {
+                games = args.next()?.parse().ok().filter(|games| *games > 0)?;
+                in_bots = false;
+            }
+            bot if in_bots && !bot.starts_with("--") => bots.push(bot.to_string()),
+            _ => return None,
+        }
+    }
+
+    (bots.len() >= 2).then_some((bots, games))
+}
+
+// How it went for the first of `bots`.
+fn play(bots: [&str; 2], seed: u64) -> Result<Outcome, String> {
+    let mut game = Game::new(Mode::Versus, seed, BOARD_SIZE);
+    let mut players = [Bot::start(bots[0])?, Bot::start(bots[1])?];
+
+    while game.tick < MAX_TICKS {
+        let snapshot = crate::net::Snapshot::new(&game);
+        for (you, bot) in players.iter_mut().enumerate() {
+            bot.tell(&snapshot, you);
+        }
+        let deadline = std::time::Instant::now() + TIMEOUT;
+        for (you, bot) in players.iter_mut().enumerate() {
+            if let Some(direction) = bot.answer(deadline) {
+                game.handle_input(you, Input::Turn(direction));
+            }
+        }
+
+        if game.tick() {
+            return Ok(crate::outcome(&game.players, 0));
+        }
+    }
+    Ok(Outcome::Draw)
+}
+
+pub fn run(bots: &[String], games: u32) -> Result<(), String> {
+    let mut standings: Vec<Standing> = bots.iter().map(|_| Standing::default()).collect();
+
+    for first in 0..bots.len() {
+        for second in first + 1..bots.len() {
+            let mut pair = Standing::default();
+            for seed in 0..games {
+                let outcome = match seed % 2 {
+                    0 => play([&bots[first], &bots[second]], seed.into())?,
+                    _ => play([&bots[second], &bots[first]], seed.into())?.other(),
+                };
+                pair.add(outcome);
+                standings[first].add(outcome);
+                standings[second].add(outcome.other());
+            }
+            println!(
+                "{} against {}: {} won, {} lost, {} drawn",
+                bots[first], bots[second], pair.won, pair.lost, pair.drawn
+            );
+        }
+    }
+
+    let mut order: Vec<usize> = (0..bots.len()).collect();
+    order.sort_by(|a, b| standings[*b].points().total_cmp(&standings[*a].points()));
+    let width = bots.iter().map(|bot| bot.len()).max().unwrap_or(0).max(3);
+
+    println!();
+    println!(
+        "{:<width$}  {:>6}  {:>4}  {:>4}  {:>5}  {:>6}",
+        "Bot", "Played", "Won", "Lost", "Drawn", "Points"
+    );
+    for index in order {
+        let standing = &standings[index];
+        println!(
+            "{:<width$}  {:>6}  {:>4}  {:>4}  {:>5}  {:>6.1}",
+            bots[index],
+            standing.won + standing.lost + standing.drawn,
+            standing.won,
+            standing.lost,
+            standing.drawn,
+            standing.points()
+        );
+    }
+    Ok(())
+}
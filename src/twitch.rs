@@ -0,0 +1,124 @@
+// `snake twitch <channel>` lets a Twitch channel's chat steer, Twitch Plays
+// style. Chat is read anonymously over Twitch's IRC interface; every message
+// that is just "u", "d", "l" or "r" (or up, down, left, right) is a vote, and
+// the direction with the most votes wins at the end of every window.
+
+use crate::game::MoveDirection;
+use crate::random::{Entropy, Rng};
+use std::io::{BufRead, Write};
+
+pub const DEFAULT_SERVER: &str = "irc.chat.twitch.tv:6667";
+
+// Chat needs far longer to react than a keyboard.
+pub const VOTE_WINDOW: std::time::Duration = std::time::Duration::from_millis(1500);
+pub const MOVE_DURATION: std::time::Duration = std::time::Duration::from_millis(500);
+
+pub struct Vote {
+    pub user: String,
+    pub direction: MoveDirection,
+}
+
+// Joins `channel` and hands every vote to the returned channel, which closes
+// once the connection does.
+pub fn connect(server: &str, channel: &str) -> Result<std::sync::mpsc::Receiver<Vote>, String> {
+    let stream = std::net::TcpStream::connect(server).map_err(|e| e.to_string())?;
+    let mut writer = stream.try_clone().map_err(|e| e.to_string())?;
+
+    // Twitch lets anyone read chat with a justinfan nickname and no password.
+    let channel = channel.trim_start_matches('#').to_lowercase();
+    write!(
+        writer,
+        "NICK justinfan{}\r\nJOIN #{}\r\n",
+        10000 + Entropy.below(90000),
+        channel
+    )
+    .map_err(|e| e.to_string())?;
+
+    let (sender, votes) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::BufReader::new(stream).lines() {
+            let Ok(line) = line else {
+                return;
+            };
+
+            if let Some(server) = line.strip_prefix("PING ") {
+                if write!(writer, "PONG {}\r\n", server).is_err() {
+                    return;
+                }
+            } else if let Some(vote) = parse_vote(&line) {
+                if sender.send(vote).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(votes)
+}
+
+// :<user>!<user>@<user>.tmi.twitch.tv PRIVMSG #<channel> :<text>
+fn parse_vote(line: &str) -> Option<Vote> {
+    let (prefix, rest) = line.strip_prefix(':')?.split_once(' ')?;
+    let (command, rest) = rest.split_once(' ')?;
+    if command != "PRIVMSG" {
+        return None;
+    }
+    let (_, text) = rest.split_once(" :")?;
+
+    let direction = match text.trim().to_lowercase().as_str() {
+        "u" | "up" => MoveDirection::Up,
+        "d" | "down" => MoveDirection::Down,
+        "l" | "left" => MoveDirection::Left,
+        "r" | "right" => MoveDirection::Right,
+        _ => return None,
+    };
+    let user = prefix.split('!').next()?.to_string();
+
+    Some(Vote { user, direction })
+}
+
+// The votes in the current window. Everyone gets one vote, changing it counts
+// only the last one.
+#[derive(Default)]
+pub struct Votes {
+    votes: Vec<Vote>,
+}
+
+impl Votes {
+    pub fn add(&mut self, vote: Vote) {
+        self.votes.retain(|v| v.user != vote.user);
+        self.votes.push(vote);
+    }
+
+    // Votes for up, down, left and right.
+    pub fn tally(&self) -> [usize; 4] {
+        let mut tally = [0; 4];
+        for vote in &self.votes {
+            tally[index(vote.direction)] += 1;
+        }
+        tally
+    }
+
+    // Ends the window with the winning direction, if anyone voted. Ties go to
+    // whichever of them was voted for first.
+    pub fn close(&mut self) -> Option<MoveDirection> {
+        let tally = self.tally();
+        let most = *tally.iter().max()?;
+        let winner = self
+            .votes
+            .iter()
+            .map(|v| v.direction)
+            .find(|d| most > 0 && tally[index(*d)] == most);
+
+        self.votes.clear();
+        winner
+    }
+}
+
+// Chat only votes for the four straight ways.
+fn index(direction: MoveDirection) -> usize {
+    MoveDirection::STRAIGHT
+        .iter()
+        .position(|d| *d == direction)
+        .expect("votes go straight")
+}
@@ -0,0 +1,177 @@
+use std::io::Write;
+
+// Items are shown translated, but chosen by their English names.
+pub struct Menu {
+    items: Vec<String>,
+    selected: usize,
+}
+
+impl Menu {
+    pub fn new(items: &[&str]) -> Self {
+        Menu {
+            items: items.iter().map(|item| item.to_string()).collect(),
+            selected: 0,
+        }
+    }
+
+    // Returns the chosen item once enter is pressed.
+    pub fn handle_key(&mut self, key: u8) -> Option<&str> {
+        match key {
+            106 => self.selected = (self.selected + 1) % self.items.len(),
+            107 => self.selected = (self.selected + self.items.len() - 1) % self.items.len(),
+            13 => return Some(&self.items[self.selected]),
+            _ => {}
+        }
+
+        None
+    }
+
+    pub fn render(
+        &self,
+        screen: &mut dyn Write,
+        screen_size: &(u16, u16),
+    ) -> Result<(), std::io::Error> {
+        let top = (screen_size.1 / 2).saturating_sub(self.items.len() as u16 / 2);
+
+        for (index, item) in self.items.iter().enumerate() {
+            let item = crate::l10n::tr(item);
+            let label = if index == self.selected {
+                format!("> {} <", item)
+            } else {
+                item.to_string()
+            };
+
+            write_centered(screen, screen_size, top + index as u16, &label)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct TextInput {
+    value: String,
+    min_len: usize,
+    max_len: usize,
+    accepts: fn(char) -> bool,
+}
+
+impl TextInput {
+    pub fn new(min_len: usize, max_len: usize, accepts: fn(char) -> bool) -> Self {
+        TextInput {
+            value: String::new(),
+            min_len,
+            max_len,
+            accepts,
+        }
+    }
+
+    // Returns the entered text once enter is pressed with a long enough value.
+    pub fn handle_key(&mut self, key: u8) -> Option<String> {
+        match key {
+            13 if self.value.len() >= self.min_len => return Some(self.value.clone()),
+            127 | 8 => {
+                self.value.pop();
+            }
+            _ => {
+                let c = key as char;
+                if self.value.len() < self.max_len && (self.accepts)(c) {
+                    self.value.push(c);
+                }
+            }
+        }
+
+        None
+    }
+
+    pub fn render(&self, screen: &mut dyn Write, (x, y): (u16, u16)) -> Result<(), std::io::Error> {
+        let padding = "_".repeat(self.max_len - self.value.len());
+
+        write!(
+            screen,
+            "{}{}{}{}{}",
+            termion::cursor::Goto(x, y),
+            termion::style::Underline,
+            self.value,
+            termion::style::Reset,
+            padding,
+        )
+    }
+
+    pub fn width(&self) -> u16 {
+        self.max_len as u16
+    }
+}
+
+pub fn render_table(
+    screen: &mut dyn Write,
+    screen_size: &(u16, u16),
+    top: u16,
+    header: &[&str],
+    rows: &[Vec<String>],
+    highlight: Option<usize>,
+) -> Result<(), std::io::Error> {
+    let mut widths: Vec<u16> = header.iter().map(|h| text_width(h)).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(text_width(cell));
+        }
+    }
+
+    // Padded by hand, format! pads to a number of chars rather than columns.
+    let format_row = |cells: &mut dyn Iterator<Item = &str>| -> String {
+        cells
+            .zip(&widths)
+            .map(|(cell, width)| {
+                let padding = " ".repeat((width - text_width(cell)) as usize);
+                format!("{}{}", cell, padding)
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+    };
+
+    write_centered(
+        screen,
+        screen_size,
+        top,
+        &format_row(&mut header.iter().copied()),
+    )?;
+
+    for (index, row) in rows.iter().enumerate() {
+        let line = format_row(&mut row.iter().map(|c| c.as_str()));
+        let y = top + index as u16 + 1;
+
+        if highlight == Some(index) {
+            write!(screen, "{}", termion::style::Invert)?;
+            write_centered(screen, screen_size, y, &line)?;
+            write!(screen, "{}", termion::style::Reset)?;
+        } else {
+            write_centered(screen, screen_size, y, &line)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn write_centered(
+    screen: &mut dyn Write,
+    screen_size: &(u16, u16),
+    y: u16,
+    text: &str,
+) -> Result<(), std::io::Error> {
+    write!(
+        screen,
+        "{}{}",
+        termion::cursor::Goto(centered_x(screen_size, text_width(text)), y),
+        text
+    )
+}
+
+// How many columns `text` takes up on the screen, which isn't its length once
+// it has accents, emoji or wide letters in it.
+pub fn text_width(text: &str) -> u16 {
+    unicode_width::UnicodeWidthStr::width(text) as u16
+}
+
+pub fn centered_x(screen_size: &(u16, u16), width: u16) -> u16 {
+    (screen_size.0.saturating_sub(width) / 2).max(1)
+}
@@ -0,0 +1,86 @@
+// Usage statistics, only kept when `usage_stats` is on in the config: how
+// many times each kind of game was started and how long sessions last,
+// added up in usage.json in the data directory. Nothing else is kept, not
+// even when. With `usage_stats_url` set too, and a build with the `online`
+// feature, the totals are sent there at the end of every session:
+//
+//   POST <url>  {"sessions": 12, "average_session_seconds": 340,
+//                "games": {"play": 30, "twins": 2}}
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "online")]
+const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Totals {
+    pub sessions: u32,
+    pub session_seconds: u64,
+    pub games: std::collections::BTreeMap<String, u32>,
+}
+
+// The totals so far, all 0 when there are none.
+pub fn totals() -> Totals {
+    // Totals that can't be read start over rather than stopping the game.
+    std::fs::read_to_string(crate::paths::usage_file())
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub struct Usage {
+    totals: Totals,
+    started: std::time::Instant,
+    path: std::path::PathBuf,
+}
+
+impl Usage {
+    // Starts counting a session, or nothing when statistics are off.
+    pub fn start(config: &crate::config::Config) -> Option<Self> {
+        if !config.usage_stats {
+            return None;
+        }
+
+        Some(Usage {
+            totals: totals(),
+            started: std::time::Instant::now(),
+            path: crate::paths::usage_file(),
+        })
+    }
+
+    // Counts a game of `kind`, the menu item it was started from.
+    pub fn game(&mut self, kind: &str) {
+        let kind = kind.to_lowercase().replace(' ', "_");
+        *self.totals.games.entry(kind).or_default() += 1;
+    }
+
+    pub fn finish(mut self, config: &crate::config::Config) {
+        self.totals.sessions += 1;
+        self.totals.session_seconds += self.started.elapsed().as_secs();
+        if let Ok(contents) = serde_json::to_string_pretty(&self.totals) {
+            let _ = std::fs::write(&self.path, contents);
+        }
+
+        #[cfg(feature = "online")]
+        if let Some(url) = &config.usage_stats_url {
+            let _ = self.send(url);
+        }
+        #[cfg(not(feature = "online"))]
+        let _ = config;
+    }
+
+    #[cfg(feature = "online")]
+    fn send(&self, url: &str) -> Result<(), ureq::Error> {
+        let agent: ureq::Agent = ureq::Agent::config_builder()
+            .timeout_global(Some(TIMEOUT))
+            .build()
+            .into();
+        let report = serde_json::json!({
+            "sessions": self.totals.sessions,
+            "average_session_seconds": self.totals.session_seconds / self.totals.sessions as u64,
+            "games": self.totals.games,
+        });
+        agent.post(url).send_json(&report).map(|_| ())
+    }
+}
@@ -0,0 +1,204 @@
+// The binary encoding network games and spectators are sent in. Numbers are
+// varints, seven bits a byte with the high bit set on all but the last, so
+// the small ones that make up most of a game take a byte each. Signed ones
+// are zigzagged first, so small negative numbers are small too. Messages go
+// out as a varint length followed by that many bytes.
+//
+// Both sides start by sending MAGIC and VERSION. Anything else, or another
+// version, means the other side won't understand us, which is the same as
+// it not being there.
+
+use crate::game::{Coordinate, MoveDirection};
+use std::io::{Read, Write};
+
+pub const MAGIC: &[u8; 3] = b"snk";
+// Whenever the encoding of anything changes.
+pub const VERSION: u8 = 2;
+
+// Larger than any board's snapshot, so a length is a broken stream long
+// before it's allocated.
+const MAX_LENGTH: u64 = 1 << 20;
+
+#[derive(Default)]
+pub struct Encoder {
+    pub bytes: Vec<u8>,
+}
+
+impl Encoder {
+    pub fn byte(&mut self, b: u8) {
+        self.bytes.push(b);
+    }
+
+    pub fn number(&mut self, mut n: u64) {
+        while n >= 0x80 {
+            self.bytes.push(n as u8 | 0x80);
+            n >>= 7;
+        }
+        self.bytes.push(n as u8);
+    }
+
+    pub fn signed(&mut self, n: i64) {
+        self.number(((n << 1) ^ (n >> 63)) as u64);
+    }
+
+    pub fn text(&mut self, s: &str) {
+        self.number(s.len() as u64);
+        self.bytes.extend_from_slice(s.as_bytes());
+    }
+
+    pub fn direction(&mut self, direction: MoveDirection) {
+        self.byte(match direction {
+            MoveDirection::Up => 0,
+            MoveDirection::Down => 1,
+            MoveDirection::Left => 2,
+            MoveDirection::Right => 3,
+            MoveDirection::UpLeft => 4,
+            MoveDirection::UpRight => 5,
+            MoveDirection::DownLeft => 6,
+            MoveDirection::DownRight => 7,
+        });
+    }
+
+    pub fn coordinate(&mut self, Coordinate(x, y): Coordinate) {
+        self.number(x.into());
+        self.number(y.into());
+    }
+
+    // The first coordinate, then how far each is from the one before, which
+    // for a snake is a cell over in one direction.
+    pub fn path(&mut self, coordinates: &[Coordinate]) {
+        self.number(coordinates.len() as u64);
+        let mut last = Coordinate(0, 0);
+        for &coordinate in coordinates {
+            self.signed(i64::from(coordinate.0) - i64::from(last.0));
+            self.signed(i64::from(coordinate.1) - i64::from(last.1));
+            last = coordinate;
+        }
+    }
+}
+
+// Every method gives None on bytes that don't decode, from running out of
+// them to numbers that don't fit.
+pub struct Decoder<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Decoder { bytes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn byte(&mut self) -> Option<u8> {
+        let (&b, rest) = self.bytes.split_first()?;
+        self.bytes = rest;
+        Some(b)
+    }
+
+    pub fn number(&mut self) -> Option<u64> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.byte()?;
+            n |= u64::from(b & 0x7f).checked_shl(shift)?;
+            if b & 0x80 == 0 {
+                return Some(n);
+            }
+        }
+        None
+    }
+
+    pub fn small<T: TryFrom<u64>>(&mut self) -> Option<T> {
+        self.number()?.try_into().ok()
+    }
+
+    pub fn signed(&mut self) -> Option<i64> {
+        let n = self.number()?;
+        Some((n >> 1) as i64 ^ -((n & 1) as i64))
+    }
+
+    pub fn text(&mut self) -> Option<String> {
+        let length: usize = self.small()?;
+        if length > self.bytes.len() {
+            return None;
+        }
+        let (text, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        String::from_utf8(text.to_vec()).ok()
+    }
+
+    pub fn direction(&mut self) -> Option<MoveDirection> {
+        match self.byte()? {
+            0 => Some(MoveDirection::Up),
+            1 => Some(MoveDirection::Down),
+            2 => Some(MoveDirection::Left),
+            3 => Some(MoveDirection::Right),
+            4 => Some(MoveDirection::UpLeft),
+            5 => Some(MoveDirection::UpRight),
+            6 => Some(MoveDirection::DownLeft),
+            7 => Some(MoveDirection::DownRight),
+            _ => None,
+        }
+    }
+
+    pub fn coordinate(&mut self) -> Option<Coordinate> {
+        Some(Coordinate(self.small()?, self.small()?))
+    }
+
+    pub fn path(&mut self) -> Option<Vec<Coordinate>> {
+        let length: usize = self.small()?;
+        // Every coordinate takes at least two bytes.
+        if length > self.bytes.len() / 2 {
+            return None;
+        }
+        let mut last = Coordinate(0, 0);
+        let mut coordinates = Vec::with_capacity(length);
+        for _ in 0..length {
+            let x = i64::from(last.0) + self.signed()?;
+            let y = i64::from(last.1) + self.signed()?;
+            last = Coordinate(x.try_into().ok()?, y.try_into().ok()?);
+            coordinates.push(last);
+        }
+        Some(coordinates)
+    }
+}
+
+pub fn write_header(writer: &mut impl Write) -> Result<(), std::io::Error> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[VERSION])
+}
+
+// Whether the other side speaks this version.
+pub fn read_header(reader: &mut impl Read) -> bool {
+    let mut header = [0; 4];
+    reader.read_exact(&mut header).is_ok() && header[..3] == MAGIC[..] && header[3] == VERSION
+}
+
+pub fn write_frame(writer: &mut impl Write, payload: &[u8]) -> Result<(), std::io::Error> {
+    let mut frame = Encoder::default();
+    frame.number(payload.len() as u64);
+    frame.bytes.extend_from_slice(payload);
+    writer.write_all(&frame.bytes)
+}
+
+// The next message's bytes, or None at the end of the stream or on one too
+// long to be real.
+pub fn read_frame(reader: &mut impl Read) -> Option<Vec<u8>> {
+    let mut length = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut b = [0];
+        reader.read_exact(&mut b).ok()?;
+        length |= u64::from(b[0] & 0x7f) << shift;
+        if b[0] & 0x80 == 0 {
+            break;
+        }
+    }
+    if length > MAX_LENGTH {
+        return None;
+    }
+    let mut payload = vec![0; length as usize];
+    reader.read_exact(&mut payload).ok()?;
+    Some(payload)
+}
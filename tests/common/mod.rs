@@ -0,0 +1,59 @@
+// Plays headless games from a list of timed inputs, for tests that check
+// what the game looks like afterwards.
+
+#![allow(dead_code)]
+
+use snake::game::{Coordinate, Food, Game, Input, Mode, MoveDirection};
+use snake::level::Level;
+
+// An input given just before the game's tick reaches `tick + 1`, the same as
+// replays record them.
+#[derive(Copy, Clone, Debug)]
+pub struct InputEvent {
+    pub tick: u32,
+    pub player: usize,
+    pub input: Input,
+}
+
+pub fn turn(tick: u32, direction: MoveDirection) -> InputEvent {
+    InputEvent {
+        tick,
+        player: 0,
+        input: Input::Turn(direction),
+    }
+}
+
+pub fn elongate(tick: u32) -> InputEvent {
+    InputEvent {
+        tick,
+        player: 0,
+        input: Input::Elongate,
+    }
+}
+
+// A classic game on an empty board without food, so nothing random gets in
+// the way. The snake starts at 4,1 going right, its tail at 1,1.
+pub fn game(size: (u16, u16)) -> Game {
+    let mut level = Level::empty(size);
+    level.food = 0;
+    Game::with_level(Mode::Classic, 0, level)
+}
+
+pub fn place_food(game: &mut Game, location: Coordinate) {
+    game.food = vec![Food::new(location)];
+}
+
+// Plays `ticks` ticks with `events` given on theirs. Returns the tick the
+// game ended on, if it did.
+pub fn play(game: &mut Game, events: &[InputEvent], ticks: u32) -> Option<u32> {
+    for _ in 0..ticks {
+        let tick = game.tick;
+        for event in events.iter().filter(|e| e.tick == tick) {
+            game.handle_input(event.player, event.input);
+        }
+        if game.tick() {
+            return Some(game.tick);
+        }
+    }
+    None
+}
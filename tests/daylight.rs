@@ -0,0 +1,11 @@
+use snake::daylight::{night, DAY_TICKS, NIGHT_TICKS};
+
+#[test]
+fn nights_fall_after_every_day() {
+    assert!(!night(0));
+    assert!(!night(DAY_TICKS - 1));
+    assert!(night(DAY_TICKS));
+    assert!(night(DAY_TICKS + NIGHT_TICKS - 1));
+    assert!(!night(DAY_TICKS + NIGHT_TICKS));
+    assert!(night(2 * DAY_TICKS + NIGHT_TICKS));
+}
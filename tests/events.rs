@@ -0,0 +1,21 @@
+use snake::events::{choose, ALL};
+use snake::random::{Rng, Scripted};
+
+#[test]
+fn heavier_events_are_picked_more() {
+    let all: Vec<usize> = (0..ALL.len()).collect();
+    let total: u32 = ALL.iter().map(|e| e.weight()).sum();
+    let mut picked = vec![0; ALL.len()];
+    for roll in 0..total {
+        picked[choose(&all, &mut Scripted::new(&[roll]))] += 1;
+    }
+    let weights: Vec<u32> = ALL.iter().map(|e| e.weight()).collect();
+    assert_eq!(picked, weights);
+}
+
+#[test]
+fn one_to_pick_from_takes_nothing_random() {
+    let mut rng = Scripted::new(&[3, 7]);
+    assert_eq!(choose(&[2], &mut rng), 2);
+    assert_eq!(rng.below(10), 3);
+}
@@ -0,0 +1,701 @@
+mod common;
+
+use common::{elongate, game, place_food, play, turn};
+use snake::game::{
+    Board, Coordinate, Event, Food, FoodKind, FoodPlacement, Game, Letters, Mode, MoveDirection,
+    BRIDGE_TICKS, FRENZY_FOOD, LETTER_BONUS,
+};
+use snake::level::{
+    self, Door, FoodWeight, Gate, Level, Mask, Terrain, TerrainKind, Topology, Zone,
+};
+use snake::random::Scripted;
+
+#[test]
+fn keeps_going_without_input() {
+    let mut game = game((20, 10));
+    assert_eq!(play(&mut game, &[], 3), None);
+    assert_eq!(game.players[0].head(), Coordinate(7, 1));
+    assert_eq!(game.players[0].segments.len(), 4);
+}
+
+#[test]
+fn turns() {
+    let mut game = game((20, 10));
+    let events = [turn(0, MoveDirection::Down), turn(2, MoveDirection::Left)];
+    assert_eq!(play(&mut game, &events, 3), None);
+    assert_eq!(game.players[0].head(), Coordinate(3, 3));
+    assert_eq!(game.players[0].move_direction, MoveDirection::Left);
+}
+
+#[test]
+fn ignores_reversing() {
+    let mut game = game((20, 10));
+    assert_eq!(play(&mut game, &[turn(0, MoveDirection::Left)], 1), None);
+    assert_eq!(game.players[0].head(), Coordinate(5, 1));
+    assert_eq!(game.players[0].move_direction, MoveDirection::Right);
+}
+
+#[test]
+fn eating_scores_and_grows() {
+    let mut game = game((20, 10));
+    place_food(&mut game, Coordinate(5, 1));
+    assert_eq!(play(&mut game, &[], 1), None);
+    assert_eq!(game.players[0].score, 1);
+    assert!(game.events.contains(&Event::FoodEaten {
+        player: 0,
+        food: 0,
+        points: 1
+    }));
+    assert_ne!(game.food[0].location, Coordinate(5, 1));
+
+    play(&mut game, &[], 1);
+    assert_eq!(game.players[0].segments.len(), 5);
+    play(&mut game, &[], 1);
+    assert_eq!(game.players[0].segments.len(), 5);
+}
+
+#[test]
+fn food_kinds_score_differently() {
+    let mut game = game((20, 10));
+    place_food(&mut game, Coordinate(5, 1));
+    game.food[0].kind = FoodKind::Mouse;
+    play(&mut game, &[], 1);
+    assert_eq!(game.players[0].score, 3);
+    // Still grows by one.
+    play(&mut game, &[], 2);
+    assert_eq!(game.players[0].segments.len(), 5);
+
+    let mut game = common::game((20, 10));
+    game.set_food_variants(false);
+    place_food(&mut game, Coordinate(5, 1));
+    play(&mut game, &[], 1);
+    assert_eq!(game.food[0].kind, FoodKind::Apple);
+}
+
+#[test]
+fn elongating_grows_a_cell_a_move() {
+    let mut game = game((20, 10));
+    let events = [elongate(0), elongate(0), elongate(1)];
+    play(&mut game, &events, 4);
+    assert_eq!(game.players[0].segments.len(), 7);
+    // The tail stayed where it was while the snake grew.
+    assert_eq!(game.players[0].segments.back(), Some(&Coordinate(2, 1)));
+}
+
+#[test]
+fn dies_at_the_edge() {
+    let mut game = game((10, 5));
+    // From 4,1 the edge is 7 moves away, the 7th is off the board.
+    assert_eq!(play(&mut game, &[], 100), Some(7));
+    assert!(!game.players[0].alive);
+    assert!(game.events.contains(&Event::Died { player: 0 }));
+    assert_eq!(game.players[0].head(), Coordinate(10, 1));
+}
+
+#[test]
+fn dies_at_the_top_edge() {
+    let mut game = game((10, 5));
+    assert_eq!(play(&mut game, &[turn(0, MoveDirection::Up)], 100), Some(1));
+    assert!(!game.players[0].alive);
+}
+
+#[test]
+fn turning_away_from_the_edge_survives() {
+    let mut game = game((10, 5));
+    assert_eq!(play(&mut game, &[turn(6, MoveDirection::Down)], 9), None);
+    assert_eq!(game.players[0].head(), Coordinate(10, 4));
+}
+
+#[test]
+fn dies_on_itself() {
+    let mut game = game((20, 10));
+    let mut events = vec![elongate(0), elongate(0)];
+    events.extend([
+        turn(1, MoveDirection::Down),
+        turn(2, MoveDirection::Left),
+        turn(3, MoveDirection::Up),
+    ]);
+    assert_eq!(play(&mut game, &events, 10), Some(4));
+    assert!(game.events.contains(&Event::Died { player: 0 }));
+}
+
+#[test]
+fn dies_on_level_walls() {
+    let mut game = game((20, 10));
+    game.level.walls.push(Coordinate(8, 1));
+    assert_eq!(play(&mut game, &[], 100), Some(4));
+    assert!(!game.players[0].alive);
+}
+
+#[test]
+fn diagonal_turns_only_work_in_diagonal_games() {
+    let mut game = game((20, 10));
+    play(&mut game, &[turn(0, MoveDirection::DownRight)], 2);
+    assert_eq!(game.players[0].head(), Coordinate(6, 1));
+
+    let mut diagonal = common::game((20, 10));
+    diagonal.diagonal = true;
+    play(&mut diagonal, &[turn(0, MoveDirection::DownRight)], 2);
+    assert_eq!(diagonal.players[0].head(), Coordinate(6, 3));
+}
+
+#[test]
+fn diagonal_moves_cant_squeeze_between_walls() {
+    let mut game = game((20, 10));
+    game.diagonal = true;
+    game.level.walls.push(Coordinate(4, 2));
+    assert_eq!(
+        play(&mut game, &[turn(0, MoveDirection::DownRight)], 1),
+        None
+    );
+    assert_eq!(game.players[0].head(), Coordinate(5, 2));
+
+    let mut game = common::game((20, 10));
+    game.diagonal = true;
+    game.level
+        .walls
+        .extend([Coordinate(4, 2), Coordinate(5, 1)]);
+    assert_eq!(
+        play(&mut game, &[turn(0, MoveDirection::DownRight)], 1),
+        Some(1)
+    );
+}
+
+#[test]
+fn diagonal_moves_cant_cross_a_diagonal_bend() {
+    let mut game = game((20, 10));
+    game.diagonal = true;
+    let events = [
+        elongate(0),
+        elongate(0),
+        turn(0, MoveDirection::DownRight),
+        turn(1, MoveDirection::Down),
+        turn(2, MoveDirection::Left),
+        turn(3, MoveDirection::Up),
+        turn(4, MoveDirection::UpRight),
+    ];
+    // 5,1 is free, but the snake went from 4,1 to 5,2 across it.
+    assert_eq!(play(&mut game, &events, 10), Some(5));
+    assert_eq!(game.players[0].head(), Coordinate(4, 2));
+}
+
+#[test]
+fn masks_fit_the_board() {
+    let size = (20, 10);
+    assert!(!Mask::Circle.contains(&Coordinate(1, 1), size));
+    assert!(Mask::Circle.contains(&Coordinate(1, 5), size));
+    assert!(!Mask::Cross.contains(&Coordinate(3, 2), size));
+    assert!(Mask::Cross.contains(&Coordinate(10, 1), size));
+    assert!(!Mask::Donut.contains(&Coordinate(10, 5), size));
+    assert!(Mask::Donut.contains(&Coordinate(10, 2), size));
+}
+
+#[test]
+fn dies_outside_the_mask() {
+    let mut level = Level::empty((20, 10));
+    level.mask = Some(Mask::Donut);
+    assert!(level.validate().is_ok());
+    let mut game = Game::with_level(Mode::Classic, 0, level);
+    assert_eq!(game.players[0].head(), Coordinate(12, 2));
+    assert_eq!(play(&mut game, &[], 100), Some(6));
+    assert!(!game.players[0].alive);
+}
+
+#[test]
+fn wrapping_edges_come_back_round() {
+    let board = |topology| Board {
+        size: (10, 5),
+        topology,
+    };
+    let corner = Coordinate(10, 5);
+    assert_eq!(
+        board(Topology::Toroidal).step(corner, MoveDirection::Down),
+        Coordinate(10, 1)
+    );
+    assert_eq!(
+        board(Topology::Cylinder).step(corner, MoveDirection::Right),
+        Coordinate(1, 5)
+    );
+    assert!(!board(Topology::Cylinder)
+        .step(corner, MoveDirection::Down)
+        .on_board((10, 5)));
+    assert!(!board(Topology::Bounded)
+        .step(corner, MoveDirection::Right)
+        .on_board((10, 5)));
+}
+
+#[test]
+fn goes_through_wrapping_edges() {
+    let mut level = Level::empty((10, 5));
+    level.food = 1;
+    level.topology = Topology::Cylinder;
+    let mut game = Game::with_level(Mode::Classic, 0, level);
+    assert_eq!(play(&mut game, &[], 8), None);
+    assert_eq!(game.players[0].head(), Coordinate(2, 1));
+    assert_eq!(play(&mut game, &[turn(8, MoveDirection::Up)], 100), Some(9));
+}
+
+#[test]
+fn spare_lives_start_over() {
+    let mut game = game((10, 5));
+    game.set_lives(2);
+    assert_eq!(play(&mut game, &[], 7), None);
+    assert!(game.events.contains(&Event::LostLife { player: 0 }));
+    assert_eq!(game.players[0].lives, 1);
+    assert_eq!(game.players[0].head(), Coordinate(4, 1));
+    assert_eq!(play(&mut game, &[], 100), Some(14));
+}
+
+#[test]
+fn bumper_stops_in_front_of_the_edge() {
+    let mut game = game((10, 5));
+    game.bumper = true;
+    assert_eq!(play(&mut game, &[], 10), None);
+    assert!(game.events.contains(&Event::Bumped { player: 0 }));
+    assert_eq!(game.players[0].head(), Coordinate(10, 1));
+    assert_eq!(play(&mut game, &[turn(10, MoveDirection::Down)], 1), None);
+    assert_eq!(game.players[0].head(), Coordinate(10, 2));
+}
+
+#[test]
+fn grace_waits_for_a_late_turn() {
+    let mut game = game((10, 5));
+    game.grace = 2;
+    assert_eq!(play(&mut game, &[turn(8, MoveDirection::Down)], 9), None);
+    assert_eq!(game.players[0].head(), Coordinate(10, 2));
+}
+
+#[test]
+fn feasts_are_eaten_from_any_of_their_cells() {
+    let mut game = game((20, 10));
+    place_food(&mut game, Coordinate(3, 2));
+    game.food[0].kind = FoodKind::Feast;
+    assert!(game.food[0].covers(Coordinate(4, 3)));
+
+    play(&mut game, &[turn(0, MoveDirection::Down)], 1);
+    assert_eq!(game.players[0].head(), Coordinate(4, 2));
+    assert_eq!(game.players[0].score, 10);
+    assert!(game.food.iter().all(|f| !f.covers(Coordinate(4, 2))));
+}
+
+#[test]
+fn nearest_food_counts_every_cell_of_a_feast() {
+    let mut game = game((20, 10));
+    game.food = vec![Food::new(Coordinate(10, 1)), Food::new(Coordinate(4, 6))];
+    game.food[1].kind = FoodKind::Feast;
+    // The head is at 4,1, five moves from the feast's top row.
+    assert_eq!(
+        game.players[0].nearest_food(&game.food),
+        Some(Coordinate(4, 6))
+    );
+    game.food.pop();
+    assert_eq!(
+        game.players[0].nearest_food(&game.food),
+        Some(Coordinate(10, 1))
+    );
+}
+
+#[test]
+fn food_goes_where_the_numbers_say() {
+    // Free cells are counted down the columns from the top left, and the
+    // snake takes up the top row's first four. Food kinds get the same
+    // numbers, where 0 is a mouse.
+    let rng = Box::new(Scripted::new(&[0]));
+    let game = Game::with_rng(
+        Mode::Classic,
+        0,
+        Level::empty((20, 10)),
+        FoodPlacement::FreeCells,
+        rng,
+    );
+    assert_eq!(game.food[0].location, Coordinate(1, 2));
+    assert_eq!(game.food[0].kind, FoodKind::Mouse);
+}
+
+#[test]
+fn food_goes_in_the_last_cell_before_the_board_is_full() {
+    // The only free cell next to the snake is in the last column and row, and
+    // the snake keeps its tail to fill the board up.
+    let rng = Box::new(Scripted::new(&[0]));
+    let mut game = Game::with_rng(
+        Mode::Classic,
+        0,
+        Level::empty((5, 1)),
+        FoodPlacement::FreeCells,
+        rng,
+    );
+    assert_eq!(game.food[0].location, Coordinate(5, 1));
+    game.grow(0);
+    assert!(game.tick());
+    assert_eq!(game.players[0].score, game.food[0].kind.points());
+    assert!(game.events.contains(&Event::BoardFull));
+}
+
+#[test]
+fn levels_can_have_food_for_every_cell_but_the_snake() {
+    // The snake starts 4 cells long.
+    let mut level = Level::empty((8, 4));
+    level.food = 8 * 4 - 4;
+    assert!(level.validate().is_ok());
+    level.food += 1;
+    assert!(level.validate().is_err());
+}
+
+#[test]
+fn parts_of_a_board_fit_each_side() {
+    assert_eq!(level::part_of((40, 5), 1, 2), (20, 5));
+    assert_eq!(level::part_of((8, 30), 1, 2), (8, 15));
+    assert_eq!(level::part_of((80, 24), 3, 4), (60, 18));
+    assert_eq!(level::part_of((12, 7), 1, 2), (10, 6));
+}
+
+#[test]
+fn later_terrain_covers_earlier() {
+    let mut level = Level::empty((20, 10));
+    level.terrain = vec![
+        Terrain {
+            zone: Zone(Coordinate(1, 5), Coordinate(20, 5)),
+            kind: TerrainKind::Boost,
+        },
+        Terrain {
+            zone: Zone(Coordinate(10, 5), Coordinate(10, 5)),
+            kind: TerrainKind::Mud,
+        },
+    ];
+    assert!(level.validate().is_ok());
+    assert_eq!(
+        level.terrain_at(&Coordinate(9, 5)),
+        Some(TerrainKind::Boost)
+    );
+    assert_eq!(level.terrain_at(&Coordinate(10, 5)), Some(TerrainKind::Mud));
+    assert_eq!(level.terrain_at(&Coordinate(10, 6)), None);
+
+    level.terrain[1].zone = Zone(Coordinate(10, 5), Coordinate(21, 5));
+    assert!(level.validate().is_err());
+}
+
+#[test]
+fn turns_do_nothing_on_ice() {
+    let mut game = game((20, 10));
+    game.level.terrain.push(Terrain {
+        zone: Zone(Coordinate(4, 1), Coordinate(6, 1)),
+        kind: TerrainKind::Ice,
+    });
+    // Turned on 4,1 and 5,1, then once off the ice at 7,1.
+    let events = [
+        turn(0, MoveDirection::Down),
+        turn(1, MoveDirection::Down),
+        turn(3, MoveDirection::Down),
+    ];
+    assert_eq!(play(&mut game, &events, 4), None);
+    assert_eq!(game.players[0].head(), Coordinate(7, 2));
+}
+
+fn terrain(kind: TerrainKind, cell: Coordinate) -> Terrain {
+    Terrain {
+        zone: Zone(cell, cell),
+        kind,
+    }
+}
+
+#[test]
+fn water_needs_a_bridge_power_up() {
+    let mut game = game((20, 10));
+    game.level
+        .terrain
+        .push(terrain(TerrainKind::Water, Coordinate(8, 1)));
+    assert_eq!(play(&mut game, &[], 100), Some(4));
+
+    let mut game = common::game((20, 10));
+    game.level
+        .terrain
+        .push(terrain(TerrainKind::Water, Coordinate(8, 1)));
+    game.level.bridge_power_ups.push(Coordinate(6, 1));
+    assert_eq!(play(&mut game, &[], 5), None);
+    assert_eq!(game.players[0].head(), Coordinate(9, 1));
+    assert!(game.level.bridge_power_ups.is_empty());
+    assert_eq!(game.players[0].bridging, BRIDGE_TICKS - 3);
+}
+
+#[test]
+fn food_stays_off_water() {
+    let mut level = Level::empty((8, 4));
+    level.terrain.push(Terrain {
+        zone: Zone(Coordinate(1, 2), Coordinate(8, 4)),
+        kind: TerrainKind::Water,
+    });
+    assert!(!level.allows_food(&Coordinate(5, 3)));
+    // The snake starts on the top row, which leaves 4 cells.
+    level.food = 4;
+    assert!(level.validate().is_ok());
+    level.food = 5;
+    assert!(level.validate().is_err());
+}
+
+#[test]
+fn bridges_cross_over_the_snake() {
+    let events = [
+        elongate(0),
+        elongate(0),
+        elongate(0),
+        elongate(0),
+        turn(0, MoveDirection::Down),
+        turn(2, MoveDirection::Right),
+        turn(3, MoveDirection::Up),
+        turn(4, MoveDirection::Left),
+    ];
+    let mut game = game((20, 10));
+    assert_eq!(play(&mut game, &events, 6), Some(5));
+
+    let mut game = common::game((20, 10));
+    game.level
+        .terrain
+        .push(terrain(TerrainKind::Bridge, Coordinate(4, 2)));
+    assert_eq!(play(&mut game, &events, 6), None);
+    assert_eq!(game.players[0].head(), Coordinate(3, 2));
+    assert_eq!(game.players[0].layers(&Coordinate(4, 2)), 2);
+}
+
+#[test]
+fn gates_only_let_snakes_through_one_way() {
+    let gate = |direction| Gate {
+        cell: Coordinate(8, 1),
+        direction,
+    };
+    let mut level = Level::empty((20, 10));
+    level.gates.push(gate(MoveDirection::Right));
+    assert!(level.validate().is_ok());
+    level.gates.push(gate(MoveDirection::Left));
+    assert!(level.validate().is_err());
+
+    let mut game = game((20, 10));
+    game.level.gates.push(gate(MoveDirection::Right));
+    assert_eq!(play(&mut game, &[], 5), None);
+    assert_eq!(game.players[0].head(), Coordinate(9, 1));
+
+    let mut game = common::game((20, 10));
+    game.level.gates.push(gate(MoveDirection::Left));
+    assert_eq!(play(&mut game, &[], 100), Some(4));
+    assert_eq!(game.players[0].head(), Coordinate(7, 1));
+}
+
+#[test]
+fn keys_open_their_doors() {
+    let mut game = game((20, 10));
+    game.level.doors.push(Door {
+        key: Coordinate(6, 1),
+        cells: vec![Coordinate(9, 1)],
+    });
+    assert!(game.level.is_wall(&Coordinate(9, 1)));
+    assert_eq!(play(&mut game, &[], 2), None);
+    assert!(game.events.contains(&Event::DoorOpened { player: 0 }));
+    assert!(game.level.doors.is_empty());
+    assert_eq!(play(&mut game, &[], 4), None);
+    assert_eq!(game.players[0].head(), Coordinate(10, 1));
+}
+
+#[test]
+fn spelling_the_word_gets_a_bonus_and_a_life() {
+    let mut game = game((20, 10));
+    game.letters = Some(Letters {
+        left: vec![('S', Coordinate(5, 1)), ('N', Coordinate(6, 1))],
+        expires: 100,
+    });
+    assert_eq!(play(&mut game, &[], 1), None);
+    assert!(game.events.contains(&Event::LetterEaten {
+        player: 0,
+        letter: 'S'
+    }));
+    assert_eq!(play(&mut game, &[], 1), None);
+    assert!(game.events.contains(&Event::WordSpelled { player: 0 }));
+    assert!(game.letters.is_none());
+    assert_eq!(game.players[0].score, LETTER_BONUS);
+    assert_eq!(game.players[0].lives, 2);
+}
+
+#[test]
+fn letters_out_of_order_are_lost() {
+    let mut game = game((20, 10));
+    game.letters = Some(Letters {
+        left: vec![('S', Coordinate(9, 1)), ('N', Coordinate(5, 1))],
+        expires: 100,
+    });
+    assert_eq!(play(&mut game, &[], 1), None);
+    assert!(game.events.contains(&Event::LettersLost));
+    assert!(game.letters.is_none());
+    assert_eq!(game.players[0].score, 0);
+}
+
+#[test]
+fn weighted_cells_take_up_more_numbers() {
+    // 90 free cells come before 10,5, which takes up the next 100 numbers.
+    let mut level = Level::empty((20, 10));
+    level.food = 1;
+    level.food_weights = vec![FoodWeight {
+        zone: Zone(Coordinate(10, 5), Coordinate(10, 5)),
+        weight: 100,
+    }];
+    let food = |number| {
+        let rng = Box::new(Scripted::new(&[number]));
+        let game = Game::with_rng(
+            Mode::Classic,
+            0,
+            level.clone(),
+            FoodPlacement::FreeCells,
+            rng,
+        );
+        game.food[0].location
+    };
+    assert_eq!(food(89), Coordinate(10, 4));
+    assert_eq!(food(90), Coordinate(10, 5));
+    assert_eq!(food(189), Coordinate(10, 5));
+    assert_eq!(food(190), Coordinate(10, 6));
+}
+
+// A game where a line of five food turns up under the snake right away, and
+// again whenever there isn't one.
+fn formation_game(size: (u16, u16)) -> Game {
+    let mut level = Level::empty(size);
+    level.food = 0;
+    let rng = Box::new(Scripted::new(&[0]));
+    let mut game = Game::with_rng(Mode::Classic, 0, level, FoodPlacement::FreeCells, rng);
+    // Which would start right away too.
+    game.random_events.clear();
+    play(&mut game, &[], 1);
+    game
+}
+
+#[test]
+fn eating_a_whole_formation_scores_a_bonus() {
+    let mut game = formation_game((20, 10));
+    let line: Vec<Coordinate> = game.food.iter().map(|f| f.location).collect();
+    assert_eq!(line, (1..=5).map(|x| Coordinate(x, 2)).collect::<Vec<_>>());
+
+    let events = [turn(1, MoveDirection::Down), turn(2, MoveDirection::Left)];
+    play(&mut game, &events, 5);
+    assert!(game.food.iter().all(|f| !line.contains(&f.location)));
+    assert_eq!(game.players[0].score, 10);
+    assert!(game.events.contains(&Event::FormationEaten {
+        player: 0,
+        bonus: 5
+    }));
+}
+
+#[test]
+fn formations_expire() {
+    let mut game = formation_game((120, 10));
+    play(&mut game, &[], 99);
+    assert_eq!(game.food.len(), 5);
+    play(&mut game, &[], 1);
+    assert!(game.events.contains(&Event::FormationExpired));
+    assert!(game.food.iter().all(|f| f.expires == Some(201)));
+}
+
+#[test]
+fn a_saved_game_carries_on_the_same() {
+    let mut game = Game::new(Mode::Versus, 11, (30, 12));
+    let events = [turn(2, MoveDirection::Down), elongate(4)];
+    assert_eq!(play(&mut game, &events, 6), None);
+
+    let saved = serde_json::to_string(&game).unwrap();
+    let mut loaded: Game = serde_json::from_str(&saved).unwrap();
+    assert_eq!(serde_json::to_string(&loaded).unwrap(), saved);
+
+    // Food only shows the random numbers were where they were once some is
+    // eaten, so both steer into whatever is nearest.
+    for _ in 0..40 {
+        for game in [&mut game, &mut loaded] {
+            let head = game.players[0].head();
+            if let Some(food) = game
+                .food
+                .iter()
+                .min_by_key(|f| f.location.0.abs_diff(head.0) + f.location.1.abs_diff(head.1))
+            {
+                let direction = match (food.location, head) {
+                    (Coordinate(x, _), Coordinate(hx, _)) if x > hx => MoveDirection::Right,
+                    (Coordinate(x, _), Coordinate(hx, _)) if x < hx => MoveDirection::Left,
+                    (Coordinate(_, y), Coordinate(_, hy)) if y > hy => MoveDirection::Down,
+                    _ => MoveDirection::Up,
+                };
+                game.handle_input(0, snake::game::Input::Turn(direction));
+            }
+            game.tick();
+        }
+    }
+    assert!(game.players[0].score > 0);
+    assert_eq!(
+        serde_json::to_string(&loaded).unwrap(),
+        serde_json::to_string(&game).unwrap()
+    );
+}
+
+#[test]
+fn streams_follow_from_the_seed() {
+    let numbers = |seed: u64, n: u64| -> Vec<u32> {
+        let mut stream = Game::new(Mode::Classic, seed, (20, 10)).stream(n);
+        (0..8).map(|_| stream.below(1000)).collect()
+    };
+    assert_eq!(numbers(5, 0), numbers(5, 0));
+    assert_ne!(numbers(5, 0), numbers(5, 1));
+    assert_ne!(numbers(5, 0), numbers(6, 0));
+}
+
+#[test]
+fn frenzy_food_is_worth_double() {
+    let mut game = game((20, 10));
+    game.food = vec![Food {
+        expires: Some(100),
+        frenzy: true,
+        ..Food::new(Coordinate(5, 1))
+    }];
+    assert_eq!(play(&mut game, &[], 1), None);
+    assert_eq!(game.players[0].score, 2);
+    assert!(game.food.is_empty());
+}
+
+#[test]
+fn frenzy_food_goes_when_the_frenzy_does() {
+    let mut game = game((20, 10));
+    game.start_random_event(0);
+    assert!(game
+        .events
+        .contains(&Event::RandomEventStarted { index: 0 }));
+    assert_eq!(game.food.len(), FRENZY_FOOD);
+    assert!(game.food.iter().all(|f| f.frenzy));
+    // Out of the way of the snake, which goes down the side.
+    game.food.retain(|f| f.location.0 > 5);
+    let left = game.food.len();
+    assert_eq!(play(&mut game, &[turn(0, MoveDirection::Down)], 1), None);
+    assert_eq!(game.food.len(), left);
+    // Before it runs into the bottom.
+    game.random_event.as_mut().unwrap().ends = game.tick + 1;
+    assert_eq!(play(&mut game, &[], 1), None);
+    assert!(game.events.contains(&Event::RandomEventEnded { index: 0 }));
+    assert!(game.food.is_empty());
+    assert!(game.random_event.is_none());
+}
+
+#[test]
+fn a_new_random_event_ends_the_last() {
+    let mut game = game((20, 10));
+    game.start_random_event(0);
+    game.start_random_event(1);
+    assert!(game.events.contains(&Event::RandomEventEnded { index: 0 }));
+    assert!(game.food.is_empty());
+    assert_eq!(game.random_event.map(|e| e.index), Some(1));
+}
+
+#[test]
+fn the_wind_pushes_snakes_along() {
+    let push = |wind| {
+        let mut game = game((20, 10));
+        game.wind = Some(wind);
+        assert_eq!(play(&mut game, &[], 4), None);
+        game.players[0].head()
+    };
+    assert_eq!(push(MoveDirection::Right), Coordinate(9, 1));
+    assert_eq!(push(MoveDirection::Down), Coordinate(8, 2));
+    // Not into the wind, or off the board.
+    assert_eq!(push(MoveDirection::Left), Coordinate(8, 1));
+    assert_eq!(push(MoveDirection::Up), Coordinate(8, 1));
+}
@@ -0,0 +1,64 @@
+use snake::game::Coordinate;
+use snake::hex::{step, HexDirection, HexGame};
+use snake::random::Scripted;
+
+const DIRECTIONS: [HexDirection; 6] = [
+    HexDirection::UpLeft,
+    HexDirection::UpRight,
+    HexDirection::Left,
+    HexDirection::Right,
+    HexDirection::DownLeft,
+    HexDirection::DownRight,
+];
+
+#[test]
+fn going_back_ends_up_where_it_started() {
+    for start in [Coordinate(5, 5), Coordinate(5, 6)] {
+        for direction in DIRECTIONS {
+            assert_eq!(step(step(start, direction), direction.opposite()), start);
+        }
+    }
+}
+
+#[test]
+fn neighbours_depend_on_the_row() {
+    assert_eq!(
+        step(Coordinate(5, 5), HexDirection::UpLeft),
+        Coordinate(4, 4)
+    );
+    assert_eq!(
+        step(Coordinate(5, 5), HexDirection::DownRight),
+        Coordinate(5, 6)
+    );
+    assert_eq!(
+        step(Coordinate(5, 6), HexDirection::UpLeft),
+        Coordinate(5, 5)
+    );
+    assert_eq!(
+        step(Coordinate(5, 6), HexDirection::DownRight),
+        Coordinate(6, 7)
+    );
+}
+
+#[test]
+fn eats_and_grows() {
+    // The second free cell on a board a row high is right in front of the
+    // snake.
+    let mut game = HexGame::with_rng((10, 1), Box::new(Scripted::new(&[1, 0])));
+    assert_eq!(game.food, Coordinate(6, 1));
+    assert!(!game.tick());
+    assert_eq!(game.score, 1);
+    assert!(!game.tick());
+    assert_eq!(game.segments.len(), 5);
+}
+
+#[test]
+fn dies_at_the_edge() {
+    let mut game = HexGame::with_rng((10, 4), Box::new(Scripted::new(&[0])));
+    // From the second row, up right is still on the board once.
+    game.turn(HexDirection::UpRight);
+    assert!(!game.tick());
+    assert_eq!(game.head(), Coordinate(6, 1));
+    assert!(game.tick());
+    assert!(!game.alive);
+}
@@ -0,0 +1,37 @@
+use snake::game::{Coordinate, MoveDirection};
+use snake::import::{import, Source};
+
+#[test]
+fn nsnake_levels() {
+    let contents = "name=Box\nauthor=someone\ndate=2012\nstart\n##########\n#     @  #\n#        #\n##########\nend\n";
+    assert_eq!(Source::detect(contents), Source::Nsnake);
+
+    let level = import(contents).unwrap();
+    assert_eq!(level.name.as_deref(), Some("Box"));
+    assert_eq!(level.author.as_deref(), Some("someone"));
+    assert_eq!(level.size, (10, 4));
+    assert_eq!(level.walls.len(), 24);
+    assert_eq!(level.spawns[0].head, Coordinate(7, 2));
+    assert_eq!(level.spawns[0].direction, MoveDirection::Right);
+    assert!(level.validate().is_ok());
+}
+
+#[test]
+fn nibbles_layouts_have_two_rows_to_a_line() {
+    let contents = "█▀▀▀▀▀▀▀▀█\n█        █\n█▄▄▄▄▄▄▄▄█\n";
+    assert_eq!(Source::detect(contents), Source::Nibbles);
+
+    let level = import(contents).unwrap();
+    assert_eq!(level.size, (10, 6));
+    assert!(level.is_wall(&Coordinate(5, 1)));
+    assert!(!level.is_wall(&Coordinate(5, 2)));
+    assert!(!level.is_wall(&Coordinate(5, 5)));
+    assert!(level.is_wall(&Coordinate(5, 6)));
+    assert!(level.is_wall(&Coordinate(1, 3)) && level.is_wall(&Coordinate(10, 4)));
+}
+
+#[test]
+fn empty_maps_are_refused() {
+    assert!(import("name=Nothing\nstart\nend\n").is_err());
+    assert!(import("\n\n").is_err());
+}
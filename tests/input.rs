@@ -0,0 +1,109 @@
+use proptest::prelude::*;
+use snake::game::MoveDirection;
+use snake::input::{parse_input, InputEvent, Mouse, MouseButton};
+
+#[test]
+fn plain_keys() {
+    assert_eq!(
+        parse_input(b"hq \r"),
+        [104, 113, 32, 13].map(InputEvent::Key)
+    );
+}
+
+#[test]
+fn arrows_both_ways() {
+    let up = InputEvent::Arrow(MoveDirection::Up);
+    let left = InputEvent::Arrow(MoveDirection::Left);
+    assert_eq!(parse_input(b"\x1b[A\x1bOD"), [up, left]);
+}
+
+#[test]
+fn f3_both_ways() {
+    assert_eq!(
+        parse_input(b"\x1bOR\x1b[13~"),
+        [InputEvent::F3, InputEvent::F3]
+    );
+}
+
+#[test]
+fn unknown_sequences_are_skipped_whole() {
+    assert_eq!(
+        parse_input(b"\x1b[1;5Ck"),
+        [InputEvent::Unknown, InputEvent::Key(107)]
+    );
+}
+
+#[test]
+fn escape_on_its_own() {
+    assert_eq!(parse_input(b"\x1b"), [InputEvent::Key(0x1b)]);
+    assert_eq!(
+        parse_input(b"\x1bq"),
+        [InputEvent::Key(0x1b), InputEvent::Key(113)]
+    );
+}
+
+#[test]
+fn cut_off_sequences() {
+    assert_eq!(parse_input(b"\x1b["), [InputEvent::Unknown]);
+    assert_eq!(parse_input(b"\x1b[13"), [InputEvent::Unknown]);
+    assert_eq!(parse_input(b"\x1bO"), [InputEvent::Unknown]);
+    // A new sequence starting in the middle of one.
+    assert_eq!(
+        parse_input(b"\x1b[1\x1b[B"),
+        [InputEvent::Unknown, InputEvent::Arrow(MoveDirection::Down)]
+    );
+}
+
+#[test]
+fn mouse_presses_drags_and_releases() {
+    assert_eq!(
+        parse_input(b"\x1b[<0;10;5M\x1b[<34;11;5M\x1b[<0;11;5m"),
+        [
+            InputEvent::Mouse(Mouse::Press(MouseButton::Left, 10, 5)),
+            InputEvent::Mouse(Mouse::Drag(MouseButton::Right, 11, 5)),
+            InputEvent::Mouse(Mouse::Release(11, 5)),
+        ]
+    );
+    // Held shift doesn't matter, the wheel isn't used.
+    assert_eq!(
+        parse_input(b"\x1b[<4;1;1M\x1b[<64;1;1M"),
+        [
+            InputEvent::Mouse(Mouse::Press(MouseButton::Left, 1, 1)),
+            InputEvent::Unknown,
+        ]
+    );
+}
+
+#[test]
+fn paste_floods() {
+    let flood = "\x1b[".repeat(10_000) + &"9".repeat(10_000);
+    let events = parse_input(flood.as_bytes());
+    assert!(events.len() <= flood.len());
+}
+
+proptest! {
+    #[test]
+    fn anything_parses(input in prop::collection::vec(any::<u8>(), 0..512)) {
+        prop_assert!(parse_input(&input).len() <= input.len());
+    }
+
+    #[test]
+    fn keys_without_escapes_are_themselves(input in prop::collection::vec(0x20..0x7f_u8, 0..64)) {
+        let keys: Vec<_> = input.iter().map(|b| InputEvent::Key(*b)).collect();
+        prop_assert_eq!(parse_input(&input), keys);
+    }
+
+    #[test]
+    fn whole_sequences_dont_run_into_the_next(
+        first in prop::collection::vec(any::<u8>(), 0..64),
+        second in prop::collection::vec(0x20..0x7f_u8, 0..64),
+    ) {
+        // Whatever came before, what comes after a whole sequence is keys.
+        let mut input = first.clone();
+        input.extend(b"\x1b[A");
+        input.extend(&second);
+        let events = parse_input(&input);
+        let tail = &events[events.len() - second.len()..];
+        prop_assert!(tail.iter().zip(&second).all(|(e, b)| *e == InputEvent::Key(*b)));
+    }
+}
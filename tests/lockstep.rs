@@ -0,0 +1,78 @@
+use snake::game::{Coordinate, Game, Mode, MoveDirection};
+use snake::lockstep::{Lockstep, DELAY};
+
+// Both sides of a co-op game, with everything sent arriving before the
+// other side's next tick.
+fn play(turns: &[(u32, usize, MoveDirection)], ticks: u32) -> [Game; 2] {
+    let mut games = [
+        Game::new(Mode::Coop, 7, (20, 10)),
+        Game::new(Mode::Coop, 7, (20, 10)),
+    ];
+    let mut sides = [Lockstep::new(0, 2), Lockstep::new(1, 2)];
+
+    for _ in 0..ticks {
+        for (side, lockstep) in sides.iter_mut().enumerate() {
+            for (_, _, direction) in turns
+                .iter()
+                .filter(|(tick, snake, _)| *tick == games[side].tick && *snake == side)
+            {
+                lockstep.press(*direction);
+            }
+        }
+        let sent: Vec<_> = (0..2)
+            .map(|side| sides[side].send(games[side].tick))
+            .collect();
+        for (side, sent) in sent.into_iter().enumerate() {
+            if let Some((tick, turns)) = sent {
+                sides[1 - side].receive(side, tick, turns);
+            }
+        }
+        for (lockstep, game) in sides.iter_mut().zip(&mut games) {
+            assert_eq!(lockstep.tick(game), Some(false));
+        }
+    }
+    games
+}
+
+#[test]
+fn both_sides_play_the_same_game() {
+    let turns = [
+        (0, 0, MoveDirection::Down),
+        (1, 1, MoveDirection::Up),
+        (4, 0, MoveDirection::Left),
+    ];
+    let [host, client] = play(&turns, 8);
+    for (a, b) in host.players.iter().zip(&client.players) {
+        assert_eq!(a.segments, b.segments);
+    }
+    let locations = |game: &Game| game.food.iter().map(|f| f.location).collect::<Vec<_>>();
+    assert_eq!(locations(&host), locations(&client));
+}
+
+#[test]
+fn turns_count_delay_ticks_after_they_were_pressed() {
+    let [straight, _] = play(&[], DELAY);
+    let [turned, _] = play(&[(0, 0, MoveDirection::Down)], DELAY);
+    assert_eq!(straight.players[0].head(), turned.players[0].head());
+
+    let [turned, _] = play(&[(0, 0, MoveDirection::Down)], DELAY + 1);
+    let Coordinate(x, y) = straight.players[0].head();
+    assert_eq!(turned.players[0].head(), Coordinate(x, y + 1));
+}
+
+#[test]
+fn waits_for_the_other_side() {
+    let mut game = Game::new(Mode::Coop, 7, (20, 10));
+    let mut lockstep = Lockstep::new(0, 2);
+    for _ in 0..DELAY {
+        lockstep.send(game.tick);
+        assert_eq!(lockstep.tick(&mut game), Some(false));
+    }
+
+    lockstep.send(game.tick);
+    assert_eq!(lockstep.tick(&mut game), None);
+    assert_eq!(game.tick, DELAY);
+
+    lockstep.receive(1, DELAY, vec![]);
+    assert_eq!(lockstep.tick(&mut game), Some(false));
+}
@@ -0,0 +1,80 @@
+use snake::game::{Game, Input, Mode, MoveDirection};
+use snake::predict::{Prediction, MAX_LEAD};
+
+fn game() -> Game {
+    Game::new(Mode::Versus, 7, (20, 10))
+}
+
+// The server's side: plays `turns` on the tick it's on and says which.
+fn serve(server: &mut Game, prediction: &mut Prediction, turns: &[(usize, MoveDirection)]) -> bool {
+    for &(snake, direction) in turns {
+        server.handle_input(snake, Input::Turn(direction));
+    }
+    let tick = server.tick;
+    server.tick();
+    prediction.confirm(tick, turns)
+}
+
+#[test]
+fn shows_a_turn_before_the_server_has_it() {
+    let mut prediction = Prediction::new(game(), 0);
+    prediction.press(MoveDirection::Down);
+    assert_eq!(
+        prediction.predicted().players[0].move_direction,
+        MoveDirection::Down
+    );
+    assert_eq!(
+        prediction.confirmed.players[0].move_direction,
+        MoveDirection::Right
+    );
+}
+
+#[test]
+fn ends_up_where_the_server_does() {
+    let mut server = game();
+    let mut prediction = Prediction::new(game(), 0);
+    let tick = prediction.press(MoveDirection::Down);
+
+    while server.tick < tick {
+        serve(&mut server, &mut prediction, &[]);
+    }
+    serve(&mut server, &mut prediction, &[(0, MoveDirection::Down)]);
+    // The other snake went straight, like it was guessed to.
+    serve(&mut server, &mut prediction, &[]);
+
+    assert_eq!(
+        prediction.confirmed.players[0].segments,
+        server.players[0].segments
+    );
+    assert_eq!(prediction.lead(), 2);
+    assert_eq!(prediction.predicted().tick, server.tick + 2);
+}
+
+#[test]
+fn leads_further_after_a_late_turn() {
+    let mut server = game();
+    let mut prediction = Prediction::new(game(), 1);
+    let tick = prediction.press(MoveDirection::Up);
+
+    while server.tick < tick + 3 {
+        serve(&mut server, &mut prediction, &[]);
+    }
+    serve(&mut server, &mut prediction, &[(1, MoveDirection::Up)]);
+    assert_eq!(prediction.lead(), 2 + 3);
+
+    for direction in [MoveDirection::Left, MoveDirection::Up] {
+        let tick = prediction.press(direction);
+        while server.tick < tick + 3 {
+            serve(&mut server, &mut prediction, &[]);
+        }
+        serve(&mut server, &mut prediction, &[(1, direction)]);
+    }
+    assert_eq!(prediction.lead(), MAX_LEAD);
+}
+
+#[test]
+fn ignores_turns_for_snakes_that_are_not_there() {
+    let mut prediction = Prediction::new(game(), 0);
+    assert!(!prediction.confirm(0, &[(7, MoveDirection::Down)]));
+    assert_eq!(prediction.confirmed.tick, 1);
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::{game, InputEvent};
+use proptest::prelude::*;
+use snake::game::{Coordinate, Event, Input, MoveDirection};
+
+fn direction() -> impl Strategy<Value = MoveDirection> {
+    prop_oneof![
+        Just(MoveDirection::Up),
+        Just(MoveDirection::Down),
+        Just(MoveDirection::Left),
+        Just(MoveDirection::Right),
+    ]
+}
+
+fn input() -> impl Strategy<Value = Input> {
+    prop_oneof![
+        8 => direction().prop_map(Input::Turn),
+        1 => Just(Input::Elongate),
+    ]
+}
+
+// Up to a few inputs before each tick.
+fn inputs() -> impl Strategy<Value = Vec<InputEvent>> {
+    prop::collection::vec((0..200_u32, input()), 0..300).prop_map(|inputs| {
+        inputs
+            .into_iter()
+            .map(|(tick, input)| InputEvent {
+                tick,
+                player: 0,
+                input,
+            })
+            .collect()
+    })
+}
+
+proptest! {
+    #[test]
+    fn stepping_back_returns(x in 1..u16::MAX, y in 1..u16::MAX, direction in direction()) {
+        let cell = Coordinate(x, y);
+        prop_assert_eq!(cell.step(direction).step(direction.opposite()), cell);
+        prop_assert_eq!(cell.direction_to(cell.step(direction)), Some(direction));
+    }
+
+    #[test]
+    fn on_board_within_bounds(x in 0..20_u16, y in 0..20_u16) {
+        let on_board = (1..=10).contains(&x) && (1..=10).contains(&y);
+        prop_assert_eq!(Coordinate(x, y).on_board((10, 10)), on_board);
+    }
+
+    #[test]
+    fn no_way_back_from_off_the_top_left(n in 0..20_u16) {
+        prop_assert!(!Coordinate(n, 0).step(MoveDirection::Up).on_board((10, 10)));
+        prop_assert!(!Coordinate(0, n).step(MoveDirection::Left).on_board((10, 10)));
+    }
+
+    #[test]
+    fn snakes_stay_whole(events in inputs()) {
+        let mut game = game((20, 10));
+        common::place_food(&mut game, Coordinate(10, 5));
+
+        // Everything that makes the snake longer so far, eating or elongating.
+        let mut grown = 0;
+        for _ in 0..200 {
+            let before = game.players[0].clone();
+            let tick = game.tick;
+            for event in events.iter().filter(|e| e.tick == tick) {
+                game.handle_input(event.player, event.input);
+                grown += (event.input == Input::Elongate) as usize;
+            }
+            let direction = game.players[0].move_direction;
+            if game.tick() {
+                break;
+            }
+            grown += game
+                .events
+                .iter()
+                .filter(|e| matches!(e, Event::FoodEaten { .. } | Event::FormationFoodEaten { .. }))
+                .count();
+
+            let snake = &game.players[0];
+            let cells: std::collections::HashSet<_> = snake.segments.iter().collect();
+            prop_assert_eq!(cells.len(), snake.segments.len(), "the snake overlaps itself");
+            prop_assert!(snake.segments.iter().all(|s| s.on_board(game.board_size)));
+            prop_assert_eq!(snake.head(), before.head().step(direction));
+            prop_assert_ne!(direction, before.move_direction.opposite());
+
+            let length = snake.segments.len();
+            prop_assert!(length == before.segments.len() || length == before.segments.len() + 1);
+            prop_assert!(length <= 4 + grown, "grew without eating or elongating");
+        }
+    }
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use common::{game, place_food, play, turn};
+use snake::game::{Coordinate, MoveDirection};
+use snake::reactions::{Feedback, Reactions, SLOW_MOTION_SCALE};
+
+const USUAL: std::time::Duration = std::time::Duration::from_millis(150);
+
+// The time scale after the snake gets to the right edge of the board and
+// turns away just in time, when moves take `move_duration`.
+fn after_near_miss(mut reactions: Reactions, move_duration: std::time::Duration) -> u32 {
+    reactions.set_move_duration(move_duration);
+    let mut game = game((20, 10));
+    assert_eq!(play(&mut game, &[], 16), None);
+    assert!(game.crash_ahead(0));
+    reactions.handle(&game);
+    assert_eq!(play(&mut game, &[turn(16, MoveDirection::Down)], 1), None);
+    reactions.handle(&game);
+    reactions.time_scale()
+}
+
+#[test]
+fn near_misses_slow_down_fast_games() {
+    let reactions = || Reactions::new(Feedback::Bell, false, true, USUAL);
+    let faster = USUAL / 2;
+    assert_eq!(after_near_miss(reactions(), faster), SLOW_MOTION_SCALE);
+    assert_eq!(after_near_miss(reactions(), USUAL), 1);
+}
+
+#[test]
+fn reduced_motion_never_flashes_or_slows() {
+    let reactions = || Reactions::new(Feedback::Flash, true, true, USUAL);
+    assert_eq!(after_near_miss(reactions(), USUAL / 2), 1);
+
+    let mut game = game((20, 10));
+    place_food(&mut game, Coordinate(5, 1));
+    assert_eq!(play(&mut game, &[], 1), None);
+    assert_eq!(game.players[0].score, 1);
+    assert_eq!(reactions().handle(&game), None);
+    assert_eq!(
+        Reactions::new(Feedback::Flash, false, true, USUAL).handle(&game),
+        Some(Feedback::Flash)
+    );
+}
@@ -0,0 +1,131 @@
+use snake::game::{Game, Input, Mode, MoveDirection};
+use snake::replay::{Replay, Verification};
+
+fn replay() -> Replay {
+    let mut replay = Replay::new(&Game::new(Mode::Classic, 3, (30, 12)));
+    replay.record(2, Input::Turn(MoveDirection::Down));
+    replay.length = 20;
+    let end = replay.simulate();
+    replay.seal(&end);
+    replay
+}
+
+// Recorded the way games are, with a checkpoint after every tick that gets
+// one.
+fn played(turns: &[(u32, MoveDirection)], ticks: u32) -> Replay {
+    let mut game = Game::new(Mode::Classic, 9, (100, 60));
+    let mut replay = Replay::new(&game);
+    while game.tick < ticks {
+        let tick = game.tick;
+        for (_, direction) in turns.iter().filter(|(t, _)| *t == tick) {
+            game.handle_input(0, Input::Turn(*direction));
+            replay.record(game.tick, Input::Turn(*direction));
+        }
+        if game.tick() {
+            break;
+        }
+        replay.checkpoint(&game);
+    }
+    replay.seal(&game);
+
+    let mut data = Vec::new();
+    replay.write_to(&mut data).unwrap();
+    Replay::read_from(&mut &data[..]).ok().unwrap()
+}
+
+#[test]
+fn keeps_diagonal_moves() {
+    let mut game = Game::new(Mode::Classic, 3, (30, 12));
+    game.diagonal = true;
+    let mut replay = Replay::new(&game);
+    replay.record(2, Input::Turn(MoveDirection::DownRight));
+    replay.length = 5;
+
+    let mut data = Vec::new();
+    replay.write_to(&mut data).unwrap();
+    let loaded = Replay::read_from(&mut &data[..]).ok().unwrap();
+    assert!(loaded.diagonal && !loaded.bumper);
+    assert_eq!(loaded.inputs, replay.inputs);
+    assert_eq!(
+        loaded.simulate().players[0].head(),
+        replay.simulate().players[0].head()
+    );
+}
+
+#[test]
+fn keeps_the_wind() {
+    let mut game = Game::new(Mode::Classic, 3, (30, 12));
+    game.set_wind(true);
+    let mut replay = Replay::new(&game);
+    replay.length = 8;
+
+    let mut data = Vec::new();
+    replay.write_to(&mut data).unwrap();
+    let loaded = Replay::read_from(&mut &data[..]).ok().unwrap();
+    assert!(loaded.wind && !loaded.diagonal && !loaded.bumper);
+    assert_eq!(
+        loaded.simulate().players[0].head(),
+        replay.simulate().players[0].head()
+    );
+}
+
+#[test]
+fn keeps_the_starting_speed() {
+    let mut replay = replay();
+    replay.tick_ms = Some(90);
+
+    let mut data = Vec::new();
+    replay.write_to(&mut data).unwrap();
+    let loaded = Replay::read_from(&mut &data[..]).ok().unwrap();
+    assert_eq!(loaded.tick_ms, Some(90));
+    assert_eq!(
+        Replay::new(&Game::new(Mode::Classic, 3, (30, 12))).tick_ms,
+        None
+    );
+}
+
+#[test]
+fn loads_compressed_and_uncompressed_files() {
+    let directory = std::env::temp_dir().join(format!("snake-replays-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let replay = replay();
+
+    let compressed = directory.join("compressed.replay");
+    replay.save(&compressed).unwrap();
+    assert!(std::fs::read(&compressed)
+        .unwrap()
+        .starts_with(&[0x1f, 0x8b]));
+
+    // As replays were saved before they were compressed.
+    let uncompressed = directory.join("uncompressed.replay");
+    let mut data = Vec::new();
+    replay.write_to(&mut data).unwrap();
+    std::fs::write(&uncompressed, data).unwrap();
+
+    for path in [&compressed, &uncompressed] {
+        let loaded = Replay::load(path).ok().unwrap();
+        assert_eq!(loaded.hash(), replay.hash());
+        assert_eq!(loaded.ended_as_recorded(&loaded.simulate()), Some(true));
+    }
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn verifies_a_recorded_game() {
+    let replay = played(&[(10, MoveDirection::Down), (14, MoveDirection::Right)], 60);
+    assert_eq!(replay.checkpoints.len(), 3);
+    assert!(matches!(replay.verify(), Verification::Verified(_)));
+}
+
+#[test]
+fn finds_the_first_checkpoint_that_differs() {
+    let mut replay = played(&[(10, MoveDirection::Down)], 60);
+    // Turned after the first checkpoint, without that being recorded.
+    replay.inputs.push((30, Input::Turn(MoveDirection::Right)));
+
+    let Verification::Diverged { recorded, played } = replay.verify() else {
+        panic!("played back the same");
+    };
+    assert_eq!(recorded.map(|c| c.tick), Some(50));
+    assert_eq!(played.tick, 50);
+}
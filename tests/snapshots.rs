@@ -0,0 +1,207 @@
+// Plays known games back through `snake export` and compares frames with
+// the ones in tests/snapshots, drawn as text. Cells with a background color
+// are drawn as a letter for the color, listed under the frame. To accept
+// frames that changed on purpose, run with UPDATE_SNAPSHOTS=1 and look at the
+// diff.
+
+use snake::game::{Game, Input, Mode, MoveDirection};
+use snake::replay::Replay;
+
+// The terminal the frames are drawn on, as big as the board.
+struct Screen {
+    width: usize,
+    cells: Vec<Vec<(char, Option<String>)>>,
+    cursor: (usize, usize),
+    background: Option<String>,
+}
+
+impl Screen {
+    fn new(width: usize, height: usize) -> Self {
+        Screen {
+            width,
+            cells: vec![vec![(' ', None); width]; height],
+            cursor: (0, 0),
+            background: None,
+        }
+    }
+
+    // Understands the escape sequences the game writes: moving the cursor,
+    // clearing and colors.
+    fn write(&mut self, output: &str) {
+        let mut chars = output.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '\x1b' {
+                let (x, y) = self.cursor;
+                if let Some(cell) = self.cells.get_mut(y).and_then(|row| row.get_mut(x)) {
+                    *cell = (c, self.background.clone());
+                }
+                self.cursor.0 += 1;
+                continue;
+            }
+
+            assert_eq!(chars.next(), Some('['), "unexpected escape in {:?}", output);
+            let mut parameters = String::new();
+            let last = loop {
+                match chars.next() {
+                    Some(c @ ('0'..='9' | ';' | '?')) => parameters.push(c),
+                    Some(c) => break c,
+                    None => panic!("cut off escape in {:?}", output),
+                }
+            };
+            let numbers: Vec<usize> = parameters
+                .split(';')
+                .map(|n| n.trim_start_matches('?').parse().unwrap_or(0))
+                .collect();
+
+            match last {
+                // Terminals take 0 for 1.
+                'H' => {
+                    self.cursor = (
+                        numbers.get(1).copied().unwrap_or(1).max(1) - 1,
+                        numbers[0].max(1) - 1,
+                    )
+                }
+                'J' => self.cells = vec![vec![(' ', None); self.width]; self.cells.len()],
+                'm' => match numbers.as_slice() {
+                    [48, 2, r, g, b] => self.background = Some(format!("{},{},{}", r, g, b)),
+                    [49] | [0] => self.background = None,
+                    _ => {}
+                },
+                'h' | 'l' => {}
+                _ => panic!("unexpected escape {}{} in {:?}", parameters, last, output),
+            }
+        }
+    }
+
+    fn text(&self) -> String {
+        let mut colors: Vec<String> = Vec::new();
+        let mut text = String::new();
+        for row in &self.cells {
+            let line: String = row
+                .iter()
+                .map(|(c, color)| match color {
+                    Some(color) if *c == ' ' => {
+                        let index = match colors.iter().position(|c| c == color) {
+                            Some(index) => index,
+                            None => {
+                                colors.push(color.clone());
+                                colors.len() - 1
+                            }
+                        };
+                        (b'A' + index as u8) as char
+                    }
+                    _ => *c,
+                })
+                .collect();
+            text += line.trim_end();
+            text += "\n";
+        }
+        for (index, color) in colors.iter().enumerate() {
+            text += &format!("{}: {}\n", (b'A' + index as u8) as char, color);
+        }
+        text
+    }
+}
+
+// Every frame of `replay`, as exported to a cast.
+fn frames(replay: &Replay) -> Vec<String> {
+    let directory = std::env::temp_dir().join(format!("snake-snapshots-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join(format!("{}.replay", replay.hash()));
+    let cast = path.with_extension("cast");
+    replay.save(&path).unwrap();
+
+    let status = std::process::Command::new(env!("CARGO_BIN_EXE_snake"))
+        .arg("export")
+        .arg(&path)
+        .arg(&cast)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let lines = std::fs::read_to_string(&cast).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(&cast).unwrap();
+    let mut lines = lines.lines();
+    let header: serde_json::Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+    let (width, height) = (
+        header["width"].as_u64().unwrap() as usize,
+        header["height"].as_u64().unwrap() as usize,
+    );
+
+    let mut screen = Screen::new(width, height);
+    lines
+        .map(|line| {
+            let event: serde_json::Value = serde_json::from_str(line).unwrap();
+            screen.write(event[2].as_str().unwrap());
+            screen.text()
+        })
+        .collect()
+}
+
+fn replay(mode: Mode, seed: u64, inputs: &[(u32, Input)], length: u32) -> Replay {
+    let game = Game::new(mode, seed, (30, 12));
+    let mut replay = Replay::new(&game);
+    for (tick, input) in inputs {
+        replay.record(*tick, *input);
+    }
+    replay.length = length;
+    let end = replay.simulate();
+    replay.seal(&end);
+    replay
+}
+
+fn assert_snapshot(name: &str, frame: &str) {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{}.txt", name));
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::write(&path, frame).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("no {}, run with UPDATE_SNAPSHOTS=1", path.display()));
+    assert!(
+        expected == frame,
+        "{} changed, expected:\n{}\ngot:\n{}",
+        name,
+        expected,
+        frame
+    );
+}
+
+#[test]
+fn classic_start() {
+    let frames = frames(&replay(Mode::Classic, 1, &[], 1));
+    assert_snapshot("classic_start", &frames[0]);
+}
+
+#[test]
+fn classic_turning() {
+    let inputs = [
+        (3, Input::Turn(MoveDirection::Down)),
+        (6, Input::Turn(MoveDirection::Left)),
+        (6, Input::Elongate),
+    ];
+    let frames = frames(&replay(Mode::Classic, 1, &inputs, 10));
+    assert_snapshot("classic_turning", &frames[9]);
+}
+
+#[test]
+fn classic_crash() {
+    let frames = frames(&replay(Mode::Classic, 1, &[], 100));
+    assert_snapshot("classic_crash", frames.last().unwrap());
+}
+
+#[test]
+fn versus_start() {
+    let frames = frames(&replay(Mode::Versus, 2, &[], 1));
+    assert_snapshot("versus_start", &frames[0]);
+}
+
+#[test]
+fn twins_start() {
+    let frames = frames(&replay(Mode::Twins, 3, &[], 1));
+    assert_snapshot("twins_start", &frames[0]);
+}
@@ -0,0 +1,103 @@
+// The game as a wasm module for index.html. It draws with the same escape
+// codes as the terminal version, so xterm.js can show its output as is.
+
+use snake::game::{self, Coordinate, Input, Mode, MoveDirection, Player};
+use std::fmt::Write;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub struct Game {
+    game: game::Game,
+    quit: bool,
+}
+
+#[wasm_bindgen]
+impl Game {
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: u16, height: u16) -> Game {
+        Game {
+            game: game::Game::new(Mode::Classic, rand::random(), (width, height)),
+            quit: false,
+        }
+    }
+
+    // Takes whatever xterm.js says was typed, with the same keys as in the
+    // terminal.
+    pub fn input(&mut self, data: &str) {
+        for b in data.bytes() {
+            let input = match b {
+                113 => {
+                    self.quit = true;
+                    continue;
+                }
+                97 => Input::Elongate,
+                104 => Input::Turn(MoveDirection::Left),
+                107 => Input::Turn(MoveDirection::Up),
+                106 => Input::Turn(MoveDirection::Down),
+                108 => Input::Turn(MoveDirection::Right),
+                _ => continue,
+            };
+
+            self.game.handle_input(0, input);
+        }
+    }
+
+    // Returns true once the game is over.
+    pub fn tick(&mut self) -> bool {
+        self.quit || self.game.tick()
+    }
+
+    pub fn score(&self) -> u32 {
+        self.game.players[0].score
+    }
+
+    pub fn render(&self) -> String {
+        let mut screen = String::from("\x1b[H\x1b[2J");
+
+        for Coordinate(x, y) in self.game.food.iter().flat_map(|f| f.cells()) {
+            write!(
+                screen,
+                "\x1b[{};{}H\x1b[48;2;255;0;0m\x1b[38;2;0;0;0m'\x1b[49m\x1b[39m",
+                y, x
+            )
+            .unwrap();
+        }
+        for (index, player) in self.game.players.iter().enumerate() {
+            match index {
+                0 => render_snake(&mut screen, player, (0, 255, 0), (255, 255, 255)),
+                _ => render_snake(&mut screen, player, (0, 128, 255), (128, 200, 255)),
+            }
+        }
+
+        write!(
+            screen,
+            "\x1b[{};2H Score: {} ",
+            self.game.board_size.1,
+            self.score()
+        )
+        .unwrap();
+
+        screen
+    }
+}
+
+fn render_snake(
+    screen: &mut String,
+    player: &Player,
+    head_color: (u8, u8, u8),
+    body_color: (u8, u8, u8),
+) {
+    for (index, Coordinate(x, y)) in player.segments.iter().enumerate() {
+        let (r, g, b) = match index {
+            0 => head_color,
+            _ => body_color,
+        };
+
+        write!(
+            screen,
+            "\x1b[{};{}H\x1b[48;2;{};{};{}m \x1b[49m",
+            y, x, r, g, b
+        )
+        .unwrap();
+    }
+}